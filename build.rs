@@ -14,7 +14,9 @@ fn main() {
 
     let target = env::var("TARGET").unwrap();
 
-    if is_unix(&target) {
+    //Software scheduler doesn't use POSIX timers.
+    if is_unix(&target) && env::var_os("CARGO_FEATURE_SOFTWARE").is_none() {
+        println!("cargo:rerun-if-changed=src/timer/posix.c");
         cc::Build::new().file("src/timer/posix.c").compile("libos-timer-posix-c.a");
     }
 }
@@ -4,12 +4,51 @@
 //!
 //! - Posix timer requires compilation of C shim (i.e. Correct C compiler must be available when
 //! compiling for posix target).
+//!
+//! # Platform specific API
+//!
+//! `Timer` and `Callback` expose the same set of methods on all platforms, with following extensions,
+//! that only make sense for particular OS API:
+//!
+//! - POSIX: `Timer::callback_thread_id`, `Timer::new_with_clock`, `Timer::init_with_clock`, `Timer::new_signal`, `Timer::schedule_interval_returning`, `Timer::with_name` (Linux), `Timer::with_delivery` (Linux).
+//! - Windows: `Timer::new_with_priority`, `Timer::priority`, `Timer::new_in_pool` with `ThreadPool`, `Timer::schedule_interval_with_window`, `Timer::schedule_interval_was_set`, `Timer::cancel_async`.
+//! - Mac: `Timer::new_with_qos`, `Timer::init_with_qos`, `Timer::new_on_queue` with `DispatchQueue`, `Timer::quality_of_service`, `Timer::schedule_interval_with_clock`, `Timer::from_raw` (with callback), `Timer::cancel_sync`.
+//!
+//! `Timer::pause` and `Timer::resume` are available everywhere, but with different fidelity: POSIX preserves exact
+//! time until the next alarm, Windows approximates it, while Mac keeps schedule running during pause.
+//!
+//! On other targets with `std` (or with `software` feature) timers are driven by software scheduler: single
+//! background thread, that sleeps until the nearest due time. It is best-effort, as its resolution is as coarse as
+//! thread sleep, and long running callback delays all other timers, but it exposes the same API.
+//!
+//! Portable code can instead use `TimerBuilder`, which ignores options, not applicable to the current platform.
+//!
+//! # Sharing state with callback
+//!
+//! Callback is invoked on OS thread, hence state, it shares with the rest of the program, must be `Send + Sync`.
+//! Instead of passing raw pointers via `Callback::raw`, use `Callback::arc`, which keeps clone of `Arc`
+//! alive for as long as timer exists, or move clone of `Arc` into `Callback::closure`.
+//!
+//! # Features
+//!
+//! - `std` - Enables APIs that depend on `std`, like `Instant` based scheduling. Enabled by default. Implies `alloc`.
+//! - `alloc` - Enables callbacks, that store closure on heap (`Callback::closure`, `Callback::with_count` and others).
+//! Without it only `Callback::plain`, `Callback::unsafe_plain`, `Callback::inline` and `Callback::raw` are available.
+//! Timer's state is still allocated on heap, hence global allocator is required regardless.
+//! - `futures` - Enables async wrappers over timer: `TimerFuture` and `TimerStream` (see `MissedTickBehavior`). Implies `std`.
+//! - `timerfd` - Enables `DeliveryMode::TimerFd` on Linux, backing timer with pollable file descriptor.
+//! - `software` - Uses software scheduler instead of OS timers on every platform. Implies `std`.
 
 #![no_std]
 #![warn(missing_docs)]
-#![cfg_attr(feature = "cargo-clippy", allow(clippy::style))]
+#![allow(clippy::style)]
+//Owner of closure is dropped explicitly to free it, while without `alloc` it is mere placeholder.
+#![cfg_attr(not(feature = "alloc"), allow(clippy::drop_non_drop))]
+
+#[cfg(feature = "std")]
+extern crate std;
 
-#[cfg(any(windows, unix))]
+#[cfg(any(windows, unix, feature = "std"))]
 mod timer;
-#[cfg(any(windows, unix))]
+#[cfg(any(windows, unix, feature = "std"))]
 pub use timer::*;
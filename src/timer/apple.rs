@@ -1,7 +1,11 @@
-use core::{time, mem, ptr};
-use core::cell::Cell;
-use core::sync::atomic::{AtomicPtr, AtomicBool, Ordering};
-use super::BoxFnPtr;
+use core::{fmt, time, mem, ptr};
+use core::cell::{Cell, UnsafeCell};
+use core::sync::atomic::{AtomicPtr, AtomicBool, AtomicU64, Ordering};
+use super::{Armed, BoxFnPtr, Capabilities, CatchUp, Clock, Lifecycle, Pacing, QosClass, Running, TimerBuilder, TimerError, TimerState};
+#[cfg(feature = "alloc")]
+use super::BoxedCallback;
+#[cfg(feature = "std")]
+use super::{Deadline, Latency, Waiters};
 
 extern crate alloc;
 use alloc::boxed::Box;
@@ -12,31 +16,43 @@ mod ffi {
     type uintptr_t = usize;
     type c_long = i64;
     type c_ulong = u64;
+    type c_char = i8;
     pub type Callback = unsafe extern "C" fn(*mut c_void);
 
     pub type dispatch_object_t = *const c_void;
     pub type dispatch_queue_t = *const c_void;
+    pub type dispatch_queue_attr_t = *const c_void;
     pub type dispatch_source_t = *const c_void;
     pub type dispatch_source_type_t = *const c_void;
     pub type dispatch_time_t = u64;
 
+    pub const DISPATCH_QUEUE_SERIAL: dispatch_queue_attr_t = core::ptr::null();
+    pub const DISPATCH_TIME_NOW: dispatch_time_t = 0;
     pub const DISPATCH_TIME_FOREVER: dispatch_time_t = !0;
     //pub const DISPATCH_WALLTIME_NOW: dispatch_time_t = !1;
+    pub const QOS_CLASS_USER_INTERACTIVE: c_long = 0x21;
+    pub const QOS_CLASS_USER_INITIATED: c_long = 0x19;
     pub const QOS_CLASS_DEFAULT: c_long = 0x15;
+    pub const QOS_CLASS_UTILITY: c_long = 0x11;
+    pub const QOS_CLASS_BACKGROUND: c_long = 0x09;
 
     extern "C" {
         pub static _dispatch_source_type_timer: c_long;
 
         pub fn dispatch_get_global_queue(identifier: c_long, flags: c_ulong) -> dispatch_queue_t;
+        pub fn dispatch_queue_create(label: *const c_char, attr: dispatch_queue_attr_t) -> dispatch_queue_t;
         pub fn dispatch_source_create(type_: dispatch_source_type_t, handle: uintptr_t, mask: c_ulong, queue: dispatch_queue_t) -> dispatch_source_t;
         pub fn dispatch_source_set_timer(source: dispatch_source_t, start: dispatch_time_t, interval: u64, leeway: u64);
         pub fn dispatch_source_set_event_handler_f(source: dispatch_source_t, handler: Callback);
+        pub fn dispatch_source_set_cancel_handler_f(source: dispatch_source_t, handler: Callback);
         pub fn dispatch_set_context(object: dispatch_object_t, context: *mut c_void);
         pub fn dispatch_resume(object: dispatch_object_t);
         pub fn dispatch_suspend(object: dispatch_object_t);
         pub fn dispatch_release(object: dispatch_object_t);
         pub fn dispatch_source_cancel(object: dispatch_object_t);
+        pub fn dispatch_source_get_data(source: dispatch_source_t) -> c_ulong;
         pub fn dispatch_walltime(when: *const c_void, delta: i64) -> dispatch_time_t;
+        pub fn dispatch_time(when: dispatch_time_t, delta: i64) -> dispatch_time_t;
     }
 }
 
@@ -56,6 +72,7 @@ unsafe extern "C" fn timer_callback_unsafe(data: *mut ffi::c_void) {
     }
 }
 
+#[cfg(feature = "alloc")]
 unsafe extern "C" fn timer_callback_generic<T: FnMut() -> ()>(data: *mut ffi::c_void) {
     if !data.is_null() {
         let cb = &mut *(data as *mut T);
@@ -64,9 +81,157 @@ unsafe extern "C" fn timer_callback_generic<T: FnMut() -> ()>(data: *mut ffi::c_
     }
 }
 
+#[cfg(feature = "alloc")]
+unsafe extern "C" fn timer_callback_boxed(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        super::BoxFnPtr::invoke(data as *mut ());
+    }
+}
+
+unsafe extern "C" fn timer_callback_inline<T: FnMut() -> () + Copy>(data: *mut ffi::c_void) {
+    let mut cb = super::InlineClosure::<T>::unpack(data as *mut ());
+
+    (cb)();
+}
+
+unsafe extern "C" fn timer_callback_context(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        let ctx = &*(data as *const Context);
+
+        ctx.fire();
+    }
+}
+
+//Cancel handler of dispatch source, that frees context once source is done with it.
+//
+//Dispatch invokes it only after event handler, that is in flight, has returned, and never invokes event handler afterwards.
+unsafe extern "C" fn timer_cancel_context(data: *mut ffi::c_void) {
+    if !data.is_null() {
+        let ctx = Box::from_raw(data as *mut Context);
+        let done = (*ctx.retired.get()).as_ref().map(|retired| retired.done);
+
+        //Closure, handed over by dropped timer, is freed together with context.
+        drop(ctx);
+        if let Some(done) = done {
+            (*done).store(true, Ordering::Release);
+        }
+    }
+}
+
+impl QosClass {
+    #[inline(always)]
+    fn identifier(self) -> i64 {
+        match self {
+            QosClass::UserInteractive => ffi::QOS_CLASS_USER_INTERACTIVE,
+            QosClass::UserInitiated => ffi::QOS_CLASS_USER_INITIATED,
+            QosClass::Default => ffi::QOS_CLASS_DEFAULT,
+            QosClass::Utility => ffi::QOS_CLASS_UTILITY,
+            QosClass::Background => ffi::QOS_CLASS_BACKGROUND,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Base of dispatch time, relatively to which timer is scheduled.
+pub enum ClockBase {
+    ///`dispatch_time`, based on `mach_absolute_time`, which is not affected by changes of system time, but stops while system sleeps.
+    ///
+    ///Used by periodic timers, so that adjustments of system time do not skew their cadence.
+    Monotonic,
+    ///`dispatch_walltime`, which is system time and keeps counting while system sleeps, but jumps when system time is changed.
+    ///
+    ///Used by one-shot timers.
+    Wall,
+}
+
+impl ClockBase {
+    #[inline(always)]
+    //Returns clock base, used by default for timer with specified `interval`.
+    fn for_interval(interval: time::Duration) -> Self {
+        match interval.as_nanos() {
+            0 => ClockBase::Wall,
+            _ => ClockBase::Monotonic,
+        }
+    }
+
+    #[inline(always)]
+    fn start(self, timeout: time::Duration) -> ffi::dispatch_time_t {
+        let delta = core::cmp::min(timeout.as_nanos(), i64::max_value() as u128) as i64;
+        unsafe {
+            match self {
+                ClockBase::Monotonic => ffi::dispatch_time(ffi::DISPATCH_TIME_NOW, delta),
+                ClockBase::Wall => ffi::dispatch_walltime(ptr::null(), delta),
+            }
+        }
+    }
+}
+
+///Serial dispatch queue, that runs callbacks of timers created on it one at a time.
+///
+///By default each timer targets global concurrent queue; timers, sharing serial queue, never run
+///their callbacks concurrently, which bounds concurrency and preserves order of their alarms.
+///
+///Queue can be dropped while its timers are alive, as dispatch sources retain their target queue.
+///
+///Dropping timer waits for its source to be cancelled on target queue, hence timer must not be dropped
+///from callback, running on the same serial queue, as it would deadlock.
+pub struct DispatchQueue {
+    handle: ffi::dispatch_queue_t,
+}
+
+unsafe impl Send for DispatchQueue {}
+unsafe impl Sync for DispatchQueue {}
+
+impl DispatchQueue {
+    ///Creates new serial queue.
+    ///
+    ///Returns `None` if OS fails to create queue.
+    pub fn serial() -> Option<Self> {
+        let handle = unsafe {
+            ffi::dispatch_queue_create(ptr::null(), ffi::DISPATCH_QUEUE_SERIAL)
+        };
+
+        match handle.is_null() {
+            true => None,
+            false => Some(Self {
+                handle,
+            }),
+        }
+    }
+}
+
+impl Drop for DispatchQueue {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::dispatch_release(self.handle);
+        }
+    }
+}
+
+impl fmt::Debug for DispatchQueue {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("DispatchQueue").field(&self.handle).finish()
+    }
+}
+
+#[cfg(feature = "alloc")]
+type CountedCallback = Box<dyn FnMut(&Timer, u64) + Send>;
+#[cfg(not(feature = "alloc"))]
+//Placeholder, as such callback cannot be created without heap, hence it is never set.
+type CountedCallback = fn(&Timer, u64);
+
+///Raw handle of OS timer: `dispatch_source_t`.
+pub type RawTimer = *mut ffi::c_void;
+
 enum CallbackVariant {
     Trivial(*mut ffi::c_void),
-    Boxed(Box<dyn FnMut()>),
+    #[cfg(feature = "alloc")]
+    Boxed(BoxedCallback),
+    //Closure, boxed by user, which is owned by context and invoked through fat pointer.
+    #[cfg(feature = "alloc")]
+    Dyn(BoxedCallback),
+    #[cfg(feature = "alloc")]
+    Counted(CountedCallback),
 }
 
 ///Timer's callback abstraction
@@ -79,7 +244,7 @@ impl Callback {
     ///Creates raw callback for platform timer.
     ///
     ///Signature depends on platform.
-    pub unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
+    pub const unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
         Self {
             variant: CallbackVariant::Trivial(data),
             ffi_cb,
@@ -87,7 +252,7 @@ impl Callback {
     }
 
     ///Creates callback using plain rust function
-    pub fn plain(cb: fn()) -> Self {
+    pub const fn plain(cb: fn()) -> Self {
         Self {
             variant: CallbackVariant::Trivial(cb as _),
             ffi_cb: timer_callback,
@@ -95,28 +260,384 @@ impl Callback {
     }
 
     ///Creates callback using plain unsafe function
-    pub fn unsafe_plain(cb: unsafe fn()) -> Self {
+    pub const fn unsafe_plain(cb: unsafe fn()) -> Self {
         Self {
             variant: CallbackVariant::Trivial(cb as _),
             ffi_cb: timer_callback_unsafe,
         }
     }
 
+    #[cfg(feature = "alloc")]
     ///Creates callback using closure, storing it on heap.
-    pub fn closure<F: 'static + FnMut()>(cb: F) -> Self {
+    pub fn closure<F: 'static + FnMut() + Send>(cb: F) -> Self {
         Self {
             variant: CallbackVariant::Boxed(Box::new(cb)),
             ffi_cb: timer_callback_generic::<F>,
         }
     }
+
+    ///Creates callback using small closure, stored in place of function's data instead of heap.
+    ///
+    ///Closure must fit into pointer (i.e. capture nothing or single reference), which is checked at compile time.
+    ///Being `Copy`, closure is copied for each run, hence changes to its captured state are not preserved between runs.
+    pub fn inline<F: 'static + FnMut() + Copy + Send>(cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(super::InlineClosure::pack(cb) as *mut ffi::c_void),
+            ffi_cb: timer_callback_inline::<F>,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    ///Creates callback using already boxed closure.
+    ///
+    ///Closure is stored as it is, without boxing it again.
+    pub fn boxed(cb: BoxedCallback) -> Self {
+        Self {
+            variant: CallbackVariant::Dyn(cb),
+            ffi_cb: timer_callback_boxed,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives number of intervals elapsed since its previous run.
+    ///
+    ///On Mac count is exact, as dispatch reports number of expirations, merged into single run of handler.
+    ///It is always 1 for one shot timer.
+    ///
+    ///Callback is run once per expiration, regardless of `CatchUp` policy.
+    pub fn with_elapsed<F: 'static + FnMut(u32) + Send>(mut cb: F) -> Self {
+        Self::with_count(move |count| cb(core::cmp::min(count, u32::max_value().into()) as u32))
+    }
+
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives number of expirations since its previous run.
+    ///
+    ///Same as `with_elapsed`, but without saturating count to `u32`.
+    pub fn with_count<F: 'static + FnMut(u64) + Send>(mut cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Counted(Box::new(move |_: &Timer, count| cb(count))),
+            ffi_cb: timer_callback,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives reference to the timer, which invokes it.
+    ///
+    ///It allows callback to re-schedule (or cancel) its own timer, e.g. to implement backoff.
+    ///
+    ///Callback receives a view of the timer, sharing its OS timer and context, rather than timer itself,
+    ///as `Timer` may be moved after creation. It is sound, because timer outlives its callbacks: on drop it
+    ///stops invoking callbacks, waits for ones in flight, and only then cancels and deletes OS timer.
+    ///
+    ///Callback is run once per expiration, regardless of `CatchUp` policy.
+    pub fn with_timer<F: 'static + FnMut(&Timer) + Send>(mut cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Counted(Box::new(move |timer: &Timer, _| cb(timer))),
+            ffi_cb: timer_callback,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that keeps periodic timer running as long as it returns `true`.
+    ///
+    ///Once closure returns `false`, timer cancels itself right after that invocation, until it is scheduled again.
+    pub fn repeating<F: 'static + FnMut() -> bool + Send>(mut cb: F) -> Self {
+        Self::with_timer(move |timer| {
+            if !cb() {
+                timer.cancel();
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Callback {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //Closure is opaque and function pointer is meaningless to user, hence only kind of callback is printed.
+        let variant = match self.variant {
+            CallbackVariant::Trivial(_) => "Trivial",
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Boxed(_) | CallbackVariant::Dyn(_) => "Boxed",
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Counted(_) => "Counted",
+        };
+        fmt.debug_struct("Callback").field("variant", &format_args!("{}", variant)).finish()
+    }
+}
+
+impl Callback {
+    fn into_handler(self) -> (BoxFnPtr, Handler) {
+        let mut handler = Handler {
+            ffi_cb: self.ffi_cb,
+            ffi_data: ptr::null_mut(),
+            boxed: BoxFnPtr::new(),
+            counted: None,
+        };
+        let data = match self.variant {
+            CallbackVariant::Trivial(data) => {
+                handler.ffi_data = data;
+                BoxFnPtr::new()
+            },
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Boxed(cb) => {
+                let data = BoxFnPtr::from_box(cb);
+                handler.ffi_data = data.0 as *mut ffi::c_void;
+                data
+            },
+            //Data is pointed to `boxed`, once handler is at its place (see `Handler::bind_boxed`).
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Dyn(cb) => {
+                handler.boxed = BoxFnPtr::from_box(cb);
+                BoxFnPtr::new()
+            },
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Counted(cb) => {
+                super::closure_stored();
+                handler.counted = Some(cb);
+                BoxFnPtr::new()
+            },
+        };
+
+        (data, handler)
+    }
+
+    fn into_context(self, handle: ffi::dispatch_source_t) -> (BoxFnPtr, *mut Context) {
+        let (data, handler) = self.into_handler();
+
+        let ctx = Box::into_raw(Box::new(Context {
+            handler: UnsafeCell::new(handler),
+            handle,
+            qos: None,
+            leeway: None,
+            //Note timer is created suspended.
+            suspend: AtomicBool::new(true),
+            state: Lifecycle::new(),
+            armed: Armed::new(),
+            fires: AtomicU64::new(0),
+            running: Running::new(),
+            pacing: Pacing::new(),
+            #[cfg(feature = "std")]
+            deadline: Deadline::new(),
+            #[cfg(feature = "std")]
+            latency: Latency::new(),
+            #[cfg(feature = "std")]
+            waiters: Waiters::new(),
+            retired: UnsafeCell::new(None),
+        }));
+        unsafe {
+            Handler::bind_boxed((*ctx).handler.get());
+        }
+        (data, ctx)
+    }
+}
+
+//Callback, that context invokes on expiration.
+struct Handler {
+    ffi_cb: ffi::Callback,
+    ffi_data: *mut ffi::c_void,
+    //Closure of `Callback::boxed`, which `ffi_data` points to.
+    boxed: BoxFnPtr,
+    //Callback, that receives number of expirations, used instead of `ffi_cb`.
+    counted: Option<CountedCallback>,
+}
+
+impl Handler {
+    //Points `ffi_data` to `boxed`, if callback is created by `Callback::boxed`.
+    //
+    //As handler refers to itself then, it must be called once handler is at its final place.
+    unsafe fn bind_boxed(this: *mut Self) {
+        if !(*this).boxed.is_null() {
+            (*this).ffi_data = ptr::addr_of_mut!((*this).boxed) as *mut ffi::c_void;
+        }
+    }
+}
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        if self.counted.take().is_some() {
+            super::closure_freed();
+        }
+    }
+}
+
+//Timer's user data, through which OS invokes actual callback.
+struct Context {
+    //Callbacks access it only once they enter `running` and wait out swap, hence `set_callback` replaces it
+    //between `begin_swap` and `end_swap` of `running`, even though context is shared.
+    handler: UnsafeCell<Handler>,
+    handle: ffi::dispatch_source_t,
+    //Explicitly requested QoS class, if any.
+    qos: Option<QosClass>,
+    //Leeway, used when schedule doesn't specify it.
+    leeway: Option<time::Duration>,
+    //Suspension count. Incremented on suspend, and decremented on each resume
+    suspend: AtomicBool,
+    state: Lifecycle,
+    armed: Armed,
+    //Number of expirations, that reached user's callback.
+    fires: AtomicU64,
+    running: Running,
+    pacing: Pacing,
+    #[cfg(feature = "std")]
+    deadline: Deadline,
+    #[cfg(feature = "std")]
+    latency: Latency,
+    #[cfg(feature = "std")]
+    waiters: Waiters,
+    //Set by dropped timer, right before source is cancelled.
+    retired: UnsafeCell<Option<Retired>>,
+}
+
+//Closure of dropped timer, handed over to cancel handler of its source.
+struct Retired {
+    _data: BoxFnPtr,
+    //Flag on stack of dropping thread, set once context and closure are freed.
+    done: *const AtomicBool,
+}
+
+impl Context {
+    //Creates view of the timer, that owns context, so that it can be passed to callback.
+    //
+    //View doesn't own closure, and it must not outlive context, hence it is never dropped.
+    unsafe fn timer_view(&self) -> mem::ManuallyDrop<Timer> {
+        mem::ManuallyDrop::new(Timer {
+            inner: AtomicPtr::new(self.handle as *mut ffi::c_void),
+            data: Cell::new(BoxFnPtr::new()),
+            ctx: AtomicPtr::new(self as *const Self as *mut Self),
+        })
+    }
+
+    //Exchanges callback with `other`.
+    //
+    //Caller must make sure that callbacks do not access it meanwhile (see `Timer::set_callback`).
+    unsafe fn swap_handler(&self, other: &mut Handler) {
+        let handler = self.handler.get();
+        ptr::swap(handler, other);
+        Handler::bind_boxed(handler);
+        Handler::bind_boxed(other);
+    }
+
+    #[inline(always)]
+    unsafe fn invoke(&self) {
+        self.invoke_count(1)
+    }
+
+    #[inline(always)]
+    unsafe fn invoke_count(&self, count: u64) {
+        let handler = &mut *self.handler.get();
+        match &mut handler.counted {
+            Some(cb) => cb(&self.timer_view(), count),
+            None => (handler.ffi_cb)(handler.ffi_data),
+        }
+    }
+
+    unsafe fn fire(&self) {
+        let running = self.running.enter();
+        if running.is_closed() {
+            return;
+        }
+        //Callback is not accessed until `set_callback` replaces it.
+        running.wait_swap();
+        //Handler may have been already dispatched, when timer got cancelled (see `Timer::cancel_sync`).
+        core::sync::atomic::fence(Ordering::SeqCst);
+        if self.state.get() == TimerState::Cancelled {
+            return;
+        }
+        #[cfg(feature = "std")]
+        self.latency.fire();
+
+        #[cfg(feature = "std")]
+        {
+            if self.deadline.is_expired() {
+                self.state.cancel();
+                self.waiters.notify();
+                self.suspend();
+                return;
+            }
+        }
+
+        let is_periodic = self.state.is_periodic();
+        if is_periodic {
+            //Dispatch never runs handler concurrently, instead it merges expirations, that happened in meantime.
+            let fired = ffi::dispatch_source_get_data(self.handle);
+            self.pacing.miss(fired.saturating_sub(1) as u32);
+        }
+
+        let is_last = match self.armed.take_alarm() {
+            Some(is_last) => is_last,
+            //Alarm, that raced with the last one of `schedule_count`.
+            None => return,
+        };
+        if is_last {
+            self.state.cancel();
+            //Cannot wait for callbacks from within callback itself
+            self.suspend();
+        }
+        self.state.fire();
+        self.fires.fetch_add(1, Ordering::AcqRel);
+        #[cfg(feature = "std")]
+        self.waiters.notify();
+        if (*self.handler.get()).counted.is_some() {
+            let count = match is_periodic {
+                true => u64::from(self.pacing.take_missed()) + 1,
+                false => 1,
+            };
+            self.invoke_count(count);
+        } else {
+            let runs = match is_periodic {
+                true => self.pacing.runs(),
+                false => 1,
+            };
+            for _ in 0..runs {
+                self.invoke();
+            }
+        }
+    }
+
+    fn suspend(&self) {
+        if let Ok(false) = self.suspend.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst) {
+            unsafe {
+                ffi::dispatch_suspend(self.handle);
+            }
+        }
+    }
+
+    fn resume(&self) {
+        if let Ok(true) = self.suspend.compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst) {
+            unsafe {
+                ffi::dispatch_resume(self.handle);
+            }
+        }
+    }
+}
+
+//Sets dispatch source timer, accounting `leeway` in nanoseconds as forwarded to OS.
+unsafe fn set_source_timer(handle: ffi::dispatch_source_t, start: ffi::dispatch_time_t, interval: u64, leeway: u64) {
+    super::leeway_forwarded(time::Duration::from_nanos(leeway));
+    ffi::dispatch_source_set_timer(handle, start, interval, leeway);
+}
+
+//Returns `TimerError::Overflow` if durations don't fit into dispatch time.
+fn check_range(timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+    match timeout.as_nanos() <= i64::max_value() as u128 && interval.as_nanos() <= u64::max_value().into() {
+        true => Ok(()),
+        false => Err(TimerError::Overflow),
+    }
 }
 
 ///Apple source dispatch timer.
+///
+///# Drop
+///
+///Dropping timer waits for its callbacks, that are already running, to finish, hence timer must not be dropped
+///from within its own callback (e.g. by taking it out of shared `Mutex<Option<Timer>>`), as it would never return.
 pub struct Timer {
     inner: AtomicPtr<ffi::c_void>,
-    //Suspension count. Incremented on suspend, and decremented on each resume
-    suspend: AtomicBool,
+    //Owns closure, if any.
+    //Set only once by thread, that initialized timer, and otherwise accessed only on drop,
+    //hence it is safe to share timer between threads.
     data: Cell<BoxFnPtr>,
+    ctx: AtomicPtr<Context>,
 }
 
 impl Timer {
@@ -124,12 +645,22 @@ impl Timer {
     ///Creates new uninitialized instance.
     ///
     ///In order to use it one must call `init`.
+    ///
+    ///Initialized timer cannot be reset in place, as other threads may use it concurrently, hence to re-use it
+    ///(e.g. in object pool) assign `uninit` to it: old timer is dropped, which cancels it, waits for its
+    ///callbacks in flight and frees OS timer together with callback, after which `init` succeeds again.
     pub const unsafe fn uninit() -> Self {
         Self {
             inner: AtomicPtr::new(ptr::null_mut()),
-            //Note timer is created suspended.
-            suspend: AtomicBool::new(true),
             data: Cell::new(BoxFnPtr::new()),
+            ctx: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    #[inline(always)]
+    fn context(&self) -> Option<&Context> {
+        unsafe {
+            self.ctx.load(Ordering::Acquire).as_ref()
         }
     }
 
@@ -140,24 +671,33 @@ impl Timer {
         inner
     }
 
-    fn suspend(&self) {
-        if let Ok(false) = self.suspend.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst) {
-            let handle = self.get_inner();
-            unsafe {
-                ffi::dispatch_suspend(handle);
-            }
+    fn suspend_source(&self) {
+        let ctx = self.context();
+        debug_assert!(ctx.is_some(), "Timer has not been initialized");
+        if let Some(ctx) = ctx {
+            ctx.suspend();
         }
     }
 
-    fn resume(&self) {
-        if let Ok(true) = self.suspend.compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst) {
-            let handle = self.get_inner();
-            unsafe {
-                ffi::dispatch_resume(handle);
-            }
+    fn resume_source(&self) {
+        let ctx = self.context();
+        debug_assert!(ctx.is_some(), "Timer has not been initialized");
+        if let Some(ctx) = ctx {
+            ctx.resume();
         }
     }
 
+    #[inline(always)]
+    ///Returns raw handle of OS timer, in order to use platform API, that is not wrapped by the crate.
+    ///
+    ///Handle is owned by timer, hence caller must neither release it nor use it after timer is dropped.
+    ///Changing state of dispatch source behind timer's back (e.g. suspending or resuming it) breaks timer's bookkeeping.
+    ///
+    ///Returns null if timer is not initialized.
+    pub fn as_raw(&self) -> RawTimer {
+        self.inner.load(Ordering::Acquire)
+    }
+
     #[inline(always)]
     ///Returns whether timer is initialized
     pub fn is_init(&self) -> bool {
@@ -172,98 +712,222 @@ impl Timer {
     ///Returns whether timer has been initialized successfully or not.
     ///
     ///If timer is already initialized does nothing, returning false.
-    pub fn init(&self, cb: Callback) -> bool {
+    ///
+    ///Initialization is atomic: when multiple threads race to initialize the same timer, exactly one
+    ///succeeds, while the rest release OS timer and callback they created, returning false.
+    pub fn init(&self, cb: impl Into<Callback>) -> bool {
+        self.try_init(cb).is_ok()
+    }
+
+    ///Performs timer initialization
+    ///
+    ///Same as `init`, but returns reason of failure:
+    ///
+    ///- `TimerError::AlreadyInitialized` if timer is already initialized, in which case it does nothing.
+    ///- `TimerError::Os` if OS fails to create timer. Dispatch doesn't report reason, hence code is always 0.
+    pub fn try_init(&self, cb: impl Into<Callback>) -> Result<(), TimerError> {
+        self.init_on_queue(cb.into(), None)
+    }
+
+    #[must_use]
+    ///Performs timer initialization, invoking `cb` on global queue with specified `qos` class.
+    ///
+    ///Otherwise it is the same as `init`.
+    pub fn init_with_qos(&self, cb: impl Into<Callback>, qos: QosClass) -> bool {
+        self.init_on_queue(cb.into(), Some(qos)).is_ok()
+    }
+
+    fn init_on_queue(&self, cb: Callback, qos: Option<QosClass>) -> Result<(), TimerError> {
         if self.is_init() {
-            return false;
+            return Err(TimerError::AlreadyInitialized);
         }
 
+        let identifier = qos.map_or(ffi::QOS_CLASS_DEFAULT, QosClass::identifier);
         let handle = unsafe {
-            let queue = ffi::dispatch_get_global_queue(ffi::QOS_CLASS_DEFAULT, 0);
+            let queue = ffi::dispatch_get_global_queue(identifier, 0);
             ffi::dispatch_source_create(&ffi::_dispatch_source_type_timer as *const _ as ffi::dispatch_source_type_t, 0, 0, queue)
         };
 
-        match self.inner.compare_exchange(ptr::null_mut(), handle as _, Ordering::SeqCst, Ordering::Acquire) {
-            Ok(_) => match handle.is_null() {
-                true => false,
-                false => {
-                    let ffi_cb = cb.ffi_cb;
-                    let (data, ffi_data) = match cb.variant {
-                        CallbackVariant::Trivial(data) => (0, data),
-                        CallbackVariant::Boxed(cb) => unsafe {
-                            let raw = Box::into_raw(cb);
-                            (mem::transmute(raw), raw as *mut ffi::c_void)
-                        },
-                    };
-
-                    unsafe {
-                        ffi::dispatch_source_set_event_handler_f(handle, ffi_cb);
-                        ffi::dispatch_set_context(handle, ffi_data);
-                    }
-                    self.data.set(BoxFnPtr(data));
-                    true
+        if handle.is_null() {
+            return Err(TimerError::Os(0));
+        }
+
+        let (data, ctx) = cb.into_context(handle);
+        unsafe {
+            //Context is not shared yet.
+            (*ctx).qos = qos;
+        }
+
+        //Context is claimed first, so that timer is never observed initialized without it.
+        match self.ctx.compare_exchange(ptr::null_mut(), ctx, Ordering::SeqCst, Ordering::Acquire) {
+            Ok(_) => {
+                unsafe {
+                    ffi::dispatch_source_set_event_handler_f(handle, timer_callback_context);
+                    ffi::dispatch_set_context(handle, ctx as *mut ffi::c_void);
                 }
+                //safe because only the thread, that claimed context, can reach here.
+                self.data.set(data);
+                self.inner.store(handle as _, Ordering::Release);
+                Ok(())
             },
             Err(_) => {
                 unsafe {
+                    //Suspended source must not be released.
+                    ffi::dispatch_source_cancel(handle);
+                    ffi::dispatch_resume(handle);
                     ffi::dispatch_release(handle);
+                    let _ = Box::from_raw(ctx);
                 }
-                false
+                //Closure of the losing thread is never reachable from timer.
+                drop(data);
+                Err(TimerError::AlreadyInitialized)
             }
         }
     }
 
-
+    #[inline(always)]
     ///Creates new timer, invoking provided `cb` when timer expires.
     ///
+    ///`cb` can be plain function or closure, as well as `Callback`.
+    ///
     ///On failure, returns `None`
-    pub fn new(cb: Callback) -> Option<Self> {
-        let handle = unsafe {
-            let queue = ffi::dispatch_get_global_queue(ffi::QOS_CLASS_DEFAULT, 0);
-            ffi::dispatch_source_create(&ffi::_dispatch_source_type_timer as *const _ as ffi::dispatch_source_type_t, 0, 0, queue)
-        };
-
-        if handle.is_null() {
-            return None;
-        }
-
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (0, data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (mem::transmute(raw), raw as *mut ffi::c_void)
-            },
-        };
-
-        unsafe {
-            ffi::dispatch_source_set_event_handler_f(handle, ffi_cb);
-            ffi::dispatch_set_context(handle, ffi_data);
-        }
+    pub fn new(cb: impl Into<Callback>) -> Option<Self> {
+        Self::new_with_retry(cb.into(), 1)
+    }
 
-        Some(Self {
-            inner: AtomicPtr::new(handle as _),
-            suspend: AtomicBool::new(true),
-            data: Cell::new(BoxFnPtr(data)),
-        })
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///Same as `new`, but on failure returns `TimerError::Os`, which code is always 0, as dispatch doesn't report reason.
+    pub fn try_new(cb: impl Into<Callback>) -> Result<Self, TimerError> {
+        Self::create(cb.into(), 1, None, None)
     }
 
-    ///Schedules timer to alarm once after `timeout` passes.
+    ///Creates new timer, invoking provided `cb` when timer expires.
     ///
-    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
-    ///To prevent that user must `cancel` timer first.
+    ///If OS fails to create timer (e.g. due to memory pressure), retries it up to `attempts` times in total,
+    ///pausing between attempts (starting with 1ms and doubling each time, up to 64ms), hence it may block briefly.
+    ///Without `std` feature there is no way to sleep, hence attempts are made back to back.
     ///
-    ///Also due to dispatch API limitations, `timeout` is truncated by `i64::max_value()`
-    pub fn schedule_once(&self, timeout: time::Duration) {
-        let handle = self.get_inner();
+    ///On failure, returns `None`
+    pub fn new_with_retry(cb: impl Into<Callback>, attempts: u32) -> Option<Self> {
+        Self::create(cb.into(), attempts, None, None).ok()
+    }
 
-        self.suspend();
+    ///Creates new timer, invoking provided `cb` on global queue with specified `qos` class.
+    ///
+    ///On failure, returns `None`
+    pub fn new_with_qos(cb: impl Into<Callback>, qos: QosClass) -> Option<Self> {
+        Self::create(cb.into(), 1, Some(qos), None).ok()
+    }
+
+    ///Creates new timer, invoking provided `cb` on serial `queue`.
+    ///
+    ///On failure, returns `None`
+    pub fn new_on_queue(cb: impl Into<Callback>, queue: &DispatchQueue) -> Option<Self> {
+        Self::create(cb.into(), 1, None, Some(queue)).ok()
+    }
+
+    ///Creates timer, that takes ownership of dispatch source `handle`, created elsewhere, invoking `cb` when it fires.
+    ///
+    ///It allows to use dispatch source, created with options, that are not exposed by the crate (e.g. custom queue).
+    ///Event handler and context of `handle` are replaced with ones of timer.
+    ///
+    ///# Safety
+    ///
+    ///`handle` must be valid timer dispatch source (`DISPATCH_SOURCE_TYPE_TIMER`), that has never been resumed
+    ///and is not owned by anything else, as timer releases it on drop.
+    pub unsafe fn from_raw(handle: RawTimer, cb: impl Into<Callback>) -> Self {
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context(handle);
+
+        ffi::dispatch_source_set_event_handler_f(handle, timer_callback_context);
+        ffi::dispatch_set_context(handle, ctx as *mut ffi::c_void);
+
+        Self {
+            inner: AtomicPtr::new(handle),
+            data: Cell::new(data),
+            ctx: AtomicPtr::new(ctx),
+        }
+    }
+
+    pub(super) fn from_builder(builder: TimerBuilder) -> Result<Self, TimerError> {
+        if builder.clock.map_or(false, Clock::is_cpu_time) {
+            return Err(TimerError::Unsupported);
+        }
+        let timer = Self::create(builder.callback.ok_or(TimerError::Uninitialized)?, 1, builder.qos, None)?;
+        unsafe {
+            //Timer is not armed yet, hence context is not shared.
+            (*timer.ctx.load(Ordering::Relaxed)).leeway = builder.leeway;
+        }
+        Ok(timer)
+    }
+
+    fn create(cb: Callback, attempts: u32, qos: Option<QosClass>, queue: Option<&DispatchQueue>) -> Result<Self, TimerError> {
+        let identifier = qos.map_or(ffi::QOS_CLASS_DEFAULT, QosClass::identifier);
+        let handle = super::retry(attempts, || unsafe {
+            let queue = match queue {
+                Some(queue) => queue.handle,
+                None => ffi::dispatch_get_global_queue(identifier, 0),
+            };
+            let handle = ffi::dispatch_source_create(&ffi::_dispatch_source_type_timer as *const _ as ffi::dispatch_source_type_t, 0, 0, queue);
+            match handle.is_null() {
+                true => Err(true),
+                false => Ok(handle),
+            }
+        }).ok_or(TimerError::Os(0))?;
+
+        let (data, ctx) = cb.into_context(handle);
 
         unsafe {
-            let start = ffi::dispatch_walltime(ptr::null(), timeout.as_nanos() as i64);
-            ffi::dispatch_source_set_timer(handle, start, ffi::DISPATCH_TIME_FOREVER, 0);
+            //Context is not shared with OS yet.
+            (*ctx).qos = qos;
+            ffi::dispatch_source_set_event_handler_f(handle, timer_callback_context);
+            ffi::dispatch_set_context(handle, ctx as *mut ffi::c_void);
         }
 
-        self.resume();
+        Ok(Self {
+            inner: AtomicPtr::new(handle as _),
+            data: Cell::new(data),
+            ctx: AtomicPtr::new(ctx),
+        })
+    }
+
+    ///Schedules timer to alarm once after `timeout` passes.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
+    ///
+    ///Returns error if timer is not initialized, or `TimerError::Overflow` if `timeout` exceeds `i64::max_value()` nanoseconds.
+    pub fn try_schedule_once(&self, timeout: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+        check_range(timeout, time::Duration::from_secs(0))?;
+
+        let handle = self.get_inner();
+
+        self.suspend_source();
+
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        let leeway = self.default_leeway(timeout, time::Duration::from_secs(0)).as_nanos() as u64;
+        if let Some(ctx) = self.context() {
+            #[cfg(feature = "std")]
+            ctx.latency.arm(timeout, time::Duration::from_secs(0));
+            ctx.armed.arm(time::Duration::from_secs(0));
+            ctx.state.arm(time::Duration::from_secs(0));
+        }
+
+        unsafe {
+            set_source_timer(handle, ClockBase::Wall.start(timeout), ffi::DISPATCH_TIME_FOREVER, leeway);
+        }
+
+        self.resume_source();
+        Ok(())
     }
 
     ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
@@ -275,19 +939,230 @@ impl Timer {
     ///
     ///- `timeout` is truncated by `i64::max_value()`
     ///- `interval` is truncated by `u64::max_value()`
+    ///- Time is measured by `ClockBase::Monotonic`, unless `interval` is zero (see `schedule_interval_with_clock`)
     ///
     ///Returns `true` if successfully set, otherwise on error returns `false`
     pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        self.arm(timeout, interval)
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Same as `schedule_interval`, but returns error if timer is not initialized.
+    ///Instead of truncating, returns `TimerError::Overflow` if `timeout` or `interval` doesn't fit into dispatch time.
+    pub fn try_schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+        check_range(timeout, interval)?;
+
+        self.schedule_interval(timeout, interval);
+        Ok(())
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`, allowing OS
+    ///to delay each alarm by up to `leeway` in order to coalesce it with other timers.
+    ///
+    ///For periodic timer `leeway` is clamped to the `interval`, as otherwise timer would fire sporadically.
+    ///Overrides global coalescing setting.
+    ///
+    ///Leeway is advisory, system may still fire timer earlier or later than requested.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_with_leeway(&self, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        self.arm_with_leeway(timeout, interval, super::clamp_leeway(leeway, interval), ClockBase::for_interval(interval))
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`, measuring
+    ///time relatively to specified clock `base`.
+    ///
+    ///By default periodic timer uses `ClockBase::Monotonic`, while one-shot timer (zero `interval`) uses `ClockBase::Wall`.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_with_clock(&self, timeout: time::Duration, interval: time::Duration, base: ClockBase) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        self.arm_with_leeway(timeout, interval, self.default_leeway(timeout, interval), base)
+    }
+
+    ///Schedules timer to alarm periodically with `interval` exactly `count` times, after which it cancels itself.
+    ///
+    ///First alarm happens after `interval`.
+    ///Timer is cancelled from within its own callback, right before invoking user's callback for the last time,
+    ///hence callback may re-schedule timer. Alarms are counted as by `fire_count`.
+    ///
+    ///`count` of 0 does nothing, returning `true`, while `count` of 1 is equivalent to `schedule_once(interval)`.
+    ///Subsequent `schedule_*` call removes the limit.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_count(&self, interval: time::Duration, count: u32) -> bool {
+        match count {
+            0 => true,
+            1 => self.schedule_once(interval),
+            count => {
+                if let Some(ctx) = self.context() {
+                    ctx.armed.limit(count);
+                }
+                self.schedule_interval(interval, interval)
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Schedules timer to alarm periodically with `interval` until `deadline` is reached.
+    ///
+    ///First alarm happens after `interval`.
+    ///Once expired after `deadline`, timer cancels itself from within its own callback, without invoking user's callback.
+    ///As cancellation happens from the timer's own callback, it doesn't wait for callbacks in flight, hence
+    ///it never dead locks.
+    ///
+    ///Subsequent `schedule_*` call removes `deadline`.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_until(&self, interval: time::Duration, deadline: std::time::Instant) -> bool {
+        if let Some(ctx) = self.context() {
+            ctx.deadline.set(Some(deadline));
+        }
+
+        self.arm(interval, interval)
+    }
+
+    #[inline(always)]
+    fn arm(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        self.arm_with_leeway(timeout, interval, self.default_leeway(timeout, interval), ClockBase::for_interval(interval))
+    }
+
+    #[inline(always)]
+    //Returns leeway to use when user did not specify it explicitly.
+    fn default_leeway(&self, timeout: time::Duration, interval: time::Duration) -> time::Duration {
+        match self.context().and_then(|ctx| ctx.leeway) {
+            Some(leeway) => super::clamp_leeway(leeway, interval),
+            None => super::coalescing_leeway(timeout, interval),
+        }
+    }
+
+    fn arm_with_leeway(&self, timeout: time::Duration, interval: time::Duration, leeway: time::Duration, base: ClockBase) -> bool {
+        //OS handle is null until timer is initialized.
+        if !self.is_init() {
+            return false;
+        }
+
         let handle = self.get_inner();
 
-        self.suspend();
+        self.suspend_source();
+
+        let leeway = leeway.as_nanos() as u64;
+        if let Some(ctx) = self.context() {
+            ctx.pacing.reset();
+            #[cfg(feature = "std")]
+            ctx.latency.arm(timeout, interval);
+            ctx.armed.arm(interval);
+            ctx.state.arm(interval);
+        }
+
+        //Zero interval would make source fire continuously.
+        let interval = match interval.as_nanos() {
+            0 => ffi::DISPATCH_TIME_FOREVER,
+            interval => core::cmp::min(interval, u64::max_value().into()) as u64,
+        };
 
         unsafe {
-            let start = ffi::dispatch_walltime(ptr::null(), timeout.as_nanos() as i64);
-            ffi::dispatch_source_set_timer(handle, start, interval.as_nanos() as _, 0);
+            set_source_timer(handle, base.start(timeout), interval, leeway);
         }
 
-        self.resume();
+        self.resume_source();
+
+        true
+    }
+
+    #[inline(always)]
+    ///Returns clock, used by timer.
+    ///
+    ///Mac doesn't allow to select clock, hence it is always `Clock::Monotonic`, which is base of periodic timers.
+    ///Note that one shot timers are based on wall time instead (see `ClockBase`).
+    pub fn clock(&self) -> Clock {
+        Clock::Monotonic
+    }
+
+    #[inline]
+    ///Returns current value of the timer's clock.
+    ///
+    ///Value is `dispatch_time_t`, which is in units of `mach_absolute_time`, and can be used with `schedule_once_at_raw`.
+    ///Note that unit's duration is platform dependent, therefore use `clock_after` to compute future value.
+    pub fn clock_now(&self) -> u64 {
+        unsafe {
+            ffi::dispatch_time(ffi::DISPATCH_TIME_NOW, 0)
+        }
+    }
+
+    #[inline]
+    ///Returns value of the timer's clock after `delta` from now.
+    ///
+    ///See `clock_now` for details.
+    pub fn clock_after(&self, delta: time::Duration) -> u64 {
+        let delta = core::cmp::min(delta.as_nanos(), i64::max_value() as u128) as i64;
+        unsafe {
+            ffi::dispatch_time(ffi::DISPATCH_TIME_NOW, delta)
+        }
+    }
+
+    ///Schedules timer to alarm once, when timer's clock reaches `clock_value`.
+    ///
+    ///`clock_value` must be obtained via `clock_now` or `clock_after` of the same timer, as its meaning depends
+    ///on platform and clock used by the timer.
+    ///If `clock_value` is already in past, timer fires immediately.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_once_at_raw(&self, clock_value: u64) -> bool {
+        if !self.is_init() {
+            return false;
+        }
+
+        let handle = self.get_inner();
+
+        self.suspend_source();
+
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        if let Some(ctx) = self.context() {
+            //Clock value is in platform specific units, so there is no way to know when to expect it.
+            #[cfg(feature = "std")]
+            ctx.latency.clear();
+            ctx.armed.arm(time::Duration::from_secs(0));
+            ctx.state.arm(time::Duration::from_secs(0));
+        }
+
+        unsafe {
+            set_source_timer(handle, clock_value, ffi::DISPATCH_TIME_FOREVER, 0);
+        }
+
+        self.resume_source();
 
         true
     }
@@ -296,30 +1171,359 @@ impl Timer {
     ///Returns `true` if timer has been scheduled and still pending.
     ///
     ///On Win/Mac it only returns whether timer has been scheduled, as there is no way to check
-    ///whether timer is ongoing
+    ///whether timer is ongoing.
+    ///On Mac one-shot timer is no longer considered scheduled once it fires, even though its source stays resumed.
     pub fn is_scheduled(&self) -> bool {
-        !self.suspend.load(Ordering::Acquire)
+        match self.context() {
+            Some(ctx) => !ctx.suspend.load(Ordering::Acquire) && ctx.state.get() != TimerState::Fired,
+            None => false,
+        }
+    }
+
+    ///Returns approximate time remaining until next expiration of the timer.
+    ///
+    ///OS provides no way to query it, hence it is computed from the time timer was scheduled and its `interval`,
+    ///without accounting for system suspend.
+    ///Returns `None` if timer is not scheduled, one-shot timer has already expired or it has been scheduled with `schedule_once_at_raw`.
+    ///Without `std` feature always returns `None`.
+    pub fn time_remaining(&self) -> Option<time::Duration> {
+        let ctx = self.context()?;
+        if !self.is_scheduled() {
+            return None;
+        }
+
+        #[cfg(feature = "std")]
+        {
+            ctx.latency.remaining()
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = ctx;
+            None
+        }
     }
 
     #[inline]
     ///Cancels ongoing timer, if it was scheduled.
+    ///
+    ///Does nothing if timer is not initialized.
     pub fn cancel(&self) {
-        self.suspend()
+        if !self.is_init() {
+            return;
+        }
+
+        if let Some(ctx) = self.context() {
+            ctx.state.cancel();
+            #[cfg(feature = "std")]
+            ctx.waiters.notify();
+        }
+
+        self.suspend_source()
+    }
+
+    ///Cancels ongoing timer, if it was scheduled, and waits for callback, that is already running.
+    ///
+    ///Unlike `cancel`, once it returns, callback is guaranteed not to run until timer is scheduled again, as
+    ///handler, that has been already dispatched to the queue, finds timer cancelled and does nothing.
+    ///
+    ///Must not be called from within timer's own callback, as it would wait for itself forever.
+    ///
+    ///Does nothing if timer is not initialized.
+    pub fn cancel_sync(&self) {
+        if !self.is_init() {
+            return;
+        }
+
+        self.cancel();
+        if let Some(ctx) = self.context() {
+            core::sync::atomic::fence(Ordering::SeqCst);
+            ctx.running.wait_idle();
+        }
+    }
+
+    #[inline(always)]
+    ///Returns number of expirations, missed by periodic timer before its most recent run.
+    ///
+    ///Only meaningful on POSIX, while Win/Mac always return `0`.
+    pub fn overrun_count(&self) -> i32 {
+        0
+    }
+
+    ///Returns interval of the periodic timer, as it has been scheduled.
+    ///
+    ///Returns `None` if timer is not initialized, not scheduled or scheduled to fire once.
+    pub fn scheduled_interval(&self) -> Option<time::Duration> {
+        let ctx = self.context()?;
+        match ctx.state.is_periodic() {
+            true => Some(ctx.armed.interval()),
+            false => None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when timer has been scheduled most recently.
+    ///
+    ///Together with `scheduled_interval` it allows to tell when periodic timer is expected to fire next.
+    ///Returns `None` if timer is not initialized or has never been scheduled.
+    pub fn last_armed_at(&self) -> Option<std::time::Instant> {
+        self.context()?.armed.at()
+    }
+
+    ///Pauses scheduled timer, so that `resume` continues its schedule later.
+    ///
+    ///On Mac dispatch source is suspended, while its schedule keeps running: alarms, that happen while paused,
+    ///are delivered as one on `resume`, being accounted as missed intervals (see `CatchUp`).
+    ///Callback, that is already running, is not waited for.
+    ///
+    ///Does nothing if timer is not initialized or not scheduled.
+    pub fn pause(&self) {
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        if !self.is_scheduled() {
+            return;
+        }
+
+        self.suspend_source();
+        ctx.armed.pause(time::Duration::from_secs(0));
+    }
+
+    ///Resumes timer, paused by `pause`.
+    ///
+    ///Does nothing if timer is not paused, or it has been cancelled or scheduled again since then.
+    pub fn resume(&self) {
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        if ctx.armed.take_paused().is_none() || ctx.state.get() != TimerState::Armed {
+            return;
+        }
+
+        self.resume_source();
+    }
+
+    #[inline]
+    ///Returns whether timer's callback is currently running.
+    ///
+    ///Result is a snapshot, that may be already outdated once returned: callback may start right after
+    ///`false` is returned, or finish right after `true` is returned.
+    ///Use `cancel_sync` or drop timer to actually wait for callback to finish.
+    ///
+    ///Uninitialized timer is never running.
+    pub fn is_running(&self) -> bool {
+        match self.context() {
+            Some(ctx) => ctx.running.is_busy(),
+            None => false,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when timer is expected to fire next.
+    ///
+    ///It is tracked by the crate itself from the time timer was scheduled and its `interval`, without querying OS,
+    ///hence it doesn't account for delays of OS or system suspend.
+    ///Returns `None` if timer is not scheduled (e.g. one shot timer has already fired) or it has been scheduled with `schedule_once_at_raw`.
+    pub fn deadline(&self) -> Option<std::time::Instant> {
+        let ctx = self.context()?;
+        match ctx.state.get() {
+            TimerState::Armed => ctx.latency.next(),
+            _ => None,
+        }
+    }
+
+    ///Returns number of times timer fired, invoking its callback.
+    ///
+    ///Expirations, skipped or merged according to `CatchUp` policy, are counted as one, while `fire_sync` is not counted.
+    pub fn fire_count(&self) -> u64 {
+        match self.context() {
+            Some(ctx) => ctx.fires.load(Ordering::Acquire),
+            None => 0,
+        }
+    }
+
+    ///Sets policy to handle intervals, missed by periodic timer.
+    ///
+    ///Takes effect starting with the next expiration.
+    pub fn set_catch_up(&self, policy: CatchUp) {
+        if let Some(ctx) = self.context() {
+            ctx.pacing.set_policy(policy);
+        }
+    }
+
+    ///Returns policy to handle intervals, missed by periodic timer.
+    pub fn catch_up(&self) -> CatchUp {
+        match self.context() {
+            Some(ctx) => ctx.pacing.policy(),
+            None => CatchUp::default(),
+        }
+    }
+
+    #[inline]
+    ///Returns QoS class, requested on creation via `new_with_qos` or `init_with_qos`.
+    ///
+    ///Returns `None` if timer uses default queue or it is not initialized.
+    pub fn quality_of_service(&self) -> Option<QosClass> {
+        self.context()?.qos
+    }
+
+    #[inline(always)]
+    ///Returns features, supported by timer on current platform.
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            can_query_remaining: false,
+            can_select_clock: false,
+            supports_leeway: true,
+            callbacks_serialized: true,
+            min_resolution: time::Duration::from_nanos(1),
+            supports_absolute: true,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Blocks current thread until timer's next expiration.
+    ///
+    ///Returns `true` once timer fires, or `false` if timer is not scheduled or gets cancelled while waiting.
+    ///Must not be called from within timer's callback, as next expiration may never come.
+    pub fn wait(&self) -> bool {
+        match self.context() {
+            Some(ctx) => ctx.waiters.wait(&ctx.fires, &ctx.state),
+            None => false,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns how late the most recent expiration was, compared to its scheduled time.
+    ///
+    ///For periodic timer it is relative to the latest point of its schedule, hence it is always less than `interval`.
+    ///Returns `None` if timer has not fired yet or it has been scheduled with `schedule_once_at_raw`.
+    pub fn last_latency(&self) -> Option<time::Duration> {
+        self.context()?.latency.last()
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when the most recent expiration was scheduled to happen.
+    ///
+    ///Together with `last_latency` it is tracked by the crate itself, and for periodic timer it is the latest point of its schedule.
+    ///Returns `None` if timer has not fired yet, or it is scheduled with raw clock value, as its schedule is not tracked then.
+    pub fn last_deadline(&self) -> Option<std::time::Instant> {
+        self.context()?.latency.due()
+    }
+
+    #[inline]
+    ///Returns timer's lifecycle state.
+    ///
+    ///Uninitialized timer is always `Idle`.
+    pub fn state(&self) -> TimerState {
+        match self.context() {
+            Some(ctx) => ctx.state.get(),
+            None => TimerState::Idle,
+        }
+    }
+
+    ///Replaces timer's callback with `cb`, without re-creating OS timer and keeping its schedule.
+    ///
+    ///Callbacks in flight are finished first, after which previous callback (with its closure, if any) is freed.
+    ///Expirations, that happen meanwhile, wait for the swap and then invoke `cb`.
+    ///Concurrent calls are serialized, hence timer can be shared (e.g. via `Arc`) while its callback is replaced.
+    ///
+    ///Must not be called from within timer's own callback, as it would wait for itself forever, nor concurrently with `fire_sync`.
+    ///
+    ///Returns `false` if timer is not initialized, in which case `cb` is dropped.
+    pub fn set_callback(&self, cb: Callback) -> bool {
+        let ctx = match self.context() {
+            Some(ctx) if self.is_init() => ctx,
+            _ => return false,
+        };
+
+        let (data, mut prev) = cb.into_handler();
+        //Swap keeps out both callbacks and concurrent `set_callback`.
+        ctx.running.begin_swap();
+        unsafe {
+            ctx.swap_handler(&mut prev);
+        }
+        //Previous closure is freed with its owner.
+        let prev_data = self.data.replace(data);
+        ctx.running.end_swap();
+
+        drop(prev);
+        drop(prev_data);
+        true
+    }
+
+    ///Invokes timer's callback synchronously on the calling thread.
+    ///
+    ///Unlike scheduling with zero timeout, callback runs before this function returns.
+    ///Primarily intended as testing aid.
+    ///
+    ///Does nothing if timer is not initialized.
+    ///
+    ///# Safety
+    ///
+    ///- Normally callback is invoked on the dispatch queue. Callback that relies on that
+    ///(e.g. uses thread locals or expects to never run on the thread that owns the timer) must not be invoked this way.
+    ///- If timer is scheduled, OS may invoke callback concurrently with this call. For closures it
+    ///means two mutable references to the same closure, hence user must make sure that timer is not scheduled
+    ///(or that callback is safe to run concurrently) before calling this function.
+    pub unsafe fn fire_sync(&self) {
+        if let Some(ctx) = self.context() {
+            ctx.invoke();
+        }
     }
 }
 
 impl Drop for Timer {
     fn drop(&mut self) {
         let handle = self.inner.load(Ordering::Relaxed);
+        let ctx = self.ctx.load(Ordering::Relaxed);
         if !handle.is_null() {
             unsafe {
+                if let Some(ctx) = self.context() {
+                    ctx.running.close();
+                    //Callbacks in flight may re-schedule timer (see `Callback::with_timer`), resuming it meanwhile.
+                    ctx.running.wait_idle();
+                }
+
+                if ctx.is_null() {
+                    ffi::dispatch_source_cancel(handle);
+                    ffi::dispatch_release(handle);
+                    return;
+                }
+
+                //Event handler may still be about to start, hence context and closure are freed by cancel handler,
+                //which dispatch runs once source is done with them.
+                //Source is kept suspended, so that cancel handler doesn't run before context is released by us.
+                (*ctx).suspend();
+                let done = AtomicBool::new(false);
+                *(*ctx).retired.get() = Some(Retired {
+                    _data: self.data.replace(BoxFnPtr::new()),
+                    done: &done,
+                });
+                self.ctx.store(ptr::null_mut(), Ordering::Relaxed);
+                ffi::dispatch_source_set_cancel_handler_f(handle, timer_cancel_context);
                 ffi::dispatch_source_cancel(handle);
 
                 //It is error to release while source is suspended
                 //So we decrement it
-                self.resume();
-
+                ffi::dispatch_resume(handle);
                 ffi::dispatch_release(handle);
+
+                //Cancel handler runs on target queue, hence timer must not be dropped from callback on the same serial queue.
+                let mut attempt = 0;
+                while !done.load(Ordering::Acquire) {
+                    super::backoff(attempt);
+                    attempt = attempt.saturating_add(1);
+                }
+            }
+            return;
+        }
+
+        if !ctx.is_null() {
+            unsafe {
+                //Callbacks, started before OS timer is gone, may still use context and closure.
+                (*ctx).running.wait_idle();
+                let _ = Box::from_raw(ctx);
             }
         }
     }
@@ -327,8 +1531,193 @@ impl Drop for Timer {
 
 #[cfg(test)]
 mod tests {
+    extern crate std;
+
     use super::*;
 
+    #[test]
+    fn from_raw() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        //Dispatch source is created directly, without context, hence nothing is leaked once timer releases it.
+        let handle = unsafe {
+            let queue = ffi::dispatch_get_global_queue(ffi::QOS_CLASS_DEFAULT, 0);
+            ffi::dispatch_source_create(&ffi::_dispatch_source_type_timer as *const _ as ffi::dispatch_source_type_t, 0, 0, queue) as RawTimer
+        };
+        assert!(!handle.is_null());
+
+        let timer = unsafe {
+            Timer::from_raw(handle, Callback::plain(cb))
+        };
+        assert!(timer.is_init());
+        assert_eq!(timer.as_raw(), handle);
+
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert_eq!(timer.fire_count(), 1);
+    }
+
+    #[test]
+    fn capabilities() {
+        const CAPABILITIES: Capabilities = Timer::capabilities();
+
+        assert!(!CAPABILITIES.can_query_remaining);
+        assert!(!CAPABILITIES.can_select_clock);
+        assert!(CAPABILITIES.supports_leeway);
+        assert!(CAPABILITIES.callbacks_serialized);
+        assert_eq!(CAPABILITIES.min_resolution, time::Duration::from_nanos(1));
+        assert_eq!(Timer::RESOLUTION, time::Duration::from_nanos(1));
+        assert_eq!(Timer::resolution(), Timer::RESOLUTION);
+        assert!(CAPABILITIES.supports_absolute);
+    }
+
+    #[test]
+    fn quality_of_service() {
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.quality_of_service(), None);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert_eq!(timer.quality_of_service(), None);
+
+        let timer = Timer::new_with_qos(Callback::plain(cb), QosClass::Utility).expect("To create timer");
+        assert_eq!(timer.quality_of_service(), Some(QosClass::Utility));
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert!(timer.init_with_qos(Callback::plain(cb), QosClass::UserInteractive));
+        assert_eq!(timer.quality_of_service(), Some(QosClass::UserInteractive));
+    }
+
+    #[test]
+    fn new_on_queue() {
+        use core::sync::atomic::{AtomicU8, AtomicUsize};
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        static RUNNING: AtomicU8 = AtomicU8::new(0);
+        static OVERLAPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn cb() {
+            if RUNNING.fetch_add(1, Ordering::AcqRel) != 0 {
+                OVERLAPS.fetch_add(1, Ordering::AcqRel);
+            }
+            std::thread::sleep(time::Duration::from_millis(5));
+            RUNNING.fetch_sub(1, Ordering::AcqRel);
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let queue = DispatchQueue::serial().expect("To create queue");
+        let first = Timer::new_on_queue(Callback::plain(cb), &queue).expect("To create timer");
+        let second = Timer::new_on_queue(Callback::plain(cb), &queue).expect("To create timer");
+        //Timers keep queue alive.
+        drop(queue);
+
+        assert!(first.schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(2)));
+        assert!(second.schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(2)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        first.cancel_sync();
+        second.cancel_sync();
+
+        assert!(COUNT.load(Ordering::Acquire) > 2);
+        assert_eq!(OVERLAPS.load(Ordering::Acquire), 0);
+    }
+
+    #[test]
+    fn schedule_interval_with_clock() {
+        use core::sync::atomic::AtomicU8;
+        static MONOTONIC: AtomicU8 = AtomicU8::new(0);
+        static WALL: AtomicU8 = AtomicU8::new(0);
+
+        fn monotonic() {
+            MONOTONIC.fetch_add(1, Ordering::AcqRel);
+        }
+
+        fn wall() {
+            WALL.fetch_add(1, Ordering::AcqRel);
+        }
+
+        assert_eq!(ClockBase::for_interval(time::Duration::from_secs(0)), ClockBase::Wall);
+        assert_eq!(ClockBase::for_interval(time::Duration::from_millis(1)), ClockBase::Monotonic);
+
+        let monotonic = Timer::new(Callback::plain(monotonic)).expect("To create timer");
+        let wall = Timer::new(Callback::plain(wall)).expect("To create timer");
+        assert!(monotonic.schedule_interval_with_clock(time::Duration::from_millis(10), time::Duration::from_millis(50), ClockBase::Monotonic));
+        assert!(wall.schedule_interval_with_clock(time::Duration::from_millis(10), time::Duration::from_millis(50), ClockBase::Wall));
+        std::thread::sleep(time::Duration::from_millis(300));
+        monotonic.cancel();
+        wall.cancel();
+        assert!(MONOTONIC.load(Ordering::Acquire) >= 2);
+        assert!(WALL.load(Ordering::Acquire) >= 2);
+    }
+
+    #[test]
+    fn schedule_once_is_not_scheduled_after_fire() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        assert!(timer.is_scheduled());
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert!(!timer.is_scheduled());
+
+        //Re-scheduling makes it pending again.
+        assert!(timer.schedule_once(time::Duration::from_secs(10)));
+        assert!(timer.is_scheduled());
+        timer.cancel();
+        assert!(!timer.is_scheduled());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn cancel_sync() {
+        use core::sync::atomic::AtomicUsize;
+        use std::sync::Arc;
+
+        let active = Arc::new(AtomicUsize::new(0));
+        let count = Arc::new(AtomicUsize::new(0));
+        let cb = {
+            let active = active.clone();
+            let count = count.clone();
+            move || {
+                active.fetch_add(1, Ordering::AcqRel);
+                count.fetch_add(1, Ordering::AcqRel);
+                std::thread::sleep(time::Duration::from_millis(2));
+                active.fetch_sub(1, Ordering::AcqRel);
+            }
+        };
+        let timer = Timer::new(Callback::closure(cb)).expect("To create timer");
+
+        for _ in 0..20 {
+            assert!(timer.schedule_interval(time::Duration::from_secs(0), time::Duration::from_millis(1)));
+            std::thread::sleep(time::Duration::from_millis(5));
+            timer.cancel_sync();
+            assert_eq!(active.load(Ordering::Acquire), 0);
+
+            let fired = count.load(Ordering::Acquire);
+            std::thread::sleep(time::Duration::from_millis(10));
+            assert_eq!(count.load(Ordering::Acquire), fired);
+        }
+
+        drop(timer);
+        assert_eq!(Arc::strong_count(&active), 1);
+    }
+
     #[test]
     fn init_plain_fn() {
         let mut timer = unsafe {
@@ -338,6 +1727,7 @@ mod tests {
         fn cb() {
         }
 
+        #[cfg(feature = "alloc")]
         let closure = || {
         };
 
@@ -346,12 +1736,14 @@ mod tests {
         assert!(!ptr.is_null());
         assert!(timer.data.get_mut().is_null());
 
+        #[cfg(feature = "alloc")]
         assert!(!timer.init(Callback::closure(closure)));
         assert!(!ptr.is_null());
         assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
         assert!(timer.data.get_mut().is_null());
     }
 
+    #[cfg(feature = "alloc")]
     #[test]
     fn init_closure() {
         let mut timer = unsafe {
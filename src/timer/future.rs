@@ -0,0 +1,353 @@
+use core::{task, time};
+use core::pin::Pin;
+use core::future::Future;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+extern crate alloc;
+use alloc::sync::Arc;
+
+use super::{Callback, Timer};
+
+//State shared between future and timer's callback.
+struct Shared {
+    fired: AtomicBool,
+    //Incremented by `disarm`, so that callbacks of previous arming are ignored.
+    generation: AtomicU64,
+    //Generation, at which timer has been armed most recently.
+    armed: AtomicU64,
+    //Number of ticks, not yet yielded by stream.
+    ticks: AtomicU64,
+    waker: std::sync::Mutex<Option<task::Waker>>,
+}
+
+impl Shared {
+    fn new() -> Self {
+        Self {
+            fired: AtomicBool::new(false),
+            generation: AtomicU64::new(0),
+            armed: AtomicU64::new(0),
+            ticks: AtomicU64::new(0),
+            waker: std::sync::Mutex::new(None),
+        }
+    }
+
+    fn wake(&self) {
+        self.fired.store(true, Ordering::Release);
+        let waker = self.waker.lock().unwrap_or_else(|error| error.into_inner()).take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    #[inline(always)]
+    //Returns whether timer has not been disarmed since it was armed most recently.
+    fn is_current(&self) -> bool {
+        self.armed.load(Ordering::Acquire) == self.generation.load(Ordering::Acquire)
+    }
+
+    //Wakes future, unless it has been reset since timer was armed.
+    fn expire(&self) {
+        if self.is_current() {
+            self.wake();
+        }
+    }
+
+    //Counts ticks, unless stream has been re-armed since they were scheduled.
+    fn tick(&self, count: u64) {
+        if self.is_current() {
+            self.ticks.fetch_add(count, Ordering::AcqRel);
+            self.wake();
+        }
+    }
+}
+
+//Cancels `timer`, so that callbacks of its current arming are ignored, until `armed` is updated with new generation.
+fn disarm(timer: &Timer, shared: &Shared) {
+    //Callbacks, entered from now on, belong to previous arming and are ignored.
+    shared.generation.fetch_add(1, Ordering::AcqRel);
+    timer.cancel();
+    //Only Win waits for callbacks on cancel, hence wait for the ones, that have passed generation check already.
+    let mut attempt = 0;
+    while timer.is_running() {
+        super::backoff(attempt);
+        attempt = attempt.saturating_add(1);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Describes how `TimerStream` behaves, when consumer doesn't keep up with ticks.
+///
+///Missed ticks are counted using number of expirations, reported to `Callback::with_count`:
+///
+///- POSIX uses overrun count (`timer_getoverrun` or `timerfd` expirations);
+///- Win estimates it from time passed since previous run, as thread pool doesn't report missed expirations;
+///- Mac uses number of expirations, merged by dispatch into single run of handler;
+///- Software scheduler counts intervals, passed while it was busy.
+///
+///In addition, ticks, that fired while consumer was not polling stream, are counted as missed.
+pub enum MissedTickBehavior {
+    ///Yields immediately for each missed tick, until stream catches up with schedule.
+    Burst,
+    ///Merges missed ticks into single one, keeping original schedule.
+    Skip,
+    ///Merges missed ticks into single one, and restarts schedule, so that next tick is one `interval` after it.
+    Delay,
+}
+
+impl Default for MissedTickBehavior {
+    #[inline(always)]
+    fn default() -> Self {
+        MissedTickBehavior::Skip
+    }
+}
+
+///Future, that completes once `timeout` passes.
+///
+///Timer is armed lazily on first poll, hence `timeout` is counted from there.
+///Dropping future before it completes, cancels underlying timer.
+pub struct TimerFuture {
+    timer: Timer,
+    timeout: time::Duration,
+    shared: Arc<Shared>,
+    armed: bool,
+}
+
+impl TimerFuture {
+    ///Creates new future, that completes after `timeout`.
+    ///
+    ///On failure to create timer, returns `None`
+    pub fn try_new(timeout: time::Duration) -> Option<Self> {
+        let shared = Arc::new(Shared::new());
+
+        let cb = {
+            let shared = shared.clone();
+            move || shared.expire()
+        };
+
+        Some(Self {
+            timer: Timer::new(Callback::closure(cb))?,
+            timeout,
+            shared,
+            armed: false,
+        })
+    }
+
+    #[inline]
+    ///Creates new future, that completes after `timeout`.
+    ///
+    ///Panics on failure to create timer.
+    pub fn new(timeout: time::Duration) -> Self {
+        match Self::try_new(timeout) {
+            Some(result) => result,
+            None => panic!("Unable to create timer"),
+        }
+    }
+
+    ///Returns whether timeout has passed.
+    pub fn is_expired(&self) -> bool {
+        self.shared.fired.load(Ordering::Acquire)
+    }
+
+    ///Restarts future with new `timeout`, allowing to await on it again.
+    ///
+    ///Timer is cancelled and armed again on next poll.
+    ///Callback of previous timeout, that is already running, is waited for, so that it doesn't complete future.
+    pub fn reset(&mut self, timeout: time::Duration) {
+        disarm(&self.timer, &self.shared);
+
+        self.timeout = timeout;
+        self.armed = false;
+        self.shared.fired.store(false, Ordering::Release);
+    }
+}
+
+impl Future for TimerFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.is_expired() {
+            return task::Poll::Ready(());
+        }
+
+        {
+            let mut waker = this.shared.waker.lock().unwrap_or_else(|error| error.into_inner());
+            match waker.as_ref() {
+                Some(waker) if waker.will_wake(ctx.waker()) => (),
+                _ => *waker = Some(ctx.waker().clone()),
+            }
+        }
+
+        if !this.armed {
+            this.armed = true;
+            this.shared.armed.store(this.shared.generation.load(Ordering::Acquire), Ordering::Release);
+            this.timer.schedule_once(this.timeout);
+        }
+
+        //Timer may fire before waker is set.
+        match this.is_expired() {
+            true => task::Poll::Ready(()),
+            false => task::Poll::Pending,
+        }
+    }
+}
+
+impl Drop for TimerFuture {
+    fn drop(&mut self) {
+        if self.armed && !self.is_expired() {
+            self.timer.cancel();
+        }
+    }
+}
+
+///Stream, that yields every `interval`.
+///
+///Timer is armed lazily on first poll, hence first item is yielded one `interval` after it.
+///If consumer doesn't keep up, missed ticks are handled according to `MissedTickBehavior`, which is `Skip` by default.
+///Stream never ends, and dropping it cancels underlying timer.
+pub struct TimerStream {
+    timer: Timer,
+    interval: time::Duration,
+    behavior: MissedTickBehavior,
+    shared: Arc<Shared>,
+    armed: bool,
+}
+
+impl TimerStream {
+    #[inline(always)]
+    ///Creates new stream, that yields every `interval`, merging missed ticks.
+    ///
+    ///On failure to create timer or if `interval` is zero, returns `None`
+    pub fn try_new(interval: time::Duration) -> Option<Self> {
+        Self::try_with_behavior(interval, MissedTickBehavior::Skip)
+    }
+
+    ///Creates new stream, that yields every `interval`, handling missed ticks according to `behavior`.
+    ///
+    ///On failure to create timer or if `interval` is zero, returns `None`
+    pub fn try_with_behavior(interval: time::Duration, behavior: MissedTickBehavior) -> Option<Self> {
+        //Zero interval would arm one shot timer, after which stream would be pending forever.
+        if interval.as_nanos() == 0 {
+            return None;
+        }
+
+        let shared = Arc::new(Shared::new());
+
+        let cb = {
+            let shared = shared.clone();
+            move |count| shared.tick(count)
+        };
+
+        Some(Self {
+            timer: Timer::new(Callback::with_count(cb))?,
+            interval,
+            behavior,
+            shared,
+            armed: false,
+        })
+    }
+
+    ///Creates new stream, that yields every `interval`, handling missed ticks according to `behavior`.
+    ///
+    ///Panics if `interval` is zero or on failure to create timer.
+    pub fn with_behavior(interval: time::Duration, behavior: MissedTickBehavior) -> Self {
+        assert!(interval.as_nanos() > 0, "`interval` must be non-zero");
+        match Self::try_with_behavior(interval, behavior) {
+            Some(result) => result,
+            None => panic!("Unable to create timer"),
+        }
+    }
+
+    #[inline(always)]
+    ///Returns behavior on missed ticks.
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.behavior
+    }
+
+    //Takes ticks, that are due, returning whether item should be yielded.
+    fn take_tick(&self) -> bool {
+        let ticks = &self.shared.ticks;
+        match self.behavior {
+            MissedTickBehavior::Burst => ticks.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| count.checked_sub(1)).is_ok(),
+            MissedTickBehavior::Skip => ticks.swap(0, Ordering::AcqRel) > 0,
+            MissedTickBehavior::Delay => match ticks.swap(0, Ordering::AcqRel) {
+                0 => false,
+                1 => true,
+                _ => {
+                    //Ticks of old schedule are dropped, so that only new one is counted.
+                    disarm(&self.timer, &self.shared);
+                    ticks.store(0, Ordering::Release);
+                    self.shared.armed.store(self.shared.generation.load(Ordering::Acquire), Ordering::Release);
+                    self.timer.schedule_interval(self.interval, self.interval);
+                    true
+                }
+            },
+        }
+    }
+
+    #[inline]
+    ///Creates new stream, that yields every `interval`.
+    ///
+    ///Panics if `interval` is zero or on failure to create timer.
+    pub fn new(interval: time::Duration) -> Self {
+        Self::with_behavior(interval, MissedTickBehavior::Skip)
+    }
+}
+
+impl futures_core::Stream for TimerStream {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.take_tick() {
+            return task::Poll::Ready(Some(()));
+        }
+
+        {
+            let mut waker = this.shared.waker.lock().unwrap_or_else(|error| error.into_inner());
+            match waker.as_ref() {
+                Some(waker) if waker.will_wake(ctx.waker()) => (),
+                _ => *waker = Some(ctx.waker().clone()),
+            }
+        }
+
+        if !this.armed {
+            this.armed = true;
+            this.timer.schedule_interval(this.interval, this.interval);
+        }
+
+        //Timer may fire before waker is set.
+        match this.take_tick() {
+            true => task::Poll::Ready(Some(())),
+            false => task::Poll::Pending,
+        }
+    }
+}
+
+impl Timer {
+    #[inline(always)]
+    ///Creates future, that completes after `timeout`.
+    ///
+    ///Panics on failure to create timer, see `TimerFuture::try_new` for fallible version.
+    pub fn sleep(timeout: time::Duration) -> TimerFuture {
+        TimerFuture::new(timeout)
+    }
+
+    #[inline(always)]
+    ///Creates stream, that yields every `interval`.
+    ///
+    ///Panics if `interval` is zero or on failure to create timer, see `TimerStream::try_new` for fallible version.
+    pub fn interval(interval: time::Duration) -> TimerStream {
+        TimerStream::new(interval)
+    }
+
+    #[inline(always)]
+    ///Creates stream, that yields every `interval`, handling missed ticks according to `behavior`.
+    ///
+    ///Panics if `interval` is zero or on failure to create timer, see `TimerStream::try_with_behavior` for fallible version.
+    pub fn interval_stream(interval: time::Duration, behavior: MissedTickBehavior) -> TimerStream {
+        TimerStream::with_behavior(interval, behavior)
+    }
+}
@@ -1,34 +1,952 @@
-use core::{mem,time};
+use core::{fmt,ptr,time};
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 
+//Timer's state is always on heap, as OS refers to it while `Timer` can be moved.
 extern crate alloc;
+#[cfg(feature = "alloc")]
 use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
 
-#[cfg(target_pointer_width = "16")]
-type FatPtr = u32;
-#[cfg(target_pointer_width = "32")]
-type FatPtr = u64;
-#[cfg(target_pointer_width = "64")]
-type FatPtr = u128;
+#[cfg(all(windows, not(feature = "software")))]
+mod win32;
+#[cfg(all(windows, not(feature = "software")))]
+pub use win32::*;
+
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "software")))]
+mod apple;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(feature = "software")))]
+pub use apple::*;
+
+//The only user of `libc`, which is dependency for the same targets only.
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios")), not(feature = "software")))]
+mod posix;
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios")), not(feature = "software")))]
+pub use posix::*;
+
+#[cfg(any(feature = "software", not(any(windows, unix))))]
+mod software;
+#[cfg(any(feature = "software", not(any(windows, unix))))]
+pub use software::*;
+
+#[cfg(feature = "futures")]
+mod future;
+#[cfg(feature = "futures")]
+pub use future::{MissedTickBehavior, TimerFuture, TimerStream};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Timer's error
+pub enum TimerError {
+    ///Timer is not initialized.
+    Uninitialized,
+    ///Timer is already scheduled and still pending.
+    Pending,
+    ///Timer is already initialized.
+    AlreadyInitialized,
+    ///Duration doesn't fit into OS timer's representation.
+    Overflow,
+    ///Requested option is not supported on current platform.
+    Unsupported,
+    ///OS error code.
+    ///
+    ///`errno` on posix, `GetLastError` on Win.
+    ///Dispatch on Mac doesn't report reason of failure, hence it is always 0.
+    Os(i32),
+}
+
+impl fmt::Display for TimerError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimerError::Uninitialized => fmt.write_str("Timer is not initialized"),
+            TimerError::Pending => fmt.write_str("Timer is already scheduled"),
+            TimerError::AlreadyInitialized => fmt.write_str("Timer is already initialized"),
+            TimerError::Overflow => fmt.write_str("Duration is out of timer's range"),
+            TimerError::Unsupported => fmt.write_str("Option is not supported on this platform"),
+            TimerError::Os(code) => fmt.write_fmt(format_args!("OS error {}", code)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TimerError {
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Timer's lifecycle state.
+///
+///Transitions:
+///
+///- Any `schedule_*` method moves timer to `Armed`.
+///- One shot timer moves from `Armed` to `Fired` right before its callback is invoked.
+///Periodic timer stays `Armed` until it is cancelled or re-scheduled.
+///- `cancel` moves `Armed` timer to `Cancelled`, otherwise state is unchanged.
+pub enum TimerState {
+    ///Timer has not been scheduled yet.
+    Idle,
+    ///Timer is scheduled.
+    Armed,
+    ///One shot timer expired.
+    Fired,
+    ///Timer has been cancelled while armed.
+    Cancelled,
+}
+
+const STATE_IDLE: u8 = 0;
+const STATE_ARMED_ONCE: u8 = 1;
+const STATE_ARMED_PERIODIC: u8 = 2;
+const STATE_FIRED: u8 = 3;
+const STATE_CANCELLED: u8 = 4;
+
+//Lifecycle state, shared between timer and its callback.
+struct Lifecycle(AtomicU8);
+
+impl Lifecycle {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self(AtomicU8::new(STATE_IDLE))
+    }
+
+    #[inline(always)]
+    //Returns previous state, to be restored if scheduling fails.
+    fn arm(&self, interval: time::Duration) -> u8 {
+        match interval.as_nanos() {
+            0 => self.0.swap(STATE_ARMED_ONCE, Ordering::AcqRel),
+            _ => self.0.swap(STATE_ARMED_PERIODIC, Ordering::AcqRel),
+        }
+    }
+
+    #[allow(unused)]
+    #[inline(always)]
+    fn restore(&self, state: u8) {
+        self.0.store(state, Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn fire(&self) {
+        let _ = self.0.compare_exchange(STATE_ARMED_ONCE, STATE_FIRED, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    #[inline(always)]
+    fn is_periodic(&self) -> bool {
+        self.0.load(Ordering::Acquire) == STATE_ARMED_PERIODIC
+    }
+
+    #[inline(always)]
+    fn cancel(&self) {
+        let _ = self.0.fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| match state {
+            STATE_ARMED_ONCE | STATE_ARMED_PERIODIC => Some(STATE_CANCELLED),
+            _ => None,
+        });
+    }
+
+    #[inline(always)]
+    fn get(&self) -> TimerState {
+        match self.0.load(Ordering::Acquire) {
+            STATE_ARMED_ONCE | STATE_ARMED_PERIODIC => TimerState::Armed,
+            STATE_FIRED => TimerState::Fired,
+            STATE_CANCELLED => TimerState::Cancelled,
+            _ => TimerState::Idle,
+        }
+    }
+}
+
+//Set in `Running` once timer is being dropped, so that callbacks, entered afterwards, do nothing.
+const RUNNING_CLOSED: usize = 1 << (usize::BITS - 1);
+//Set in `Running` while callback is being replaced, so that callbacks, entered afterwards, wait for it.
+const RUNNING_SWAP: usize = 1 << (usize::BITS - 2);
+const RUNNING_FLAGS: usize = RUNNING_CLOSED | RUNNING_SWAP;
+
+//Number of callbacks that are currently running, and number of ones among them, that wait in `wait_swap`.
+struct Running(AtomicUsize, AtomicUsize);
+
+impl Running {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self(AtomicUsize::new(0), AtomicUsize::new(0))
+    }
+
+    #[inline(always)]
+    fn enter(&self) -> RunningGuard<'_> {
+        let prev = self.0.fetch_add(1, Ordering::AcqRel);
+        RunningGuard(self, prev)
+    }
+
+    #[inline(always)]
+    //Prevents callbacks, entered afterwards, from invoking user's callback.
+    fn close(&self) {
+        self.0.fetch_or(RUNNING_CLOSED, Ordering::AcqRel);
+    }
+
+    //Makes callbacks, entered afterwards, wait until `end_swap`, then waits for callbacks, that are already running.
+    //
+    //Waits for `end_swap` first, if swap is already started by someone else.
+    //Must not be called from within callback, as it would wait for itself forever.
+    fn begin_swap(&self) {
+        let mut attempt = 0;
+        while self.0.fetch_or(RUNNING_SWAP, Ordering::SeqCst) & RUNNING_SWAP != 0 {
+            backoff(attempt);
+            attempt = attempt.saturating_add(1);
+        }
+
+        let mut attempt = 0;
+        loop {
+            //Waiting callbacks only accumulate until `end_swap`, hence once all entered ones wait, none runs.
+            let waiting = self.1.load(Ordering::SeqCst);
+            if self.0.load(Ordering::SeqCst) & !RUNNING_FLAGS == waiting {
+                break;
+            }
+            backoff(attempt);
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    #[inline(always)]
+    //Lets callbacks, waiting since `begin_swap`, proceed.
+    fn end_swap(&self) {
+        self.0.fetch_and(!RUNNING_SWAP, Ordering::SeqCst);
+    }
+
+    #[inline(always)]
+    //Returns whether any callback is in flight.
+    fn is_busy(&self) -> bool {
+        self.0.load(Ordering::Acquire) & !RUNNING_FLAGS != 0
+    }
+
+    //Waits until all callbacks, that are in flight, are finished.
+    //
+    //Must not be called from within callback, as it would wait for itself forever.
+    fn wait_idle(&self) {
+        let mut attempt = 0;
+        while self.0.load(Ordering::Acquire) & !RUNNING_FLAGS != 0 {
+            backoff(attempt);
+            attempt = attempt.saturating_add(1);
+        }
+    }
+}
+
+//Pauses busy wait, that has already been retried `attempt` times.
+//
+//Spins for a while, as callbacks are usually short, then yields and, once it takes long, sleeps
+//with period, doubling up to 1ms, so that waiting for long callback doesn't burn CPU.
+//Without `std` there is no way to block, hence it keeps spinning.
+fn backoff(attempt: u32) {
+    const SPIN_LIMIT: u32 = 6;
+    #[cfg(feature = "std")]
+    const YIELD_LIMIT: u32 = 16;
+
+    match attempt {
+        attempt if attempt < SPIN_LIMIT => for _ in 0..1 << attempt {
+            core::hint::spin_loop();
+        },
+        #[cfg(feature = "std")]
+        attempt if attempt < YIELD_LIMIT => std::thread::yield_now(),
+        #[cfg(feature = "std")]
+        attempt => {
+            let shift = core::cmp::min(attempt - YIELD_LIMIT, 10);
+            std::thread::sleep(time::Duration::from_micros(core::cmp::min(1 << shift, 1000)));
+        },
+        #[cfg(not(feature = "std"))]
+        _ => for _ in 0..1 << SPIN_LIMIT {
+            core::hint::spin_loop();
+        },
+    }
+}
+
+//Holds number of callbacks, that have been running on enter.
+struct RunningGuard<'a>(&'a Running, #[allow(unused)] usize);
+
+impl RunningGuard<'_> {
+    #[allow(unused)]
+    #[inline(always)]
+    //Returns whether some other callback has been already running on enter.
+    fn is_concurrent(&self) -> bool {
+        self.1 & !RUNNING_FLAGS != 0
+    }
+
+    #[inline(always)]
+    //Returns whether timer has been already dropping on enter.
+    fn is_closed(&self) -> bool {
+        self.1 & RUNNING_CLOSED != 0
+    }
+
+    //Waits until callback is replaced, if it has been being replaced on enter (see `Running::begin_swap`).
+    fn wait_swap(&self) {
+        let running = self.0;
+        if self.1 & RUNNING_SWAP != 0 {
+            loop {
+                running.1.fetch_add(1, Ordering::SeqCst);
+                let mut attempt = 0;
+                while running.0.load(Ordering::SeqCst) & RUNNING_SWAP != 0 {
+                    backoff(attempt);
+                    attempt = attempt.saturating_add(1);
+                }
+                running.1.fetch_sub(1, Ordering::SeqCst);
+                //Next swap may have begun meanwhile, counting on callback to keep waiting.
+                if running.0.load(Ordering::SeqCst) & RUNNING_SWAP == 0 {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for RunningGuard<'_> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.0.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Policy of periodic timer to handle intervals, missed while callback was running.
+///
+///Interval is missed when timer expires while its callback is still running (e.g. callback takes
+///longer than `interval`), or, on Mac, when OS merges multiple expirations into one (e.g. after system sleep).
+///Missed intervals are accounted on the next expiration, that runs callback accordingly, after which timer continues
+///at its regular schedule.
+///
+///Default is `Single`.
+pub enum CatchUp {
+    ///Callback is run once, regardless how many intervals were missed.
+    Single,
+    ///Callback is run once for each missed interval, in addition to the current one.
+    ///
+    ///Replays are still limited by `CatchUp::MAX_REPLAY`, so that long stall (e.g. system suspend) doesn't run
+    ///callback millions of times back to back. Use `Callback::with_count` to learn exact number of missed intervals.
+    ReplayAll,
+    ///Callback is run once for each missed interval, but no more than specified number of times, in addition to the current one.
+    ///
+    ///Bound is limited by `CatchUp::MAX_REPLAY`.
+    ReplayBounded(u32),
+}
+
+impl CatchUp {
+    ///Maximum number of missed intervals, replayed on single expiration.
+    ///
+    ///Intervals, missed beyond it, are dropped.
+    pub const MAX_REPLAY: u32 = 1000;
+}
+
+impl Default for CatchUp {
+    #[inline(always)]
+    fn default() -> Self {
+        CatchUp::Single
+    }
+}
+
+const CATCH_UP_SINGLE: u64 = 0;
+const CATCH_UP_ALL: u64 = 1;
+const CATCH_UP_BOUNDED: u64 = 2;
+
+//Catch-up policy and number of missed intervals, shared between timer and its callback.
+struct Pacing {
+    //Policy kind in upper half, bound in lower half.
+    policy: AtomicU64,
+    missed: AtomicU32,
+}
+
+impl Pacing {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self {
+            policy: AtomicU64::new(CATCH_UP_SINGLE << 32),
+            missed: AtomicU32::new(0),
+        }
+    }
+
+    #[inline(always)]
+    fn set_policy(&self, policy: CatchUp) {
+        let policy = match policy {
+            CatchUp::Single => CATCH_UP_SINGLE << 32,
+            CatchUp::ReplayAll => CATCH_UP_ALL << 32,
+            CatchUp::ReplayBounded(bound) => (CATCH_UP_BOUNDED << 32) | bound as u64,
+        };
+        self.policy.store(policy, Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn policy(&self) -> CatchUp {
+        let policy = self.policy.load(Ordering::Acquire);
+        match policy >> 32 {
+            CATCH_UP_ALL => CatchUp::ReplayAll,
+            CATCH_UP_BOUNDED => CatchUp::ReplayBounded(policy as u32),
+            _ => CatchUp::Single,
+        }
+    }
+
+    #[inline(always)]
+    //Forgets about missed intervals of previous schedule.
+    fn reset(&self) {
+        self.missed.store(0, Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn miss(&self, count: u32) {
+        let _ = self.missed.fetch_update(Ordering::AcqRel, Ordering::Acquire, |missed| Some(missed.saturating_add(count)));
+    }
+
+    #[allow(unused)]
+    #[inline(always)]
+    //Returns number of intervals missed since last call.
+    fn take_missed(&self) -> u32 {
+        self.missed.swap(0, Ordering::AcqRel)
+    }
+
+    #[inline(always)]
+    //Returns number of times to run callback on current expiration.
+    fn runs(&self) -> u32 {
+        let missed = self.take_missed();
+        let replay = match self.policy() {
+            CatchUp::Single => 0,
+            CatchUp::ReplayAll => core::cmp::min(missed, CatchUp::MAX_REPLAY),
+            CatchUp::ReplayBounded(bound) => core::cmp::min(missed, core::cmp::min(bound, CatchUp::MAX_REPLAY)),
+        };
+        replay + 1
+    }
+}
+
+#[cfg(feature = "std")]
+//Instant after which periodic timer stops itself, in nanoseconds since `latency_base`, 0 if there is none.
+struct Deadline(AtomicU64);
+
+#[cfg(feature = "std")]
+impl Deadline {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    #[inline(always)]
+    fn set(&self, deadline: Option<std::time::Instant>) {
+        let deadline = match deadline {
+            //Deadline, that precedes base, is already expired.
+            Some(deadline) => {
+                let nanos = deadline.saturating_duration_since(latency_base()).as_nanos();
+                core::cmp::max(core::cmp::min(nanos, u64::max_value().into()) as u64, 1)
+            },
+            None => 0,
+        };
+        self.0.store(deadline, Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn is_expired(&self) -> bool {
+        match self.0.load(Ordering::Acquire) {
+            0 => false,
+            deadline => latency_now() >= deadline,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+//Wakes threads, blocked in `Timer::wait`, once timer fires or gets cancelled.
+struct Waiters {
+    //Number of blocked threads, so that callback doesn't touch mutex when nobody waits.
+    count: AtomicUsize,
+    lock: std::sync::Mutex<()>,
+    cond: std::sync::Condvar,
+}
+
+#[cfg(feature = "std")]
+impl Waiters {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            lock: std::sync::Mutex::new(()),
+            cond: std::sync::Condvar::new(),
+        }
+    }
+
+    #[inline(always)]
+    //Must be called after `fires` or `state` is updated.
+    fn notify(&self) {
+        core::sync::atomic::fence(Ordering::SeqCst);
+        if self.count.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        //Taking lock ensures that waiter either observes update or is already blocked.
+        drop(self.lock.lock().unwrap_or_else(|error| error.into_inner()));
+        self.cond.notify_all();
+    }
+
+    //Blocks until `fires` changes, returning `false` if timer is not armed or gets cancelled.
+    fn wait(&self, fires: &AtomicU64, state: &Lifecycle) -> bool {
+        let mut guard = self.lock.lock().unwrap_or_else(|error| error.into_inner());
+        self.count.fetch_add(1, Ordering::SeqCst);
+        core::sync::atomic::fence(Ordering::SeqCst);
+
+        let start = fires.load(Ordering::Acquire);
+        let mut result = state.get() == TimerState::Armed;
+        while result {
+            if fires.load(Ordering::Acquire) != start {
+                break;
+            }
+            //One-shot timer is marked as fired right before its counter is incremented.
+            match state.get() {
+                TimerState::Armed | TimerState::Fired => (),
+                TimerState::Idle | TimerState::Cancelled => result = false,
+            }
+            if result {
+                guard = self.cond.wait(guard).unwrap_or_else(|error| error.into_inner());
+            }
+        }
+
+        self.count.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Clock, that is used by POSIX timer.
+///
+///Available on all platforms for the sake of `TimerBuilder`, but only POSIX timer can select clock.
+pub enum Clock {
+    ///`CLOCK_MONOTONIC`, which is not affected by changes of system time, but stops during system suspend.
+    ///
+    ///Used by `Timer::new`.
+    Monotonic,
+    ///`CLOCK_REALTIME`, which is system time.
+    ///
+    ///Relative timeouts are not affected by its changes, but `schedule_once_at_raw` fires, when system time reaches it.
+    Realtime,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///`CLOCK_BOOTTIME`, which is the same as `Monotonic`, but keeps counting during system suspend.
+    Boottime,
+    ///`CLOCK_PROCESS_CPUTIME_ID`, which is CPU time, consumed by all threads of the process.
+    ///
+    ///Timer fires once process consumes specified amount of CPU time, rather than once wall time passes, which
+    ///makes it suitable for profiling or as watchdog of busy loop.
+    ///Not supported on Win/Mac, where `TimerBuilder` fails with `TimerError::Unsupported`.
+    ProcessCpuTime,
+    ///`CLOCK_THREAD_CPUTIME_ID`, which is CPU time, consumed by the thread, that creates timer.
+    ///
+    ///Same as `ProcessCpuTime`, but only accounts the creating thread.
+    ///Not supported on Win/Mac, where `TimerBuilder` fails with `TimerError::Unsupported`.
+    ThreadCpuTime,
+}
+
+impl Clock {
+    #[allow(unused)]
+    #[inline(always)]
+    //Returns whether clock measures CPU time, which only POSIX timer supports.
+    const fn is_cpu_time(self) -> bool {
+        match self {
+            Clock::ProcessCpuTime | Clock::ThreadCpuTime => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Quality of service class of the global dispatch queue, on which timer's callback is invoked.
+///
+///Available on all platforms for the sake of `TimerBuilder`, but only Mac has notion of QoS.
+pub enum QosClass {
+    ///Work, that interacts with user, such as animations.
+    UserInteractive,
+    ///Work, that user is waiting for.
+    UserInitiated,
+    ///Default class, used by `Timer::new`.
+    Default,
+    ///Long running work, that user is not actively waiting for.
+    Utility,
+    ///Maintenance work, that is invisible to user.
+    Background,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Features, supported by current platform's timer.
+///
+///Allows portable code to degrade gracefully, when some feature is not available.
+pub struct Capabilities {
+    ///OS can report time remaining until next expiration, otherwise `Timer::time_remaining` is approximate.
+    pub can_query_remaining: bool,
+    ///OS allows to choose clock, that timer uses.
+    pub can_select_clock: bool,
+    ///OS allows to delay expiration in order to coalesce timers (see `set_global_coalescing`).
+    pub supports_leeway: bool,
+    ///OS never runs callbacks of the same timer concurrently.
+    pub callbacks_serialized: bool,
+    ///Smallest `interval` that OS can honor.
+    pub min_resolution: time::Duration,
+    ///Timer can be scheduled at absolute clock value (see `schedule_once_at_raw`).
+    pub supports_absolute: bool,
+}
+
+#[cfg(feature = "std")]
+//Returns instant, relatively to which latency is tracked.
+fn latency_base() -> std::time::Instant {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    static mut BASE: Option<std::time::Instant> = None;
+
+    unsafe {
+        //`BASE` is written only once, before `INIT` completes, and only read afterwards.
+        INIT.call_once(|| BASE = Some(std::time::Instant::now()));
+        match BASE {
+            Some(base) => base,
+            None => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[inline(always)]
+fn latency_now() -> u64 {
+    latency_base().elapsed().as_nanos() as u64
+}
+
+#[cfg(feature = "std")]
+//Tracks how late timer fires, in nanoseconds since `latency_base`.
+struct Latency {
+    //Instant when timer is expected to fire next, 0 if not tracked.
+    expected: AtomicU64,
+    interval: AtomicU64,
+    //Latency of the last fire plus one, 0 if timer has not fired yet.
+    last: AtomicU64,
+    //Instant, when the last fire was scheduled, plus one, 0 if it is not tracked.
+    due: AtomicU64,
+}
+
+#[cfg(feature = "std")]
+impl Latency {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self {
+            expected: AtomicU64::new(0),
+            interval: AtomicU64::new(0),
+            last: AtomicU64::new(0),
+            due: AtomicU64::new(0),
+        }
+    }
+
+    #[inline(always)]
+    fn arm(&self, timeout: time::Duration, interval: time::Duration) {
+        let expected = latency_now().saturating_add(timeout.as_nanos() as u64);
+        self.interval.store(interval.as_nanos() as u64, Ordering::Release);
+        //0 is reserved for untracked timer
+        self.expected.store(core::cmp::max(expected, 1), Ordering::Release);
+    }
+
+    #[allow(unused)]
+    #[inline(always)]
+    //Stops tracking, when expected fire time is unknown.
+    fn clear(&self) {
+        self.expected.store(0, Ordering::Release);
+    }
+
+    fn fire(&self) {
+        let now = latency_now();
+        let expected = self.expected.load(Ordering::Acquire);
+        if expected == 0 {
+            self.due.store(0, Ordering::Release);
+            return;
+        }
+
+        let late = now.saturating_sub(expected);
+        let interval = self.interval.load(Ordering::Acquire);
+        let late = match interval {
+            0 => late,
+            //Periodic timer is late relative to the latest point of its schedule.
+            interval => {
+                let late = late % interval;
+                let _ = self.expected.compare_exchange(expected, now - late + interval, Ordering::AcqRel, Ordering::Acquire);
+                late
+            },
+        };
+
+        self.due.store((now - late).saturating_add(1), Ordering::Release);
+        self.last.store(late.saturating_add(1), Ordering::Release);
+    }
+
+    #[allow(unused)]
+    //Approximates time until the next fire, assuming periodic timer keeps to its schedule.
+    fn remaining(&self) -> Option<time::Duration> {
+        let expected = self.expected.load(Ordering::Acquire);
+        if expected == 0 {
+            return None;
+        }
+
+        let now = latency_now();
+        if now < expected {
+            return Some(time::Duration::from_nanos(expected - now));
+        }
+
+        match self.interval.load(Ordering::Acquire) {
+            //One-shot timer has already expired.
+            0 => None,
+            interval => Some(time::Duration::from_nanos(interval - (now - expected) % interval)),
+        }
+    }
+
+    #[allow(unused)]
+    //Approximates instant of the next fire, assuming periodic timer keeps to its schedule.
+    fn next(&self) -> Option<std::time::Instant> {
+        let expected = self.expected.load(Ordering::Acquire);
+        if expected == 0 {
+            return None;
+        }
+
+        let now = latency_now();
+        let next = match self.interval.load(Ordering::Acquire) {
+            interval if interval > 0 && now >= expected => now + (interval - (now - expected) % interval),
+            _ => expected,
+        };
+        latency_base().checked_add(time::Duration::from_nanos(next))
+    }
+
+    #[inline(always)]
+    fn last(&self) -> Option<time::Duration> {
+        match self.last.load(Ordering::Acquire) {
+            0 => None,
+            late => Some(time::Duration::from_nanos(late - 1)),
+        }
+    }
+
+    #[inline(always)]
+    fn due(&self) -> Option<std::time::Instant> {
+        match self.due.load(Ordering::Acquire) {
+            0 => None,
+            due => latency_base().checked_add(time::Duration::from_nanos(due - 1)),
+        }
+    }
+}
+
+//Schedule, requested by the most recent arm of the timer.
+struct Armed {
+    //Interval in nanoseconds, 0 for one shot timer.
+    interval: AtomicU64,
+    //Number of alarms, requested by `schedule_count`, that is applied by the next arm.
+    limit: AtomicU32,
+    //Number of alarms left plus one, 0 if timer is not limited.
+    remaining: AtomicU64,
+    //Time until the next alarm of paused timer in nanoseconds plus one, 0 if timer is not paused.
+    paused: AtomicU64,
+    #[cfg(feature = "std")]
+    //Instant of the most recent arm in nanoseconds since `latency_base` plus one, 0 if timer has not been armed yet.
+    at: AtomicU64,
+}
+
+impl Armed {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self {
+            interval: AtomicU64::new(0),
+            limit: AtomicU32::new(0),
+            remaining: AtomicU64::new(0),
+            paused: AtomicU64::new(0),
+            #[cfg(feature = "std")]
+            at: AtomicU64::new(0),
+        }
+    }
+
+    #[inline(always)]
+    fn arm(&self, interval: time::Duration) {
+        self.interval.store(core::cmp::min(interval.as_nanos(), u64::max_value().into()) as u64, Ordering::Release);
+        let remaining = match self.limit.swap(0, Ordering::AcqRel) {
+            0 => 0,
+            limit => u64::from(limit) + 1,
+        };
+        self.remaining.store(remaining, Ordering::Release);
+        self.paused.store(0, Ordering::Release);
+        #[cfg(feature = "std")]
+        self.at.store(latency_now().saturating_add(1), Ordering::Release);
+    }
+
+    #[inline(always)]
+    //Limits number of alarms, starting with the next arm.
+    fn limit(&self, count: u32) {
+        self.limit.store(count, Ordering::Release);
+    }
+
+    #[inline(always)]
+    //Accounts alarm of timer, returning whether it is the last one, or `None` if timer has already run out of them.
+    fn take_alarm(&self) -> Option<bool> {
+        match self.remaining.fetch_update(Ordering::AcqRel, Ordering::Acquire, |remaining| match remaining {
+            0 | 1 => None,
+            remaining => Some(remaining - 1),
+        }) {
+            Ok(remaining) => Some(remaining == 2),
+            Err(0) => Some(false),
+            Err(_) => None,
+        }
+    }
+
+    #[inline(always)]
+    //Remembers time until the next alarm of timer, that is being paused.
+    fn pause(&self, remaining: time::Duration) {
+        let remaining = core::cmp::min(remaining.as_nanos(), u128::from(u64::max_value() - 1)) as u64;
+        self.paused.store(remaining + 1, Ordering::Release);
+    }
+
+    #[inline(always)]
+    //Returns time until the next alarm of paused timer, if it is paused.
+    fn take_paused(&self) -> Option<time::Duration> {
+        match self.paused.swap(0, Ordering::AcqRel) {
+            0 => None,
+            remaining => Some(time::Duration::from_nanos(remaining - 1)),
+        }
+    }
+
+    #[inline(always)]
+    fn interval(&self) -> time::Duration {
+        time::Duration::from_nanos(self.interval.load(Ordering::Acquire))
+    }
+
+    #[cfg(feature = "std")]
+    #[inline(always)]
+    fn at(&self) -> Option<std::time::Instant> {
+        match self.at.load(Ordering::Acquire) {
+            0 => None,
+            at => latency_base().checked_add(time::Duration::from_nanos(at - 1)),
+        }
+    }
+}
+
+//Divisor of the interval, used as default leeway when coalescing is enabled globally.
+const COALESCING_FRACTION: u32 = 10;
+
+static GLOBAL_COALESCING: AtomicBool = AtomicBool::new(false);
+
+///Enables or disables coalescing for all timers in the process.
+///
+///When enabled, every subsequent schedule allows OS to delay timer by up to 10% of the `interval`
+///(or of the `timeout` for one shot timers), letting it to batch timers and save power.
+///Timers that are already scheduled are not affected.
+///
+///Per timer settings take precedence over this switch.
+///
+///Supported on Win/Mac, where it sets window length and leeway respectively.
+///On other platforms it is no-op.
+pub fn set_global_coalescing(enabled: bool) {
+    GLOBAL_COALESCING.store(enabled, Ordering::Release);
+}
+
+#[inline]
+///Returns whether coalescing is enabled globally.
+pub fn global_coalescing() -> bool {
+    GLOBAL_COALESCING.load(Ordering::Acquire)
+}
+
+#[allow(unused)]
+//Returns leeway to use when user did not specify it explicitly.
+fn coalescing_leeway(timeout: time::Duration, interval: time::Duration) -> time::Duration {
+    if !global_coalescing() {
+        return time::Duration::from_secs(0);
+    }
+
+    match interval.as_nanos() {
+        0 => timeout / COALESCING_FRACTION,
+        _ => interval / COALESCING_FRACTION,
+    }
+}
+
+#[cfg(feature = "std")]
+//Longest pause between attempts to create OS timer.
+const MAX_RETRY_PAUSE_MS: u64 = 64;
+
+//Calls `create` until it succeeds, but no more than `attempts` times (at least once).
+//
+//`create` returns `Err(true)` on transient failure, that is worth retrying, and `Err(false)` otherwise.
+//Pause between attempts starts at 1ms and doubles with each attempt.
+//Without `std` there is no way to sleep, and spinning for milliseconds would only burn CPU, hence attempts are made back to back.
+fn retry<T, F: FnMut() -> Result<T, bool>>(attempts: u32, mut create: F) -> Option<T> {
+    #[cfg(feature = "std")]
+    let mut pause_ms = 1;
+    let mut attempt = 1;
 
-#[cfg(windows)]
-mod win32;
-#[cfg(windows)]
-pub use win32::*;
+    loop {
+        match create() {
+            Ok(result) => return Some(result),
+            Err(true) if attempt < attempts => {
+                #[cfg(feature = "std")]
+                {
+                    std::thread::sleep(time::Duration::from_millis(pause_ms));
+                    pause_ms = core::cmp::min(pause_ms * 2, MAX_RETRY_PAUSE_MS);
+                }
+                attempt += 1;
+            },
+            Err(_) => return None,
+        }
+    }
+}
 
-#[cfg(any(target_os = "macos", target_os = "ios"))]
-mod apple;
-#[cfg(any(target_os = "macos", target_os = "ios"))]
-pub use apple::*;
+#[allow(unused)]
+#[inline(always)]
+//Limits leeway of periodic timer by its interval, as otherwise timer would fire sporadically.
+fn clamp_leeway(leeway: time::Duration, interval: time::Duration) -> time::Duration {
+    match interval.as_nanos() {
+        0 => leeway,
+        _ => core::cmp::min(leeway, interval),
+    }
+}
 
-#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
-mod posix;
-#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
-pub use posix::*;
+//Converts frequency into interval, if it is valid.
+fn hz_interval(hz: f64) -> Option<time::Duration> {
+    if !hz.is_finite() || hz <= 0.0 {
+        return None;
+    }
 
+    match time::Duration::try_from_secs_f64(1.0 / hz) {
+        Ok(interval) if interval.as_nanos() > 0 => Some(interval),
+        _ => None,
+    }
+}
+
+//Timer can be moved and shared between threads, as:
+//
+//- OS handle (POSIX timer id, thread pool timer or dispatch source) can be armed and cancelled from any thread.
+//- Handle and context are atomics, which are set only once by `init` (which is guarded by compare and swap on context).
+//- `data` cell is only written by the thread, that won `init`, before handle is published, or by `set_callback`, while it holds
+//  swap of `running`, and otherwise is read on drop only.
+//- Callback is required to be `Send`, as it is invoked on OS thread (raw callbacks are `unsafe` to create, leaving it to user).
 unsafe impl Send for Timer {}
 unsafe impl Sync for Timer {}
 
+impl fmt::Debug for Timer {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let is_init = self.is_init();
+        fmt.debug_struct("Timer")
+            .field("init", &is_init)
+            .field("scheduled", &(is_init && self.is_scheduled()))
+            .field("handle", &self.as_raw())
+            .finish()
+    }
+}
+
 impl Timer {
+    ///Smallest duration, that timer can represent: 1ms on Win (and for software timer), 1ns elsewhere.
+    ///
+    ///Durations are truncated to it (though non-zero `interval` on Win is rounded up), hence portable code
+    ///can round its durations up to multiple of it in order to not request precision, that OS cannot honor.
+    ///Same as `Capabilities::min_resolution`.
+    pub const RESOLUTION: time::Duration = Self::capabilities().min_resolution;
+
+    #[inline(always)]
+    ///Returns `RESOLUTION` of timer on current platform.
+    pub const fn resolution() -> time::Duration {
+        Self::RESOLUTION
+    }
+
+    ///Creates new timer and schedules it to alarm once after `timeout` passes.
+    ///
+    ///Returns error if timer cannot be created (see `try_new`) or scheduled (see `try_schedule_once`).
+    pub fn start_once(cb: impl Into<Callback>, timeout: time::Duration) -> Result<Self, TimerError> {
+        let timer = Self::try_new(cb)?;
+        timer.try_schedule_once(timeout)?;
+        Ok(timer)
+    }
+
+    ///Creates new timer and schedules it to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Returns error if timer cannot be created (see `try_new`) or scheduled (see `try_schedule_interval`).
+    pub fn start_interval(cb: impl Into<Callback>, timeout: time::Duration, interval: time::Duration) -> Result<Self, TimerError> {
+        let timer = Self::try_new(cb)?;
+        timer.try_schedule_interval(timeout, interval)?;
+        Ok(timer)
+    }
+
     #[inline(always)]
     ///Creates new schedule
     pub const fn schedule(&self) -> Schedule<'_> {
@@ -36,20 +954,204 @@ impl Timer {
             timer: self,
             timeout: time::Duration::from_secs(0),
             interval: time::Duration::from_secs(0),
+            leeway: None,
         }
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
     #[inline(always)]
     ///Schedules timer to alarm once after `timeout` passes.
     ///
     ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
     ///To prevent that user must `cancel` timer first.
     ///
+    ///Once fired, timer can be scheduled again, re-using the same OS timer.
+    ///
+    ///`timeout`, that doesn't fit into OS timer, is truncated (see `schedule_interval`).
+    ///Use `try_schedule_once` to get `TimerError::Overflow` instead.
+    ///
     ///Returns `true` if successfully set, otherwise on error returns `false`
     pub fn schedule_once(&self, timeout: time::Duration) -> bool {
         self.schedule_interval(timeout, time::Duration::from_secs(0))
     }
+
+    #[inline(always)]
+    ///Schedules timer to alarm once after `timeout` passes, allowing OS to delay it by up to `leeway`
+    ///in order to coalesce it with other timers.
+    ///
+    ///Overrides global coalescing setting. See `schedule_interval_with_leeway` for details.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_once_with_leeway(&self, timeout: time::Duration, leeway: time::Duration) -> bool {
+        self.schedule_interval_with_leeway(timeout, time::Duration::from_secs(0), leeway)
+    }
+
+    #[inline(always)]
+    ///Schedules timer to alarm once after `millis` milliseconds pass.
+    ///
+    ///Same as `schedule_once(Duration::from_millis(millis))`.
+    pub fn schedule_once_ms(&self, millis: u64) -> bool {
+        self.schedule_once(time::Duration::from_millis(millis))
+    }
+
+    #[inline(always)]
+    ///Schedules timer to alarm periodically every `interval_ms` milliseconds with initial alarm after `timeout_ms` milliseconds.
+    ///
+    ///Same as `schedule_interval` with both durations constructed via `Duration::from_millis`.
+    pub fn schedule_interval_ms(&self, timeout_ms: u64, interval_ms: u64) -> bool {
+        self.schedule_interval(time::Duration::from_millis(timeout_ms), time::Duration::from_millis(interval_ms))
+    }
+
+    #[inline(always)]
+    ///Schedules timer to alarm once after `micros` microseconds pass.
+    ///
+    ///Same as `schedule_once(Duration::from_micros(micros))`.
+    pub fn schedule_once_us(&self, micros: u64) -> bool {
+        self.schedule_once(time::Duration::from_micros(micros))
+    }
+
+    #[inline(always)]
+    ///Schedules timer to alarm periodically every `interval_us` microseconds with initial alarm after `timeout_us` microseconds.
+    ///
+    ///Same as `schedule_interval` with both durations constructed via `Duration::from_micros`.
+    ///Note that Win rounds durations to milliseconds.
+    pub fn schedule_interval_us(&self, timeout_us: u64, interval_us: u64) -> bool {
+        self.schedule_interval(time::Duration::from_micros(timeout_us), time::Duration::from_micros(interval_us))
+    }
+
+    #[inline]
+    ///Schedules timer to alarm immediately, and then periodically with `interval`.
+    ///
+    ///Note that first alarm still happens asynchronously, on the timer's thread (or queue on Mac).
+    ///It is requested with the smallest possible timeout (1ns, which is due time of zero on Win and current time on Mac),
+    ///hence periodic alarms are counted from it: first of them happens `interval` after the immediate one, never before it.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_immediate(&self, interval: time::Duration) -> bool {
+        //Zero timeout disarms POSIX timer, hence use the smallest possible one.
+        self.schedule_interval(time::Duration::from_nanos(1), interval)
+    }
+
+    #[cfg(feature = "std")]
+    ///Schedules timer to alarm at `first` instant, and then periodically with `interval`.
+    ///
+    ///If `first` is already in past, timer alarms as soon as possible.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_from(&self, first: std::time::Instant, interval: time::Duration) -> bool {
+        let timeout = first.saturating_duration_since(std::time::Instant::now());
+        //Zero timeout disarms POSIX timer, hence use the smallest possible one.
+        self.schedule_interval(core::cmp::max(timeout, time::Duration::from_nanos(1)), interval)
+    }
+
+    #[cfg(feature = "std")]
+    ///Schedules timer to alarm once at `deadline` instant.
+    ///
+    ///If `deadline` is already in past, timer alarms as soon as possible.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_at(&self, deadline: std::time::Instant) -> bool {
+        let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+        //Zero timeout disarms POSIX timer, hence use the smallest possible one.
+        self.schedule_once(core::cmp::max(timeout, time::Duration::from_nanos(1)))
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`, unless it is already pending.
+    ///
+    ///Unlike `schedule_interval`, that silently replaces pending schedule (or, on POSIX, leads to undefined behaviour),
+    ///it fails with `TimerError::Pending`, if timer is scheduled and hasn't expired yet (periodic timer is pending until cancelled).
+    ///
+    ///Note that check is not atomic with scheduling itself, hence concurrent calls must be synchronized by user.
+    pub fn schedule_interval_strict(&self, timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+
+        //Mac reports one shot timer as scheduled even after it fired, so rely on lifecycle as well.
+        if self.is_scheduled() && self.state() == TimerState::Armed {
+            return Err(TimerError::Pending);
+        }
+
+        self.try_schedule_interval(timeout, interval)
+    }
+
+    #[inline]
+    ///Cancels timer, if it is pending, and then schedules it to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Unlike `schedule_interval`, behaviour of re-scheduling pending timer is the same on all platforms:
+    ///previous schedule never fires after new one is set.
+    ///
+    ///Callback, that is already running, is not waited for, hence it can be called from within timer's own callback
+    ///(e.g. `Callback::with_timer`) on every platform.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn reschedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        self.cancel_nowait();
+        self.schedule_interval(timeout, interval)
+    }
+
+    #[inline(always)]
+    //Cancels timer without waiting for running callbacks, so that it is safe to call from within timer's own callback.
+    //
+    //Only Win waits on `cancel`, hence elsewhere it is the same.
+    fn cancel_nowait(&self) {
+        #[cfg(all(windows, not(feature = "software")))]
+        self.cancel_async();
+        #[cfg(not(all(windows, not(feature = "software"))))]
+        self.cancel();
+    }
+
+    ///Schedules timer to alarm periodically with frequency of `hz` times per second, with initial alarm of `first`.
+    ///
+    ///Interval is rounded to the resolution of underlying OS API, hence at high frequencies actual rate may
+    ///differ noticeably (e.g. Win uses milliseconds, so 60Hz becomes 16ms interval, which is 62.5Hz).
+    ///
+    ///Returns `false` if `hz` is not positive finite number or too small to be represented as interval,
+    ///otherwise behaves as `schedule_interval`.
+    pub fn schedule_hz(&self, first: time::Duration, hz: f64) -> bool {
+        match hz_interval(hz) {
+            Some(interval) => self.schedule_interval(first, interval),
+            None => false,
+        }
+    }
+
+    ///Schedules timer as `schedule_interval` does, returning guard, that cancels timer once dropped.
+    ///
+    ///It allows to arm timer for duration of some scope (e.g. watchdog of a request), while timer itself
+    ///lives on and can be armed again.
+    ///
+    ///Guard doesn't wait for running callback on drop, hence it can be dropped from within timer's own callback
+    ///on every platform, but callback, that is already running, may still complete afterwards.
+    ///
+    ///On failure, returns `None`
+    pub fn arm_scoped(&self, timeout: time::Duration, interval: time::Duration) -> Option<TimerGuard<'_>> {
+        match self.schedule_interval(timeout, interval) {
+            true => Some(TimerGuard {
+                timer: self,
+            }),
+            false => None,
+        }
+    }
+}
+
+#[must_use = "Timer is cancelled as soon as guard is dropped"]
+///Guard, that cancels timer on drop, returned by `Timer::arm_scoped`.
+pub struct TimerGuard<'a> {
+    timer: &'a Timer,
+}
+
+impl TimerGuard<'_> {
+    #[inline(always)]
+    ///Returns timer, that is armed by this guard.
+    pub fn timer(&self) -> &Timer {
+        self.timer
+    }
+}
+
+impl Drop for TimerGuard<'_> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.timer.cancel_nowait();
+    }
 }
 
 ///Timer's schedule
@@ -57,6 +1159,7 @@ pub struct Schedule<'a> {
     timer: &'a Timer,
     timeout: time::Duration,
     interval: time::Duration,
+    leeway: Option<time::Duration>,
 }
 
 impl<'a> Schedule<'a> {
@@ -77,36 +1180,531 @@ impl<'a> Schedule<'a> {
         self
     }
 
+    #[inline(always)]
+    ///Sets `leeway`, by which OS may delay timer in order to coalesce it with others.
+    ///
+    ///Overrides global coalescing setting. See `Timer::schedule_interval_with_leeway` for details.
+    pub const fn leeway(mut self, leeway: time::Duration) -> Self {
+        self.leeway = Some(leeway);
+        self
+    }
+
     #[inline(always)]
     ///Schedules timer execution, using provided settings.
     ///
     ///Returns `true` if successfully set, otherwise on error returns `false`
     pub fn schedule(&self) -> bool {
-        self.timer.schedule_interval(self.timeout, self.interval)
+        match self.leeway {
+            Some(leeway) => self.timer.schedule_interval_with_leeway(self.timeout, self.interval, leeway),
+            None => self.timer.schedule_interval(self.timeout, self.interval),
+        }
+    }
+}
+
+///Timer's configuration, which allows to set platform specific options in portable way.
+///
+///Options, that are not applicable to the current platform, are ignored:
+///
+///- `clock` is only used on POSIX, though CPU time clocks make `build` fail elsewhere, as timer would measure different time;
+///- `qos` is only used on Mac;
+///- `leeway` is only used on Win/Mac (see `Capabilities::supports_leeway`).
+pub struct TimerBuilder {
+    callback: Option<Callback>,
+    #[allow(unused)]
+    clock: Option<Clock>,
+    #[allow(unused)]
+    qos: Option<QosClass>,
+    #[allow(unused)]
+    leeway: Option<time::Duration>,
+}
+
+impl TimerBuilder {
+    #[inline(always)]
+    ///Creates builder with default options and no callback.
+    pub const fn new() -> Self {
+        Self {
+            callback: None,
+            clock: None,
+            qos: None,
+            leeway: None,
+        }
+    }
+
+    #[inline(always)]
+    ///Sets callback, invoked when timer expires.
+    pub fn callback(mut self, cb: impl Into<Callback>) -> Self {
+        self.callback = Some(cb.into());
+        self
+    }
+
+    #[inline(always)]
+    ///Sets clock, used by timer.
+    ///
+    ///Only used on POSIX, see `Timer::new_with_clock`.
+    pub const fn clock(mut self, clock: Clock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    #[inline(always)]
+    ///Sets QoS class of the queue, on which callback is invoked.
+    ///
+    ///Only used on Mac, see `Timer::new_with_qos`.
+    pub const fn qos(mut self, qos: QosClass) -> Self {
+        self.qos = Some(qos);
+        self
+    }
+
+    #[inline(always)]
+    ///Sets default `leeway`, used by every schedule, that doesn't specify leeway explicitly.
+    ///
+    ///Overrides global coalescing setting. See `Timer::schedule_interval_with_leeway` for details.
+    ///
+    ///Only used on Win/Mac.
+    pub const fn leeway(mut self, leeway: time::Duration) -> Self {
+        self.leeway = Some(leeway);
+        self
+    }
+
+    ///Creates timer, using provided options.
+    ///
+    ///Returns `TimerError::Uninitialized` if callback is not set, or `TimerError::Os` if OS fails to create timer.
+    pub fn build(self) -> Result<Timer, TimerError> {
+        Timer::from_builder(self)
+    }
+}
+
+impl Default for TimerBuilder {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+///Heap allocated callback, that can be sent to timer's thread.
+pub type BoxedCallback = Box<dyn FnMut() + Send>;
+
+#[cfg(feature = "alloc")]
+impl Callback {
+    ///Creates callback using closure, that runs at most once, storing it on heap.
+    ///
+    ///Closure is consumed by its first run, hence if timer is periodic (or scheduled again), only the first
+    ///expiration runs it, while subsequent ones are no-op.
+    pub fn once<F: 'static + FnOnce() + Send>(cb: F) -> Self {
+        let mut cb = Some(cb);
+        Self::closure(move || {
+            if let Some(cb) = cb.take() {
+                cb()
+            }
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Callback {
+    ///Creates callback using plain rust function, that receives `ctx` by reference.
+    ///
+    ///`ctx` is stored on heap and owned by timer, hence it is dropped together with timer.
+    pub fn with_context<T: 'static + Send>(cb: fn(&T), ctx: T) -> Self {
+        Self::closure(move || cb(&ctx))
+    }
+
+    ///Creates callback using plain rust function, that receives `state` shared via `Arc`.
+    ///
+    ///Unlike `with_context`, owner of timer can keep clone of `state` to observe it, while timer
+    ///holds its own reference, releasing it on drop, hence the same `state` can be shared by multiple timers.
+    pub fn arc<T: 'static + Send + Sync>(state: Arc<T>, cb: fn(&T)) -> Self {
+        Self::closure(move || cb(&state))
+    }
+
+    #[cfg(feature = "std")]
+    ///Creates callback using closure, that receives instant of its run and instant, when it was scheduled to run.
+    ///
+    ///Run instant is taken on entry to callback, while scheduled one is `Timer::last_deadline`, which is
+    ///`None` if crate doesn't track timer's schedule (e.g. Mac timer, scheduled with raw clock value).
+    ///Difference between them is how late timer fired, including time to dispatch callback.
+    pub fn with_instant<F: 'static + FnMut(std::time::Instant, Option<std::time::Instant>) + Send>(mut cb: F) -> Self {
+        Self::with_timer(move |timer| {
+            let now = std::time::Instant::now();
+            cb(now, timer.last_deadline())
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+//Covers function pointers and `BoxedCallback` as well, hence separate impls for them would conflict with it.
+impl<F: 'static + FnMut() + Send> From<F> for Callback {
+    #[inline(always)]
+    ///Creates callback using closure, storing it on heap.
+    ///
+    ///Besides closures, it accepts plain functions (`fn()`) and `BoxedCallback`, though they are stored on heap too,
+    ///use `Callback::plain` to avoid allocation.
+    fn from(cb: F) -> Self {
+        Self::closure(cb)
+    }
+}
+
+#[cfg(feature = "std")]
+//Timers, armed by `after`, that are kept alive until they fire, with flag, set once their callback returns.
+static AFTER_TIMERS: std::sync::Mutex<alloc::vec::Vec<(u64, Timer, Arc<AtomicBool>)>> = std::sync::Mutex::new(alloc::vec::Vec::new());
+#[cfg(feature = "std")]
+static AFTER_ID: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "std")]
+#[must_use = "Dropping handle cancels timer, use `After::detach` to let it fire"]
+///Handle of the timer, armed by `after`.
+///
+///Dropping it cancels timer, unless it is detached.
+pub struct After {
+    id: u64,
+}
+
+#[cfg(feature = "std")]
+impl After {
+    #[inline(always)]
+    ///Lets timer fire on its own, giving up ability to cancel it.
+    pub fn detach(self) {
+        core::mem::forget(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for After {
+    fn drop(&mut self) {
+        let timer = {
+            let mut timers = AFTER_TIMERS.lock().unwrap_or_else(|error| error.into_inner());
+            timers.iter().position(|(id, _, _)| *id == self.id).map(|idx| timers.swap_remove(idx))
+        };
+        //Timer waits for its callback on drop, so it is dropped outside of the lock.
+        drop(timer);
+    }
+}
+
+#[cfg(feature = "std")]
+//Releases timers of `after` once their callback completes, as timer cannot be dropped from within its own callback.
+fn after_reaper() -> Option<std::thread::Thread> {
+    static REAPER: std::sync::Mutex<Option<std::thread::Thread>> = std::sync::Mutex::new(None);
+
+    let mut reaper = REAPER.lock().unwrap_or_else(|error| error.into_inner());
+    if reaper.is_none() {
+        let thread = std::thread::Builder::new().name("os-timer-after".into()).spawn(|| loop {
+            std::thread::park();
+            let expired: alloc::vec::Vec<_> = {
+                let mut timers = AFTER_TIMERS.lock().unwrap_or_else(|error| error.into_inner());
+                //Timer is `Fired` already while its callback runs, hence only completed ones are released.
+                let (expired, pending) = timers.drain(..).partition(|(_, _, is_done)| is_done.load(Ordering::Acquire));
+                *timers = pending;
+                expired
+            };
+            //Timer waits for its callback on drop, so it is dropped outside of the lock.
+            drop(expired);
+        });
+        *reaper = Some(thread.ok()?.thread().clone());
+    }
+    reaper.clone()
+}
+
+#[cfg(feature = "std")]
+//Marks timer of `after` as completed, even if its callback panics.
+struct AfterDone<'a> {
+    is_done: &'a AtomicBool,
+    reaper: &'a std::thread::Thread,
+}
+
+#[cfg(feature = "std")]
+impl Drop for AfterDone<'_> {
+    fn drop(&mut self) {
+        self.is_done.store(true, Ordering::Release);
+        self.reaper.unpark();
+    }
+}
+
+#[cfg(feature = "std")]
+///Runs `cb` once after `delay` passes.
+///
+///Timer is owned by internal registry, so that there is no need to keep it alive.
+///Dropping returned handle cancels timer, unless it is detached via `After::detach`.
+///
+///Timer cannot be dropped from within its own callback, hence once callback returns, timer is released
+///by background thread, that is spawned on first call.
+///
+///Returns `None` if OS fails to create or schedule timer, or to spawn background thread.
+pub fn after<F: 'static + FnOnce() + Send>(delay: time::Duration, cb: F) -> Option<After> {
+    let reaper = after_reaper()?;
+    let is_done = Arc::new(AtomicBool::new(false));
+    let timer = {
+        let is_done = is_done.clone();
+        let mut cb = Some(cb);
+        Timer::new(Callback::closure(move || {
+            if let Some(cb) = cb.take() {
+                let _done = AfterDone {
+                    is_done: &is_done,
+                    reaper: &reaper,
+                };
+                cb();
+            }
+        }))?
+    };
+    if !timer.schedule_once(delay) {
+        return None;
+    }
+
+    let id = AFTER_ID.fetch_add(1, Ordering::Relaxed);
+    AFTER_TIMERS.lock().unwrap_or_else(|error| error.into_inner()).push((id, timer, is_done));
+    Some(After {
+        id,
+    })
+}
+
+#[cfg(debug_assertions)]
+static LIVE_CLOSURES: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(debug_assertions)]
+///Returns number of closures, that are currently owned by timers.
+///
+///Once all timers are dropped, it must be zero, otherwise some closure has been leaked.
+///
+///Only available in debug builds.
+pub fn leaked_closure_count() -> usize {
+    LIVE_CLOSURES.load(Ordering::Acquire)
+}
+
+#[cfg(debug_assertions)]
+//Leeway in nanoseconds plus one, that has been passed to OS most recently, 0 if there was none.
+static LAST_LEEWAY: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(debug_assertions)]
+///Returns leeway, that has been passed to OS by the most recent schedule of any timer in the process.
+///
+///Leeway is passed to OS only on Win/Mac (see `Capabilities::supports_leeway`), hence elsewhere it is always `None`.
+///
+///Only available in debug builds.
+pub fn last_forwarded_leeway() -> Option<time::Duration> {
+    match LAST_LEEWAY.load(Ordering::Acquire) {
+        0 => None,
+        leeway => Some(time::Duration::from_nanos(leeway - 1)),
+    }
+}
+
+#[allow(unused)]
+#[inline(always)]
+//Accounts leeway, that is passed to OS.
+fn leeway_forwarded(leeway: time::Duration) {
+    #[cfg(debug_assertions)]
+    LAST_LEEWAY.store(core::cmp::min(leeway.as_nanos(), u128::from(u64::max_value() - 1)) as u64 + 1, Ordering::Release);
+    #[cfg(not(debug_assertions))]
+    let _ = leeway;
+}
+
+#[cfg(feature = "alloc")]
+#[inline(always)]
+//Accounts closure, that timer takes ownership of.
+fn closure_stored() {
+    #[cfg(debug_assertions)]
+    LIVE_CLOSURES.fetch_add(1, Ordering::AcqRel);
+}
+
+#[inline(always)]
+//Accounts closure, owned by timer, that has been freed.
+fn closure_freed() {
+    #[cfg(debug_assertions)]
+    LIVE_CLOSURES.fetch_sub(1, Ordering::AcqRel);
+}
+
+//Closure, that is stored within pointer sized data of callback instead of heap.
+struct InlineClosure<F>(F);
+
+impl<F: Copy> InlineClosure<F> {
+    const FITS: () = assert!(core::mem::size_of::<F>() <= core::mem::size_of::<*mut ()>() && core::mem::align_of::<F>() <= core::mem::align_of::<*mut ()>(), "Closure must fit into pointer");
+
+    #[inline(always)]
+    fn pack(cb: F) -> *mut () {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::FITS;
+        let mut data = ptr::null_mut::<()>();
+        unsafe {
+            ptr::write(&mut data as *mut *mut () as *mut F, cb);
+        }
+        data
+    }
+
+    #[inline(always)]
+    //Safety: `data` must be created by `pack` of the same closure type.
+    unsafe fn unpack(data: *mut ()) -> F {
+        ptr::read(&data as *const *mut () as *const F)
     }
 }
 
-struct BoxFnPtr(pub FatPtr);
+#[cfg(feature = "alloc")]
+struct BoxFnPtr(pub *mut (dyn FnMut() + Send));
 
+#[cfg(feature = "alloc")]
 impl BoxFnPtr {
     #[inline(always)]
     const fn new() -> Self {
-        Self(0)
+        Self(ptr::null_mut::<fn()>() as *mut (dyn FnMut() + Send))
+    }
+
+    #[inline(always)]
+    fn from_box(cb: BoxedCallback) -> Self {
+        closure_stored();
+        Self(Box::into_raw(cb))
+    }
+
+    #[inline(always)]
+    fn is_null(&self) -> bool {
+        self.0.is_null()
     }
 
     #[inline(always)]
-    const fn is_null(&self) -> bool {
-        self.0 == 0
+    //Invokes closure, owned by `BoxFnPtr`, that `data` points to.
+    //
+    //Used by callback of `Callback::boxed`, which has no concrete closure type to cast its data into,
+    //hence it calls trait object through fat pointer instead.
+    //
+    //Safety: `data` must point to `BoxFnPtr`, that owns closure, and that is not accessed concurrently.
+    unsafe fn invoke(data: *mut ()) {
+        let cb = &*(data as *const Self);
+        (*cb.0)()
     }
 }
 
+#[cfg(feature = "alloc")]
 impl Drop for BoxFnPtr {
     #[inline(always)]
     fn drop(&mut self) {
         if !self.is_null() {
             unsafe {
-                let _ = Box::from_raw(mem::transmute::<_, *mut dyn FnMut()>(self.0));
+                let _ = Box::from_raw(self.0);
             }
+            closure_freed();
         }
     }
 }
+
+#[cfg(not(feature = "alloc"))]
+//Placeholder, as there is no closure to own without heap.
+struct BoxFnPtr;
+
+#[cfg(not(feature = "alloc"))]
+impl BoxFnPtr {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    fn is_null(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leeway_clamp() {
+        let second = time::Duration::from_secs(1);
+        let millis = time::Duration::from_millis(20);
+        let zero = time::Duration::from_secs(0);
+
+        assert_eq!(clamp_leeway(second, millis), millis);
+        assert_eq!(clamp_leeway(millis, second), millis);
+        assert_eq!(clamp_leeway(second, second), second);
+        //One shot timer has no period to break.
+        assert_eq!(clamp_leeway(second, zero), second);
+    }
+
+    #[test]
+    fn hz_to_interval() {
+        assert_eq!(hz_interval(1.0), Some(time::Duration::from_secs(1)));
+        assert_eq!(hz_interval(4.0), Some(time::Duration::from_millis(250)));
+        assert_eq!(hz_interval(0.5), Some(time::Duration::from_secs(2)));
+        assert_eq!(hz_interval(60.0), Some(time::Duration::from_nanos(16_666_667)));
+
+        assert_eq!(hz_interval(0.0), None);
+        assert_eq!(hz_interval(-60.0), None);
+        assert_eq!(hz_interval(f64::NAN), None);
+        assert_eq!(hz_interval(f64::INFINITY), None);
+        assert_eq!(hz_interval(f64::MIN_POSITIVE), None);
+        assert_eq!(hz_interval(1e10), None);
+    }
+
+    #[test]
+    fn retry_attempts() {
+        let mut calls = 0;
+        assert_eq!(retry(3, || -> Result<(), bool> {
+            calls += 1;
+            Err(true)
+        }), None);
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        assert_eq!(retry(5, || {
+            calls += 1;
+            match calls {
+                3 => Ok(calls),
+                _ => Err(true),
+            }
+        }), Some(3));
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        assert_eq!(retry(5, || -> Result<(), bool> {
+            calls += 1;
+            Err(false)
+        }), None);
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        assert_eq!(retry(0, || -> Result<(), bool> {
+            calls += 1;
+            Err(true)
+        }), None);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn catch_up_runs() {
+        let pacing = Pacing::new();
+        assert_eq!(pacing.policy(), CatchUp::Single);
+        assert_eq!(pacing.runs(), 1);
+
+        pacing.miss(5);
+        assert_eq!(pacing.runs(), 1);
+        assert_eq!(pacing.runs(), 1);
+
+        pacing.set_policy(CatchUp::ReplayAll);
+        assert_eq!(pacing.policy(), CatchUp::ReplayAll);
+        pacing.miss(2);
+        pacing.miss(3);
+        assert_eq!(pacing.runs(), 6);
+        assert_eq!(pacing.runs(), 1);
+
+        pacing.set_policy(CatchUp::ReplayBounded(2));
+        assert_eq!(pacing.policy(), CatchUp::ReplayBounded(2));
+        pacing.miss(5);
+        assert_eq!(pacing.runs(), 3);
+        pacing.miss(1);
+        assert_eq!(pacing.runs(), 2);
+
+        pacing.set_policy(CatchUp::ReplayBounded(0));
+        assert_eq!(pacing.policy(), CatchUp::ReplayBounded(0));
+        pacing.miss(5);
+        assert_eq!(pacing.runs(), 1);
+
+        pacing.set_policy(CatchUp::ReplayAll);
+        pacing.miss(u32::max_value());
+        pacing.miss(1);
+        pacing.reset();
+        assert_eq!(pacing.runs(), 1);
+        pacing.miss(u32::max_value());
+        assert_eq!(pacing.runs(), CatchUp::MAX_REPLAY + 1);
+
+        pacing.set_policy(CatchUp::ReplayBounded(u32::max_value()));
+        pacing.miss(u32::max_value());
+        assert_eq!(pacing.runs(), CatchUp::MAX_REPLAY + 1);
+    }
+}
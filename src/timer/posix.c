@@ -1,5 +1,13 @@
+#ifdef __linux__
+#define _GNU_SOURCE
+#endif
+
+#include <errno.h>
 #include <signal.h>
 #include <time.h>
+#ifdef __linux__
+#include <pthread.h>
+#endif
 
 typedef void (*callback)(union sigval);
 
@@ -18,3 +26,27 @@ timer_t posix_timer(clockid_t clock, callback cb, void* data) {
         return id;
     }
 }
+
+//Unlike `SIGEV_THREAD`, signal timer's id is kernel's one, which may be 0, hence result is returned via `id`.
+//Returns -1 on failure.
+int posix_timer_signal(clockid_t clock, int signo, void* data, timer_t* id) {
+    struct sigevent sev = {
+        .sigev_notify = SIGEV_SIGNAL,
+        .sigev_signo = signo,
+    };
+
+    sev.sigev_value.sival_ptr = data;
+
+    return timer_create(clock, &sev, id);
+}
+
+#ifdef __linux__
+//Names the calling thread, which is notification thread of timer.
+void posix_thread_name(const char* name) {
+    pthread_setname_np(pthread_self(), name);
+}
+#endif
+
+int posix_errno() {
+    return errno;
+}
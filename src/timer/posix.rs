@@ -1,10 +1,15 @@
-use core::{ptr, time, mem};
-use core::cell::Cell;
-use core::sync::atomic::{AtomicUsize, Ordering};
-use super::BoxFnPtr;
+use core::{fmt, ptr, time, mem};
+use core::cell::{Cell, UnsafeCell};
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicUsize, AtomicPtr, Ordering};
+use super::{Armed, BoxFnPtr, Capabilities, CatchUp, Clock, Lifecycle, Pacing, Running, RunningGuard, TimerBuilder, TimerError, TimerState};
+#[cfg(feature = "alloc")]
+use super::BoxedCallback;
+#[cfg(feature = "std")]
+use super::{Deadline, Latency, Waiters};
 
 extern crate alloc;
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 
 mod ffi {
     use core::mem;
@@ -30,6 +35,7 @@ mod ffi {
         }
     }
 
+    #[cfg(feature = "alloc")]
     pub unsafe extern "C" fn timer_callback_generic<T: FnMut() -> ()>(value: libc::sigval) {
         if !value.sival_ptr.is_null() {
             let cb = &mut *(value.sival_ptr as *mut T);
@@ -38,46 +44,137 @@ mod ffi {
         }
     }
 
-    #[repr(C)]
-    #[derive(PartialEq)]
-    pub struct timespec {
-        pub tv_sec: libc::time_t,
-        pub tv_nsec: libc::c_long,
+    #[cfg(feature = "alloc")]
+    pub unsafe extern "C" fn timer_callback_boxed(value: libc::sigval) {
+        if !value.sival_ptr.is_null() {
+            super::super::BoxFnPtr::invoke(value.sival_ptr as *mut ());
+        }
+    }
+
+    pub unsafe extern "C" fn timer_callback_inline<T: FnMut() -> () + Copy>(value: libc::sigval) {
+        let mut cb = super::super::InlineClosure::<T>::unpack(value.sival_ptr as *mut ());
+
+        (cb)();
+    }
+
+    pub unsafe extern "C" fn timer_callback_context(value: libc::sigval) {
+        //Value is id of context, rather than pointer, as context may be freed already (see `Registry`).
+        super::REGISTRY.fire(value.sival_ptr as usize);
     }
 
     #[repr(C)]
-    #[derive(PartialEq)]
     pub struct itimerspec {
-        pub it_interval: timespec,
-        pub it_value: timespec,
+        pub it_interval: libc::timespec,
+        pub it_value: libc::timespec,
     }
 
-    pub const ZERO_TIMER_DURATION: itimerspec = itimerspec {
-        it_interval: timespec {
-            tv_sec: 0,
-            tv_nsec: 0
-        },
-        it_value: timespec {
-            tv_sec: 0,
-            tv_nsec: 0
-        },
-    };
+    //Layout must match C definition, which is built from the same `timespec`.
+    const _: () = assert!(mem::size_of::<itimerspec>() == 2 * mem::size_of::<libc::timespec>());
+    const _: () = assert!(mem::align_of::<itimerspec>() == mem::align_of::<libc::timespec>());
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const _: () = assert!(mem::size_of::<itimerspec>() == mem::size_of::<libc::itimerspec>());
+
+    //Converts duration into `timespec`, using field types of target's `timespec`.
+    //
+    //`timespec` may have private padding fields on some targets, hence it is zeroed first.
+    pub fn timespec(duration: core::time::Duration) -> libc::timespec {
+        let mut result: libc::timespec = unsafe {
+            mem::zeroed()
+        };
+        //`time_t` is signed and may be 32bit, while nanoseconds always fit into `tv_nsec`, whatever its type is.
+        result.tv_sec = core::cmp::min(duration.as_secs(), libc::time_t::max_value() as u64) as _;
+        result.tv_nsec = duration.subsec_nanos() as _;
+        result
+    }
+
+    //Converts `timespec` back into duration, treating negative fields as zero.
+    pub fn duration(value: &libc::timespec) -> core::time::Duration {
+        let secs = if value.tv_sec > 0 { value.tv_sec as u64 } else { 0 };
+        let nanos = if value.tv_nsec > 0 { value.tv_nsec as u32 } else { 0 };
+        core::time::Duration::from_secs(secs) + core::time::Duration::from_nanos(nanos.into())
+    }
+
+    impl itimerspec {
+        #[inline(always)]
+        pub fn zero() -> Self {
+            unsafe {
+                mem::zeroed()
+            }
+        }
+
+        #[inline(always)]
+        pub fn is_zero(&self) -> bool {
+            self.it_interval.tv_sec == 0 && self.it_interval.tv_nsec == 0 && self.it_value.tv_sec == 0 && self.it_value.tv_nsec == 0
+        }
+    }
 
     extern "C" {
         pub fn timer_settime(timerid: timer_t, flags: libc::c_int, new_value: *const itimerspec, old_value: *mut itimerspec) -> libc::c_int;
-        pub fn timer_gettime(timerid: timer_t, curr_value: *const itimerspec) -> libc::c_int;
+        pub fn timer_gettime(timerid: timer_t, curr_value: *mut itimerspec) -> libc::c_int;
         pub fn timer_delete(timerid: timer_t);
+        pub fn timer_getoverrun(timerid: timer_t) -> libc::c_int;
     }
 
-    #[link(name = "os-timer-posix-c", lind = "static")]
+    #[link(name = "os-timer-posix-c", kind = "static")]
     extern "C" {
         pub fn posix_timer(clock: libc::c_int, cb: Callback, data: *mut libc::c_void) -> timer_t;
+        pub fn posix_timer_signal(clock: libc::c_int, signo: libc::c_int, data: *mut libc::c_void, id: *mut timer_t) -> libc::c_int;
+        #[cfg(target_os = "linux")]
+        pub fn posix_thread_name(name: *const libc::c_char);
+        pub fn posix_errno() -> libc::c_int;
+    }
+}
+
+#[cfg(feature = "alloc")]
+type CountedCallback = Box<dyn FnMut(&Timer, u64) + Send>;
+#[cfg(not(feature = "alloc"))]
+//Placeholder, as such callback cannot be created without heap, hence it is never set.
+type CountedCallback = fn(&Timer, u64);
+
+///Raw handle of OS timer: `timer_t` cast to integer, or `timerfd` descriptor for `DeliveryMode::TimerFd` (Linux).
+pub type RawTimer = ffi::timer_t;
+
+impl Clock {
+    #[inline(always)]
+    fn id(self) -> libc::clockid_t {
+        match self {
+            Clock::Monotonic => libc::CLOCK_MONOTONIC,
+            Clock::Realtime => libc::CLOCK_REALTIME,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Clock::Boottime => libc::CLOCK_BOOTTIME,
+            Clock::ProcessCpuTime => libc::CLOCK_PROCESS_CPUTIME_ID,
+            Clock::ThreadCpuTime => libc::CLOCK_THREAD_CPUTIME_ID,
+        }
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Mechanism, that backs timer on Linux.
+pub enum DeliveryMode {
+    ///POSIX timer, invoking callback on the thread, spawned by OS (`SIGEV_THREAD`).
+    ///
+    ///Used by `Timer::new`.
+    ThreadCallback,
+    #[cfg(feature = "timerfd")]
+    ///`timerfd`, that becomes readable on expiration.
+    ///
+    ///OS never invokes callback by itself, instead user polls `Timer::timer_fd` within own event loop
+    ///and calls `Timer::dispatch` once it is readable.
+    ///
+    ///Requires `timerfd` feature.
+    TimerFd,
+}
+
 enum CallbackVariant {
     Trivial(*mut ffi::c_void),
-    Boxed(Box<dyn FnMut()>),
+    #[cfg(feature = "alloc")]
+    Boxed(BoxedCallback),
+    //Closure, boxed by user, which is owned by context and invoked through fat pointer.
+    #[cfg(feature = "alloc")]
+    Dyn(BoxedCallback),
+    #[cfg(feature = "alloc")]
+    Counted(CountedCallback),
 }
 
 ///Timer's callback abstraction
@@ -90,7 +187,7 @@ impl Callback {
     ///Creates raw callback for platform timer.
     ///
     ///Signature depends on platform.
-    pub unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
+    pub const unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
         Self {
             variant: CallbackVariant::Trivial(data),
             ffi_cb,
@@ -98,7 +195,7 @@ impl Callback {
     }
 
     ///Creates callback using plain rust function
-    pub fn plain(cb: fn()) -> Self {
+    pub const fn plain(cb: fn()) -> Self {
         Self {
             variant: CallbackVariant::Trivial(cb as _),
             ffi_cb: ffi::timer_callback,
@@ -106,246 +203,2134 @@ impl Callback {
     }
 
     ///Creates callback using plain unsafe function
-    pub fn unsafe_plain(cb: unsafe fn()) -> Self {
+    pub const fn unsafe_plain(cb: unsafe fn()) -> Self {
         Self {
             variant: CallbackVariant::Trivial(cb as _),
             ffi_cb: ffi::timer_callback_unsafe,
         }
     }
 
+    #[cfg(feature = "alloc")]
     ///Creates callback using closure, storing it on heap.
-    pub fn closure<F: 'static + FnMut()>(cb: F) -> Self {
+    pub fn closure<F: 'static + FnMut() + Send>(cb: F) -> Self {
         Self {
             variant: CallbackVariant::Boxed(Box::new(cb)),
             ffi_cb: ffi::timer_callback_generic::<F>,
         }
     }
-}
-
-///Posix timer wrapper
-pub struct Timer {
-    inner: AtomicUsize,
-    data: Cell<BoxFnPtr>,
-}
 
-impl Timer {
-    #[inline]
-    ///Creates new uninitialized instance.
+    ///Creates callback using small closure, stored in place of function's data instead of heap.
     ///
-    ///In order to use it one must call `init`.
-    pub const unsafe fn uninit() -> Self {
+    ///Closure must fit into pointer (i.e. capture nothing or single reference), which is checked at compile time.
+    ///Being `Copy`, closure is copied for each run, hence changes to its captured state are not preserved between runs.
+    pub fn inline<F: 'static + FnMut() + Copy + Send>(cb: F) -> Self {
         Self {
-            inner: AtomicUsize::new(0),
-            data: Cell::new(BoxFnPtr::new()),
+            variant: CallbackVariant::Trivial(super::InlineClosure::pack(cb) as *mut ffi::c_void),
+            ffi_cb: ffi::timer_callback_inline::<F>,
         }
     }
 
+    #[cfg(feature = "alloc")]
     #[inline(always)]
-    fn get_inner(&self) -> usize {
-        let inner = self.inner.load(Ordering::Acquire);
-        debug_assert_ne!(inner, 0, "Timer has not been initialized");
-        inner
+    ///Creates callback using already boxed closure.
+    ///
+    ///Closure is stored as it is, without boxing it again.
+    pub fn boxed(cb: BoxedCallback) -> Self {
+        Self {
+            variant: CallbackVariant::Dyn(cb),
+            ffi_cb: ffi::timer_callback_boxed,
+        }
     }
 
-    #[inline(always)]
-    ///Returns whether timer is initialized
-    pub fn is_init(&self) -> bool {
-        self.inner.load(Ordering::Acquire) != 0
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives number of expirations since its previous run.
+    ///
+    ///Number includes expirations, reported by kernel as overrun (`timer_getoverrun`), and expirations, that
+    ///happened while callback was still running. It is always 1 for one shot timer.
+    ///
+    ///Callback is run once per expiration, regardless of `CatchUp` policy.
+    pub fn with_count<F: 'static + FnMut(u64) + Send>(mut cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Counted(Box::new(move |_: &Timer, count| cb(count))),
+            ffi_cb: ffi::timer_callback,
+        }
     }
 
-    #[must_use]
-    ///Performs timer initialization
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives reference to the timer, which invokes it.
     ///
-    ///`cb` pointer to function to invoke when timer expires.
+    ///It allows callback to re-schedule (or cancel) its own timer, e.g. to implement backoff.
     ///
-    ///Returns whether timer has been initialized successfully or not.
+    ///Callback receives a view of the timer, sharing its OS timer and context, rather than timer itself,
+    ///as `Timer` may be moved after creation. It is sound, because timer outlives its callbacks: on drop it
+    ///stops invoking callbacks, waits for ones in flight, and only then cancels and deletes OS timer.
     ///
-    ///If timer is already initialized does nothing, returning false.
-    pub fn init(&self, cb: Callback) -> bool {
-        if self.is_init() {
-            return false;
+    ///Callback is run once per expiration, regardless of `CatchUp` policy.
+    pub fn with_timer<F: 'static + FnMut(&Timer) + Send>(mut cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Counted(Box::new(move |timer: &Timer, _| cb(timer))),
+            ffi_cb: ffi::timer_callback,
         }
+    }
 
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (BoxFnPtr(0), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (BoxFnPtr(mem::transmute(raw)), raw as *mut ffi::c_void)
-            },
-        };
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that keeps periodic timer running as long as it returns `true`.
+    ///
+    ///Once closure returns `false`, timer cancels itself right after that invocation, until it is scheduled again.
+    pub fn repeating<F: 'static + FnMut() -> bool + Send>(mut cb: F) -> Self {
+        Self::with_timer(move |timer| {
+            if !cb() {
+                timer.cancel();
+            }
+        })
+    }
 
-        let handle = unsafe {
-            ffi::posix_timer(libc::CLOCK_MONOTONIC, ffi_cb, ffi_data)
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives number of intervals elapsed since its previous run.
+    ///
+    ///On POSIX count is exact, being the same as reported by `with_count` (saturated to `u32`).
+    pub fn with_elapsed<F: 'static + FnMut(u32) + Send>(mut cb: F) -> Self {
+        Self::with_count(move |count| cb(core::cmp::min(count, u32::max_value().into()) as u32))
+    }
+}
+
+impl fmt::Debug for Callback {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //Closure is opaque and function pointer is meaningless to user, hence only kind of callback is printed.
+        let variant = match self.variant {
+            CallbackVariant::Trivial(_) => "Trivial",
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Boxed(_) | CallbackVariant::Dyn(_) => "Boxed",
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Counted(_) => "Counted",
         };
+        fmt.debug_struct("Callback").field("variant", &format_args!("{}", variant)).finish()
+    }
+}
 
-        match self.inner.compare_exchange(0, handle, Ordering::SeqCst, Ordering::Acquire) {
-            Ok(_) => match handle {
-                0 => false,
-                _ => {
-                    //safe because we can never reach here once `handle.is_null() != true`
-                    self.data.set(data);
-                    true
-                },
+impl Callback {
+    fn into_handler(self) -> (BoxFnPtr, Handler) {
+        let mut handler = Handler {
+            ffi_cb: self.ffi_cb,
+            ffi_data: ptr::null_mut(),
+            boxed: BoxFnPtr::new(),
+            counted: None,
+        };
+        let data = match self.variant {
+            CallbackVariant::Trivial(data) => {
+                handler.ffi_data = data;
+                BoxFnPtr::new()
             },
-            Err(_) => {
-                unsafe {
-                    ffi::timer_delete(handle);
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Boxed(cb) => {
+                let data = BoxFnPtr::from_box(cb);
+                handler.ffi_data = data.0 as *mut ffi::c_void;
+                data
+            },
+            //Data is pointed to `boxed`, once handler is at its place (see `Handler::bind_boxed`).
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Dyn(cb) => {
+                handler.boxed = BoxFnPtr::from_box(cb);
+                BoxFnPtr::new()
+            },
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Counted(cb) => {
+                super::closure_stored();
+                handler.counted = Some(cb);
+                BoxFnPtr::new()
+            },
+        };
+
+        (data, handler)
+    }
+
+    fn into_context(self) -> (BoxFnPtr, *mut Context) {
+        let (data, handler) = self.into_handler();
+
+        let ctx = Box::into_raw(Box::new(Context {
+            handler: UnsafeCell::new(handler),
+            handle: AtomicUsize::new(0),
+            clock: Clock::Monotonic,
+            signo: 0,
+            #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+            timerfd: false,
+            thread: AtomicUsize::new(0),
+            overrun: AtomicI32::new(0),
+            #[cfg(target_os = "linux")]
+            name: [0; THREAD_NAME_LEN],
+            id: AtomicUsize::new(0),
+            state: Lifecycle::new(),
+            armed: Armed::new(),
+            fires: AtomicU64::new(0),
+            running: Running::new(),
+            pacing: Pacing::new(),
+            #[cfg(feature = "std")]
+            deadline: Deadline::new(),
+            #[cfg(feature = "std")]
+            latency: Latency::new(),
+            #[cfg(feature = "std")]
+            waiters: Waiters::new(),
+        }));
+        unsafe {
+            Handler::bind_boxed((*ctx).handler.get());
+        }
+        (data, ctx)
+    }
+}
+
+#[cfg(target_os = "linux")]
+//Linux limit on thread name length, including nul terminator.
+const THREAD_NAME_LEN: usize = 16;
+
+//Callback, that context invokes on expiration.
+struct Handler {
+    ffi_cb: ffi::Callback,
+    ffi_data: *mut ffi::c_void,
+    //Closure of `Callback::boxed`, which `ffi_data` points to.
+    boxed: BoxFnPtr,
+    //Callback, that receives number of expirations, used instead of `ffi_cb`.
+    counted: Option<CountedCallback>,
+}
+
+impl Handler {
+    //Points `ffi_data` to `boxed`, if callback is created by `Callback::boxed`.
+    //
+    //As handler refers to itself then, it must be called once handler is at its final place.
+    unsafe fn bind_boxed(this: *mut Self) {
+        if !(*this).boxed.is_null() {
+            (*this).ffi_data = ptr::addr_of_mut!((*this).boxed) as *mut ffi::c_void;
+        }
+    }
+}
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        if self.counted.take().is_some() {
+            super::closure_freed();
+        }
+    }
+}
+
+//Timer's user data, through which OS invokes actual callback.
+struct Context {
+    //Callbacks access it only once they enter `running` and wait out swap, hence `set_callback` replaces it
+    //between `begin_swap` and `end_swap` of `running`, even though context is shared.
+    handler: UnsafeCell<Handler>,
+    //Set once timer is created, before it can be scheduled.
+    handle: AtomicUsize,
+    //Set only before timer is created.
+    clock: Clock,
+    #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+    //Whether `handle` is `timerfd` descriptor instead of POSIX timer.
+    timerfd: bool,
+    //pthread_t of the last thread that run callback, 0 if there was none.
+    thread: AtomicUsize,
+    //Overrun, reported for the most recent expiration.
+    overrun: AtomicI32,
+    #[cfg(target_os = "linux")]
+    //Name of callback thread, applied once callback starts, nul terminated and empty if thread is not named.
+    name: [libc::c_char; THREAD_NAME_LEN],
+    //Id in `REGISTRY`, 0 if context is not registered.
+    id: AtomicUsize,
+    //Signal, delivered on expiration instead of invoking callback, 0 if not used.
+    signo: libc::c_int,
+    state: Lifecycle,
+    armed: Armed,
+    //Number of expirations, that reached user's callback.
+    fires: AtomicU64,
+    running: Running,
+    pacing: Pacing,
+    #[cfg(feature = "std")]
+    deadline: Deadline,
+    #[cfg(feature = "std")]
+    latency: Latency,
+    #[cfg(feature = "std")]
+    waiters: Waiters,
+}
+
+impl Context {
+    //Returns `errno` on failure.
+    unsafe fn create(&self) -> Result<ffi::timer_t, libc::c_int> {
+        #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+        {
+            if self.timerfd {
+                let fd = match libc::timerfd_create(self.clock.id(), libc::TFD_NONBLOCK | libc::TFD_CLOEXEC) {
+                    -1 => return Err(ffi::posix_errno()),
+                    //Zero handle means uninitialized timer, so move descriptor away from it.
+                    0 => {
+                        let fd = libc::fcntl(0, libc::F_DUPFD_CLOEXEC, 1);
+                        let error = ffi::posix_errno();
+                        libc::close(0);
+                        match fd {
+                            -1 => return Err(error),
+                            fd => fd,
+                        }
+                    },
+                    fd => fd,
+                };
+                return Ok(fd as ffi::timer_t);
+            }
+        }
+
+        if self.signo != 0 {
+            let mut handle = 0;
+            if ffi::posix_timer_signal(self.clock.id(), self.signo, ptr::null_mut(), &mut handle) == -1 {
+                return Err(ffi::posix_errno());
+            }
+            //Zero handle means uninitialized timer, so replace the first timer of process with another one.
+            if handle == 0 {
+                let result = ffi::posix_timer_signal(self.clock.id(), self.signo, ptr::null_mut(), &mut handle);
+                let error = ffi::posix_errno();
+                ffi::timer_delete(0);
+                if result == -1 {
+                    return Err(error);
                 }
-                false
             }
+            return Ok(handle);
         }
-    }
 
-    ///Creates new timer, invoking provided `cb` when timer expires.
-    ///
-    ///On failure, returns `None`
-    pub fn new(cb: Callback) -> Option<Self> {
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (BoxFnPtr(0), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (BoxFnPtr(mem::transmute(raw)), raw as *mut ffi::c_void)
+        //Creation may be retried, while context must be registered once.
+        let id = match self.id.load(Ordering::Acquire) {
+            0 => {
+                let id = REGISTRY.insert(self);
+                self.id.store(id, Ordering::Release);
+                id
             },
+            id => id,
         };
+        match ffi::posix_timer(self.clock.id(), ffi::timer_callback_context, id as *mut ffi::c_void) {
+            0 => Err(ffi::posix_errno()),
+            handle => Ok(handle),
+        }
+    }
 
-        let handle = unsafe {
-            ffi::posix_timer(libc::CLOCK_MONOTONIC, ffi_cb, ffi_data)
-        };
+    unsafe fn settime(&self, flags: libc::c_int, new_value: &ffi::itimerspec, old_value: *mut ffi::itimerspec) -> libc::c_int {
+        let handle = self.handle.load(Ordering::Acquire);
+        #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+        {
+            if self.timerfd {
+                //TFD_TIMER_ABSTIME has the same value as TIMER_ABSTIME
+                return libc::timerfd_settime(handle as libc::c_int, flags, new_value as *const ffi::itimerspec as *const libc::itimerspec, old_value as *mut libc::itimerspec);
+            }
+        }
+        ffi::timer_settime(handle, flags, new_value, old_value)
+    }
 
-        if handle == 0 {
-            return None;
+    unsafe fn gettime(&self, curr_value: *mut ffi::itimerspec) -> libc::c_int {
+        let handle = self.handle.load(Ordering::Acquire);
+        #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+        {
+            if self.timerfd {
+                return libc::timerfd_gettime(handle as libc::c_int, curr_value as *mut libc::itimerspec);
+            }
         }
+        ffi::timer_gettime(handle, curr_value)
+    }
 
-        Some(Self {
-            inner: AtomicUsize::new(handle),
-            data: Cell::new(data),
-        })
+    unsafe fn delete(&self, handle: ffi::timer_t) {
+        #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+        {
+            if self.timerfd {
+                libc::close(handle as libc::c_int);
+                return;
+            }
+        }
+        ffi::timer_delete(handle)
     }
 
-    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
-    ///
-    ///Note that if timer has been scheduled before, but hasn't expire yet, behaviour is undefined (Callback may or may not be called).
-    ///To prevent that user must `cancel` timer first.
-    ///
-    ///Returns `true` if successfully set, otherwise on error returns `false`
-    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
-        let it_value = ffi::timespec {
-            tv_sec: timeout.as_secs() as libc::time_t,
-            #[cfg(not(any(target_os = "openbsd", target_os = "netbsd")))]
-            tv_nsec: timeout.subsec_nanos() as libc::suseconds_t,
-            #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
-            tv_nsec: timeout.subsec_nanos() as libc::c_long,
-        };
+    //Creates view of the timer, that owns context, so that it can be passed to callback.
+    //
+    //View doesn't own closure, and it must not outlive context, hence it is never dropped.
+    unsafe fn timer_view(&self) -> mem::ManuallyDrop<Timer> {
+        mem::ManuallyDrop::new(Timer {
+            inner: AtomicUsize::new(self.handle.load(Ordering::Acquire)),
+            data: Cell::new(BoxFnPtr::new()),
+            ctx: AtomicPtr::new(self as *const Self as *mut Self),
+        })
+    }
 
-        let it_interval = ffi::timespec {
-            tv_sec: interval.as_secs() as libc::time_t,
-            #[cfg(not(any(target_os = "openbsd", target_os = "netbsd")))]
-            tv_nsec: interval.subsec_nanos() as libc::suseconds_t,
-            #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
-            tv_nsec: interval.subsec_nanos() as libc::c_long,
-        };
+    //Exchanges callback with `other`.
+    //
+    //Caller must make sure that callbacks do not access it meanwhile (see `Timer::set_callback`).
+    unsafe fn swap_handler(&self, other: &mut Handler) {
+        let handler = self.handler.get();
+        ptr::swap(handler, other);
+        Handler::bind_boxed(handler);
+        Handler::bind_boxed(other);
+    }
 
-        let new_value = ffi::itimerspec {
-            it_interval,
-            it_value,
-        };
+    #[inline(always)]
+    unsafe fn invoke(&self) {
+        self.invoke_count(1)
+    }
 
-        unsafe {
-            ffi::timer_settime(self.get_inner(), 0, &new_value, ptr::null_mut()) == 0
+    #[inline(always)]
+    unsafe fn invoke_count(&self, count: u64) {
+        let handler = &mut *self.handler.get();
+        match &mut handler.counted {
+            Some(cb) => cb(&self.timer_view(), count),
+            None => (handler.ffi_cb)(libc::sigval {
+                sival_ptr: handler.ffi_data,
+            }),
         }
     }
 
-    #[inline]
-    ///Returns `true` if timer has been scheduled and still pending.
-    ///
-    ///On Win/Mac it only returns whether timer has been scheduled, as there is no way to check
-    ///whether timer is ongoing
-    pub fn is_scheduled(&self) -> bool {
-        let handle = self.get_inner();
-        let curr_value = unsafe {
-            let mut curr_value = mem::MaybeUninit::<ffi::itimerspec>::uninit();
+    //`running` is entered by caller, as context must not be freed meanwhile (see `Registry`).
+    //`expirations` is number of expirations, if it is reported by OS on delivery (`timerfd`).
+    unsafe fn fire(&self, running: RunningGuard<'_>, expirations: Option<u64>) {
+        if running.is_closed() {
+            return;
+        }
+        //Callback is not accessed until `set_callback` replaces it.
+        running.wait_swap();
+        #[cfg(feature = "std")]
+        self.latency.fire();
 
-            if ffi::timer_gettime(handle, curr_value.as_mut_ptr()) != 0 {
-                return false;
+        #[cfg(feature = "std")]
+        {
+            if self.deadline.is_expired() {
+                self.state.cancel();
+                self.waiters.notify();
+                self.settime(0, &ffi::itimerspec::zero(), ptr::null_mut());
+                return;
             }
-            curr_value.assume_init()
+        }
+
+        if self.state.is_periodic() {
+            //Each expiration runs on its own thread, so skip it, if previous one is not done yet.
+            if running.is_concurrent() {
+                self.pacing.miss(1);
+                return;
+            }
+
+            let overrun = match expirations {
+                Some(expirations) => core::cmp::min(expirations.saturating_sub(1), u32::max_value().into()) as u32,
+                None => core::cmp::max(ffi::timer_getoverrun(self.handle.load(Ordering::Acquire)), 0) as u32,
+            };
+            self.overrun.store(core::cmp::min(overrun, i32::max_value() as u32) as i32, Ordering::Release);
+            if overrun > 0 {
+                self.pacing.miss(overrun);
+            }
+        } else {
+            self.overrun.store(0, Ordering::Release);
+        }
+
+        let is_last = match self.armed.take_alarm() {
+            Some(is_last) => is_last,
+            //Alarm, that raced with the last one of `schedule_count`.
+            None => return,
         };
+        if is_last {
+            self.state.cancel();
+            //Cannot wait for callbacks from within callback itself
+            self.settime(0, &ffi::itimerspec::zero(), ptr::null_mut());
+        }
 
-        curr_value != ffi::ZERO_TIMER_DURATION
-    }
+        let thread = libc::pthread_self();
+        self.thread.store(thread as usize, Ordering::Release);
+        self.state.fire();
+        self.fires.fetch_add(1, Ordering::AcqRel);
+        #[cfg(feature = "std")]
+        self.waiters.notify();
 
-    #[inline]
-    ///Cancels ongoing timer, if it was scheduled.
-    pub fn cancel(&self) {
-        if self.is_scheduled() {
-            unsafe {
-                ffi::timer_settime(self.get_inner(), 0, &mem::MaybeUninit::zeroed().assume_init(), ptr::null_mut());
+        if (*self.handler.get()).counted.is_some() {
+            self.invoke_count(u64::from(self.pacing.take_missed()) + 1);
+        } else {
+            for _ in 0..self.pacing.runs() {
+                self.invoke();
             }
         }
     }
 }
 
-impl Drop for Timer {
+impl Drop for Context {
     fn drop(&mut self) {
-        let handle = self.inner.load(Ordering::Relaxed);
-        if handle != 0 {
-            self.cancel();
-            unsafe {
-                ffi::timer_delete(handle)
-            }
+        let id = *self.id.get_mut();
+        if id != 0 {
+            REGISTRY.remove(id);
+            //Callbacks, that looked context up before it was removed, may still use it.
+            self.running.wait_idle();
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+//Contexts of `SIGEV_THREAD` timers, that are not freed yet, keyed by id, which is passed to OS instead of pointer.
+//
+//Each notification runs on its own thread, which OS may start before timer is deleted, while it gets to callback
+//only after context is freed. Hence callback looks context up and enters its `running` under the lock, so that
+//once context is removed, waiting for `running` covers every callback, that can still use it.
+struct Registry {
+    lock: AtomicBool,
+    //Sorted by id, as ids are only increasing.
+    contexts: UnsafeCell<Vec<(usize, *const Context)>>,
+    next_id: Cell<usize>,
+}
 
-    #[test]
-    fn init_plain_fn() {
-        let mut timer = unsafe {
-            Timer::uninit()
-        };
+//Fields are accessed only under `lock`.
+unsafe impl Sync for Registry {}
 
-        fn cb() {
+static REGISTRY: Registry = Registry {
+    lock: AtomicBool::new(false),
+    contexts: UnsafeCell::new(Vec::new()),
+    next_id: Cell::new(1),
+};
+
+impl Registry {
+    fn with<R>(&self, f: impl FnOnce(&mut Vec<(usize, *const Context)>, &Cell<usize>) -> R) -> R {
+        let mut attempt = 0;
+        while self.lock.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            super::backoff(attempt);
+            attempt = attempt.saturating_add(1);
         }
+        let result = f(unsafe { &mut *self.contexts.get() }, &self.next_id);
+        self.lock.store(false, Ordering::Release);
+        result
+    }
 
-        let closure = || {
-        };
+    //Returns id, which is never 0.
+    fn insert(&self, ctx: *const Context) -> usize {
+        self.with(|contexts, next_id| {
+            let id = next_id.get();
+            next_id.set(id + 1);
+            contexts.push((id, ctx));
+            id
+        })
+    }
 
-        assert!(timer.init(Callback::plain(cb)));
-        let ptr = timer.inner.load(Ordering::Relaxed);
-        assert_ne!(ptr, 0);
-        assert!(timer.data.get_mut().is_null());
+    fn remove(&self, id: usize) {
+        self.with(|contexts, _| if let Ok(idx) = contexts.binary_search_by_key(&id, |entry| entry.0) {
+            contexts.remove(idx);
+        })
+    }
 
-        assert!(!timer.init(Callback::closure(closure)));
-        assert_ne!(ptr, 0);
-        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
-        assert!(timer.data.get_mut().is_null());
+    //Fires context, registered as `id`, unless it is removed already.
+    unsafe fn fire(&self, id: usize) {
+        let entered = self.with(|contexts, _| match contexts.binary_search_by_key(&id, |entry| entry.0) {
+            Ok(idx) => {
+                let ctx = &*contexts[idx].1;
+                Some((ctx, ctx.running.enter()))
+            },
+            Err(_) => None,
+        });
+
+        if let Some((ctx, running)) = entered {
+            #[cfg(target_os = "linux")]
+            {
+                if ctx.name[0] != 0 {
+                    ffi::posix_thread_name(ctx.name.as_ptr());
+                }
+            }
+            ctx.fire(running, None);
+        }
     }
+}
 
-    #[test]
-    fn init_closure() {
-        let mut timer = unsafe {
-            Timer::uninit()
-        };
+//Returns `TimerError::Overflow` if durations don't fit into `timespec`.
+fn check_range(timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+    match timeout.as_secs() <= libc::time_t::max_value() as u64 && interval.as_secs() <= libc::time_t::max_value() as u64 {
+        true => Ok(()),
+        false => Err(TimerError::Overflow),
+    }
+}
 
-        fn cb() {
+///Posix timer wrapper
+///
+///# Drop
+///
+///Dropping timer waits for its callbacks, that are already running, to finish, hence timer must not be dropped
+///from within its own callback (e.g. by taking it out of shared `Mutex<Option<Timer>>`), as it would never return.
+pub struct Timer {
+    inner: AtomicUsize,
+    //Owns closure, if any.
+    //Set only once by thread, that initialized timer, and otherwise accessed only on drop,
+    //hence it is safe to share timer between threads.
+    data: Cell<BoxFnPtr>,
+    ctx: AtomicPtr<Context>,
+}
+
+impl Timer {
+    #[inline]
+    ///Creates new uninitialized instance.
+    ///
+    ///In order to use it one must call `init`.
+    ///
+    ///Initialized timer cannot be reset in place, as other threads may use it concurrently, hence to re-use it
+    ///(e.g. in object pool) assign `uninit` to it: old timer is dropped, which cancels it, waits for its
+    ///callbacks in flight and frees OS timer together with callback, after which `init` succeeds again.
+    pub const unsafe fn uninit() -> Self {
+        Self {
+            inner: AtomicUsize::new(0),
+            data: Cell::new(BoxFnPtr::new()),
+            ctx: AtomicPtr::new(ptr::null_mut()),
         }
+    }
 
-        let closure = || {
-        };
+    #[inline(always)]
+    fn context(&self) -> Option<&Context> {
+        unsafe {
+            self.ctx.load(Ordering::Acquire).as_ref()
+        }
+    }
 
-        assert!(timer.init(Callback::closure(closure)));
-        let ptr = timer.inner.load(Ordering::Relaxed);
-        assert_ne!(ptr, 0);
-        assert!(!timer.data.get_mut().is_null());
+    #[inline(always)]
+    ///Returns raw handle of OS timer, in order to use platform API, that is not wrapped by the crate.
+    ///
+    ///Handle is owned by timer, hence caller must neither release it nor use it after timer is dropped.
+    ///
+    ///Returns 0 if timer is not initialized.
+    pub fn as_raw(&self) -> RawTimer {
+        self.inner.load(Ordering::Acquire)
+    }
+
+    #[inline(always)]
+    ///Returns whether timer is initialized
+    pub fn is_init(&self) -> bool {
+        self.inner.load(Ordering::Acquire) != 0
+    }
+
+    #[must_use]
+    ///Performs timer initialization
+    ///
+    ///`cb` function to invoke when timer expires, which can be plain function or closure.
+    ///
+    ///Returns whether timer has been initialized successfully or not.
+    ///
+    ///If timer is already initialized does nothing, returning false.
+    ///
+    ///Initialization is atomic: when multiple threads race to initialize the same timer, exactly one
+    ///succeeds, while the rest release OS timer and callback they created, returning false.
+    pub fn init(&self, cb: impl Into<Callback>) -> bool {
+        self.try_init(cb).is_ok()
+    }
+
+    ///Performs timer initialization
+    ///
+    ///Same as `init`, but returns reason of failure:
+    ///
+    ///- `TimerError::AlreadyInitialized` if timer is already initialized, in which case it does nothing.
+    ///- `TimerError::Os` with `errno` if OS fails to create timer.
+    pub fn try_init(&self, cb: impl Into<Callback>) -> Result<(), TimerError> {
+        if self.is_init() {
+            return Err(TimerError::AlreadyInitialized);
+        }
+
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
+        self.init_context(data, ctx)
+    }
+
+    #[must_use]
+    ///Performs timer initialization, using specified `clock`.
+    ///
+    ///Otherwise it is the same as `init`.
+    pub fn init_with_clock(&self, cb: impl Into<Callback>, clock: Clock) -> bool {
+        if self.is_init() {
+            return false;
+        }
+
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
+        unsafe {
+            (*ctx).clock = clock;
+        }
+        self.init_context(data, ctx).is_ok()
+    }
+
+    fn init_context(&self, data: BoxFnPtr, ctx: *mut Context) -> Result<(), TimerError> {
+        let handle = match unsafe { (*ctx).create() } {
+            Ok(handle) => unsafe {
+                (*ctx).handle.store(handle, Ordering::Release);
+                handle
+            },
+            Err(error) => {
+                unsafe {
+                    let _ = Box::from_raw(ctx);
+                }
+                return Err(TimerError::Os(error));
+            }
+        };
+
+        //Context is claimed first, so that timer is never observed initialized without it.
+        match self.ctx.compare_exchange(ptr::null_mut(), ctx, Ordering::SeqCst, Ordering::Acquire) {
+            Ok(_) => {
+                //safe because only the thread, that claimed context, can reach here.
+                self.data.set(data);
+                self.inner.store(handle, Ordering::Release);
+                Ok(())
+            },
+            Err(_) => {
+                unsafe {
+                    (*ctx).delete(handle);
+                    let _ = Box::from_raw(ctx);
+                }
+                //Closure of the losing thread is never reachable from timer.
+                drop(data);
+                Err(TimerError::AlreadyInitialized)
+            }
+        }
+    }
+
+    #[inline(always)]
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///`cb` can be plain function or closure, as well as `Callback`.
+    ///
+    ///On failure, returns `None`
+    pub fn new(cb: impl Into<Callback>) -> Option<Self> {
+        Self::new_with_retry(cb.into(), 1)
+    }
+
+    ///Creates new timer, invoking provided `cb` when timer expires, using specified `clock`.
+    ///
+    ///On failure, returns `None`
+    pub fn new_with_clock(cb: impl Into<Callback>, clock: Clock) -> Option<Self> {
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
+        unsafe {
+            (*ctx).clock = clock;
+        }
+        Self::from_context(data, ctx, 1).ok()
+    }
+
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///Same as `new`, but on failure returns `TimerError::Os` with `errno`.
+    pub fn try_new(cb: impl Into<Callback>) -> Result<Self, TimerError> {
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
+        Self::from_context(data, ctx, 1)
+    }
+
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///If OS fails to create timer due to lack of resources (`EAGAIN`), retries it up to `attempts` times in total,
+    ///pausing between attempts (starting with 1ms and doubling each time, up to 64ms), hence it may block briefly.
+    ///Without `std` feature there is no way to sleep, hence attempts are made back to back.
+    ///
+    ///On failure, returns `None`
+    pub fn new_with_retry(cb: impl Into<Callback>, attempts: u32) -> Option<Self> {
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
+        Self::from_context(data, ctx, attempts).ok()
+    }
+
+    #[cfg(target_os = "linux")]
+    ///Creates new timer, invoking provided `cb` when timer expires on the thread, named `name`.
+    ///
+    ///Linux limits thread name to 16 bytes, including nul terminator, hence `name` longer than 15 bytes is truncated.
+    ///
+    ///Name is applied by C shim at startup of the notification thread, before any Rust code runs on it.
+    ///It cannot be applied earlier: glibc's `SIGEV_THREAD` spawns new thread for each expiration and
+    ///thread attributes cannot carry name, hence there is no callback thread to name until timer fires.
+    ///
+    ///On failure, returns `None`
+    pub fn with_name(cb: impl Into<Callback>, name: &str) -> Option<Self> {
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
+
+        unsafe {
+            let len = core::cmp::min(name.len(), THREAD_NAME_LEN - 1);
+            for (dst, src) in (*ctx).name.iter_mut().zip(name.as_bytes()[..len].iter()) {
+                *dst = *src as libc::c_char;
+            }
+        }
+
+        Self::from_context(data, ctx, 1).ok()
+    }
+
+    ///Creates new timer, using specified `clock`, that delivers signal `signo` on expiration (`SIGEV_SIGNAL`).
+    ///
+    ///There is no Rust callback: it is up to user to install handler for `signo` (typically `SIGRTMIN() + n`,
+    ///as real-time signals are queued rather than merged) before scheduling timer.
+    ///Handler receives null `si_value`.
+    ///
+    ///Handler runs in signal context, interrupting arbitrary thread, hence it may only use async-signal-safe functions:
+    ///it must not allocate, take locks, or call methods of `Timer`.
+    ///
+    ///As expirations never reach the crate, `fire_count`, `overrun_count`, `last_latency` and `state` do not track them,
+    ///and `wait` returns only on cancellation.
+    ///
+    ///On failure, returns `None`
+    pub fn new_signal(clock: Clock, signo: libc::c_int) -> Option<Self> {
+        let cb = unsafe {
+            Callback::raw(ffi::timer_callback, ptr::null_mut())
+        };
+        let (data, ctx) = cb.into_context();
+        unsafe {
+            (*ctx).clock = clock;
+            (*ctx).signo = signo;
+        }
+        Self::from_context(data, ctx, 1).ok()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Creates new timer, backed by specified delivery `mode`.
+    ///
+    ///See `DeliveryMode` for details.
+    ///
+    ///On failure, returns `None`
+    pub fn with_delivery(cb: impl Into<Callback>, mode: DeliveryMode) -> Option<Self> {
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
+
+        match mode {
+            DeliveryMode::ThreadCallback => (),
+            #[cfg(feature = "timerfd")]
+            DeliveryMode::TimerFd => unsafe {
+                (*ctx).timerfd = true;
+            },
+        }
+
+        Self::from_context(data, ctx, 1).ok()
+    }
+
+    ///Creates timer, that takes ownership of OS timer `handle`, created elsewhere.
+    ///
+    ///OS keeps invoking callback, that `handle` has been created with, hence timer doesn't observe expirations:
+    ///`fire_count`, `state`, `wait` and other bookkeeping do not reflect them.
+    ///Clock of `handle` is assumed to be `Clock::Monotonic` by `clock`, `clock_now` and `clock_after`.
+    ///
+    ///# Safety
+    ///
+    ///`handle` must be valid POSIX timer (not `timerfd`), that is not scheduled and not owned by anything else,
+    ///as timer deletes it on drop.
+    pub unsafe fn from_raw(handle: RawTimer) -> Self {
+        let cb = Callback::raw(ffi::timer_callback, ptr::null_mut());
+        let (data, ctx) = cb.into_context();
+        (*ctx).handle.store(handle, Ordering::Release);
+
+        Self {
+            inner: AtomicUsize::new(handle),
+            data: Cell::new(data),
+            ctx: AtomicPtr::new(ctx),
+        }
+    }
+
+    pub(super) fn from_builder(builder: TimerBuilder) -> Result<Self, TimerError> {
+        let (data, ctx) = builder.callback.ok_or(TimerError::Uninitialized)?.into_context();
+        if let Some(clock) = builder.clock {
+            unsafe {
+                (*ctx).clock = clock;
+            }
+        }
+        Self::from_context(data, ctx, 1)
+    }
+
+    fn from_context(data: BoxFnPtr, ctx: *mut Context, attempts: u32) -> Result<Self, TimerError> {
+        let mut error = 0;
+        let handle = super::retry(attempts, || unsafe {
+            (*ctx).create().map_err(|code| {
+                error = code;
+                code == libc::EAGAIN
+            })
+        });
+
+        let handle = match handle {
+            Some(handle) => handle,
+            None => {
+                unsafe {
+                    let _ = Box::from_raw(ctx);
+                }
+                return Err(TimerError::Os(error));
+            }
+        };
+
+        unsafe {
+            (*ctx).handle.store(handle, Ordering::Release);
+        }
+
+        Ok(Self {
+            inner: AtomicUsize::new(handle),
+            data: Cell::new(data),
+            ctx: AtomicPtr::new(ctx),
+        })
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, behaviour is undefined (Callback may or may not be called).
+    ///To prevent that user must `cancel` timer first.
+    ///
+    ///Zero `timeout` fires timer as soon as possible, even though `timer_settime` itself treats it as request to disarm.
+    ///
+    ///# Note
+    ///
+    ///- `timeout` and `interval` are truncated by `time_t::max_value()` seconds
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        self.set_interval(timeout, interval).is_ok()
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Same as `schedule_interval`, but returns error if timer is not initialized or OS fails to set timer.
+    ///Instead of truncating, returns `TimerError::Overflow` if `timeout` or `interval` doesn't fit into `timespec`.
+    pub fn try_schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+        check_range(timeout, interval)?;
+        self.set_interval(timeout, interval)
+    }
+
+    fn set_interval(&self, timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        self.settime(0, timeout, interval, ptr::null_mut())
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`, returning its previous schedule.
+    ///
+    ///Previous schedule is time, that has been remaining until the next alarm, and interval, as reported by OS,
+    ///both of which are zero if timer has not been armed.
+    ///
+    ///Returns `None` if timer is not initialized or OS fails to set timer.
+    pub fn schedule_interval_returning(&self, timeout: time::Duration, interval: time::Duration) -> Option<(time::Duration, time::Duration)> {
+        if !self.is_init() {
+            return None;
+        }
+
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        let mut old_value = ffi::itimerspec::zero();
+        self.settime(0, timeout, interval, &mut old_value).ok()?;
+        Some((ffi::duration(&old_value.it_value), ffi::duration(&old_value.it_interval)))
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`, allowing OS
+    ///to delay each alarm by up to `leeway` in order to coalesce it with other timers.
+    ///
+    ///POSIX timers do not support leeway, hence it is ignored, making it the same as `schedule_interval`.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_with_leeway(&self, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        let _ = leeway;
+        self.arm(timeout, interval)
+    }
+
+    ///Schedules timer to alarm periodically with `interval` exactly `count` times, after which it cancels itself.
+    ///
+    ///First alarm happens after `interval`.
+    ///Timer is cancelled from within its own callback, right before invoking user's callback for the last time,
+    ///hence callback may re-schedule timer. Alarms are counted as by `fire_count`.
+    ///
+    ///`count` of 0 does nothing, returning `true`, while `count` of 1 is equivalent to `schedule_once(interval)`.
+    ///Subsequent `schedule_*` call removes the limit.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_count(&self, interval: time::Duration, count: u32) -> bool {
+        match count {
+            0 => true,
+            1 => self.schedule_once(interval),
+            count => {
+                if let Some(ctx) = self.context() {
+                    ctx.armed.limit(count);
+                }
+                self.schedule_interval(interval, interval)
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Schedules timer to alarm periodically with `interval` until `deadline` is reached.
+    ///
+    ///First alarm happens after `interval`.
+    ///Once expired after `deadline`, timer cancels itself from within its own callback, without invoking user's callback.
+    ///As cancellation happens from the timer's own callback, it doesn't wait for callbacks in flight, hence
+    ///it never dead locks.
+    ///
+    ///Subsequent `schedule_*` call removes `deadline`.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_until(&self, interval: time::Duration, deadline: std::time::Instant) -> bool {
+        if let Some(ctx) = self.context() {
+            ctx.deadline.set(Some(deadline));
+        }
+
+        self.arm(interval, interval)
+    }
+
+    #[inline(always)]
+    fn arm(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        self.settime(0, timeout, interval, ptr::null_mut()).is_ok()
+    }
+
+    ///Schedules timer to alarm once after `timeout` passes.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
+    ///
+    ///Returns error if timer is not initialized or OS fails to set timer, or `TimerError::Overflow` if `timeout` doesn't fit into `timespec`.
+    pub fn try_schedule_once(&self, timeout: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+        check_range(timeout, time::Duration::from_secs(0))?;
+
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        self.settime(0, timeout, time::Duration::from_secs(0), ptr::null_mut())
+    }
+
+    fn settime(&self, flags: libc::c_int, timeout: time::Duration, interval: time::Duration, old_value: *mut ffi::itimerspec) -> Result<(), TimerError> {
+        //Zero value disarms timer, hence use the smallest possible one to fire as soon as possible.
+        let timeout = core::cmp::max(timeout, time::Duration::from_nanos(1));
+        let it_value = ffi::timespec(timeout);
+        let it_interval = ffi::timespec(interval);
+
+        let new_value = ffi::itimerspec {
+            it_interval,
+            it_value,
+        };
+
+        let ctx = self.context();
+        let prev_state = ctx.map(|ctx| {
+            ctx.pacing.reset();
+            #[cfg(feature = "std")]
+            match flags & libc::TIMER_ABSTIME {
+                0 => ctx.latency.arm(timeout, interval),
+                _ => ctx.latency.arm(time::Duration::from_nanos((timeout.as_nanos() as u64).saturating_sub(self.clock_now())), interval),
+            }
+            ctx.armed.arm(interval);
+            ctx.state.arm(interval)
+        });
+
+        let result = match ctx {
+            Some(ctx) => unsafe {
+                match ctx.settime(flags, &new_value, old_value) {
+                    0 => Ok(()),
+                    _ => Err(TimerError::Os(ffi::posix_errno())),
+                }
+            },
+            None => Err(TimerError::Uninitialized),
+        };
+
+        if let (Err(_), Some(ctx), Some(prev_state)) = (&result, ctx, prev_state) {
+            ctx.state.restore(prev_state);
+        }
+
+        result
+    }
+
+    #[inline]
+    ///Returns clock, used by timer, as selected on creation (e.g. via `init_with_clock`).
+    ///
+    ///Returns `Clock::Monotonic`, which is used by default, if timer is not initialized.
+    pub fn clock(&self) -> Clock {
+        match self.context() {
+            Some(ctx) => ctx.clock,
+            None => Clock::Monotonic,
+        }
+    }
+
+    ///Returns current value of the timer's clock.
+    ///
+    ///Value is in nanoseconds of the clock, used by timer (`CLOCK_MONOTONIC` unless selected otherwise), and can be used with `schedule_once_at_raw`.
+    pub fn clock_now(&self) -> u64 {
+        let mut now = ffi::timespec(time::Duration::from_secs(0));
+        unsafe {
+            libc::clock_gettime(self.clock().id(), &mut now);
+        }
+
+        (now.tv_sec as u64).saturating_mul(1_000_000_000).saturating_add(now.tv_nsec as u64)
+    }
+
+    #[inline]
+    ///Returns value of the timer's clock after `delta` from now.
+    ///
+    ///See `clock_now` for details.
+    pub fn clock_after(&self, delta: time::Duration) -> u64 {
+        let delta = core::cmp::min(delta.as_nanos(), u64::max_value().into()) as u64;
+        self.clock_now().saturating_add(delta)
+    }
+
+    ///Schedules timer to alarm once, when timer's clock reaches `clock_value`.
+    ///
+    ///`clock_value` must be obtained via `clock_now` or `clock_after` of the same timer, as its meaning depends
+    ///on platform and clock used by the timer.
+    ///If `clock_value` is already in past, timer fires immediately.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_once_at_raw(&self, clock_value: u64) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        //Zero value disarms timer.
+        let clock_value = core::cmp::max(clock_value, 1);
+        self.settime(libc::TIMER_ABSTIME, time::Duration::from_nanos(clock_value), time::Duration::from_secs(0), ptr::null_mut()).is_ok()
+    }
+
+    #[inline]
+    ///Returns `true` if timer has been scheduled and still pending.
+    ///
+    ///On Win/Mac it only returns whether timer has been scheduled, as there is no way to check
+    ///whether timer is ongoing
+    pub fn is_scheduled(&self) -> bool {
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return false,
+        };
+        let curr_value = unsafe {
+            let mut curr_value = mem::MaybeUninit::<ffi::itimerspec>::uninit();
+
+            if ctx.gettime(curr_value.as_mut_ptr()) != 0 {
+                return false;
+            }
+            curr_value.assume_init()
+        };
+
+        !curr_value.is_zero()
+    }
+
+    ///Returns time remaining until next expiration of the timer.
+    ///
+    ///Exact value is queried from OS, unlike Win/Mac where it is approximated.
+    ///Returns `None` if timer is not scheduled or on error.
+    pub fn time_remaining(&self) -> Option<time::Duration> {
+        let ctx = self.context()?;
+        let curr_value = unsafe {
+            let mut curr_value = mem::MaybeUninit::<ffi::itimerspec>::uninit();
+
+            if ctx.gettime(curr_value.as_mut_ptr()) != 0 {
+                return None;
+            }
+            curr_value.assume_init()
+        };
+
+        match ffi::duration(&curr_value.it_value) {
+            remaining if remaining.as_nanos() == 0 => None,
+            remaining => Some(remaining),
+        }
+    }
+
+    #[inline]
+    ///Cancels ongoing timer, if it was scheduled.
+    ///
+    ///Does nothing if timer is not initialized.
+    pub fn cancel(&self) {
+        if !self.is_init() {
+            return;
+        }
+
+        if let Some(ctx) = self.context() {
+            ctx.state.cancel();
+            #[cfg(feature = "std")]
+            ctx.waiters.notify();
+
+            if self.is_scheduled() {
+                unsafe {
+                    ctx.settime(0, &ffi::itimerspec::zero(), ptr::null_mut());
+                }
+            }
+        }
+    }
+
+    ///Returns number of expirations, missed by periodic timer before its most recent run.
+    ///
+    ///Value is reported by kernel (`timer_getoverrun`) on the last delivery, hence it is `0` until timer fires.
+    ///
+    ///Only meaningful on POSIX, while Win/Mac always return `0`.
+    pub fn overrun_count(&self) -> i32 {
+        match self.context() {
+            Some(ctx) => ctx.overrun.load(Ordering::Acquire),
+            None => 0,
+        }
+    }
+
+    ///Returns interval of the periodic timer, as it has been scheduled.
+    ///
+    ///Returns `None` if timer is not initialized, not scheduled or scheduled to fire once.
+    pub fn scheduled_interval(&self) -> Option<time::Duration> {
+        let ctx = self.context()?;
+        match ctx.state.is_periodic() {
+            true => Some(ctx.armed.interval()),
+            false => None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when timer has been scheduled most recently.
+    ///
+    ///Together with `scheduled_interval` it allows to tell when periodic timer is expected to fire next.
+    ///Returns `None` if timer is not initialized or has never been scheduled.
+    pub fn last_armed_at(&self) -> Option<std::time::Instant> {
+        self.context()?.armed.at()
+    }
+
+    ///Pauses scheduled timer, so that `resume` continues its schedule later.
+    ///
+    ///On POSIX timer is disarmed, remembering exact time until its next alarm, as reported by OS,
+    ///hence time spent paused is not accounted and alarms are not missed.
+    ///Callback, that is already running, is not waited for.
+    ///
+    ///Does nothing if timer is not initialized or not scheduled.
+    pub fn pause(&self) {
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+
+        //Previous value is returned atomically, hence timer cannot expire between query and disarm.
+        let mut old_value = ffi::itimerspec::zero();
+        let result = unsafe {
+            ctx.settime(0, &ffi::itimerspec::zero(), &mut old_value)
+        };
+        let remaining = ffi::duration(&old_value.it_value);
+        if result == 0 && remaining.as_nanos() > 0 {
+            ctx.armed.pause(remaining);
+            #[cfg(feature = "std")]
+            ctx.latency.clear();
+        }
+    }
+
+    ///Resumes timer, paused by `pause`, to alarm after time that has been remaining at the moment of pause.
+    ///
+    ///Periodic timer continues with the same `interval` afterwards.
+    ///
+    ///Does nothing if timer is not paused, or it has been cancelled or scheduled again since then.
+    pub fn resume(&self) {
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let remaining = match ctx.armed.take_paused() {
+            Some(remaining) => remaining,
+            None => return,
+        };
+        if ctx.state.get() != TimerState::Armed {
+            return;
+        }
+
+        let interval = ctx.armed.interval();
+        let new_value = ffi::itimerspec {
+            it_interval: ffi::timespec(interval),
+            //Zero value disarms timer, hence use the smallest possible one.
+            it_value: ffi::timespec(core::cmp::max(remaining, time::Duration::from_nanos(1))),
+        };
+        #[cfg(feature = "std")]
+        ctx.latency.arm(remaining, interval);
+        unsafe {
+            ctx.settime(0, &new_value, ptr::null_mut());
+        }
+    }
+
+    #[inline]
+    ///Returns whether timer's callback is currently running.
+    ///
+    ///Result is a snapshot, that may be already outdated once returned: callback may start right after
+    ///`false` is returned, or finish right after `true` is returned.
+    ///Drop timer to actually wait for callback to finish.
+    ///
+    ///Uninitialized timer is never running.
+    pub fn is_running(&self) -> bool {
+        match self.context() {
+            Some(ctx) => ctx.running.is_busy(),
+            None => false,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when timer is expected to fire next.
+    ///
+    ///It is tracked by the crate itself from the time timer was scheduled and its `interval`, without querying OS,
+    ///hence it doesn't account for delays of OS or system suspend.
+    ///Returns `None` if timer is not scheduled (e.g. one shot timer has already fired) or paused.
+    pub fn deadline(&self) -> Option<std::time::Instant> {
+        let ctx = self.context()?;
+        match ctx.state.get() {
+            TimerState::Armed => ctx.latency.next(),
+            _ => None,
+        }
+    }
+
+    ///Returns number of times timer fired, invoking its callback.
+    ///
+    ///Expirations, skipped or merged according to `CatchUp` policy, are counted as one, while `fire_sync` is not counted.
+    pub fn fire_count(&self) -> u64 {
+        match self.context() {
+            Some(ctx) => ctx.fires.load(Ordering::Acquire),
+            None => 0,
+        }
+    }
+
+    ///Sets policy to handle intervals, missed by periodic timer.
+    ///
+    ///Takes effect starting with the next expiration.
+    pub fn set_catch_up(&self, policy: CatchUp) {
+        if let Some(ctx) = self.context() {
+            ctx.pacing.set_policy(policy);
+        }
+    }
+
+    ///Returns policy to handle intervals, missed by periodic timer.
+    pub fn catch_up(&self) -> CatchUp {
+        match self.context() {
+            Some(ctx) => ctx.pacing.policy(),
+            None => CatchUp::default(),
+        }
+    }
+
+    #[inline(always)]
+    ///Returns features, supported by timer on current platform.
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            can_query_remaining: true,
+            can_select_clock: true,
+            supports_leeway: false,
+            callbacks_serialized: false,
+            min_resolution: time::Duration::from_nanos(1),
+            supports_absolute: true,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Blocks current thread until timer's next expiration.
+    ///
+    ///Returns `true` once timer fires, or `false` if timer is not scheduled or gets cancelled while waiting.
+    ///Must not be called from within timer's callback, as next expiration may never come.
+    pub fn wait(&self) -> bool {
+        match self.context() {
+            Some(ctx) => ctx.waiters.wait(&ctx.fires, &ctx.state),
+            None => false,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns how late the most recent expiration was, compared to its scheduled time.
+    ///
+    ///For periodic timer it is relative to the latest point of its schedule, hence it is always less than `interval`.
+    ///Returns `None` if timer has not fired yet.
+    pub fn last_latency(&self) -> Option<time::Duration> {
+        self.context()?.latency.last()
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when the most recent expiration was scheduled to happen.
+    ///
+    ///Together with `last_latency` it is tracked by the crate itself, and for periodic timer it is the latest point of its schedule.
+    ///Returns `None` if timer has not fired yet.
+    pub fn last_deadline(&self) -> Option<std::time::Instant> {
+        self.context()?.latency.due()
+    }
+
+    #[inline]
+    ///Returns timer's lifecycle state.
+    ///
+    ///Uninitialized timer is always `Idle`.
+    pub fn state(&self) -> TimerState {
+        match self.context() {
+            Some(ctx) => ctx.state.get(),
+            None => TimerState::Idle,
+        }
+    }
+
+    ///Returns id of the thread that invoked timer's callback.
+    ///
+    ///Returns `None` if timer is not initialized or callback has not been invoked yet.
+    ///
+    ///Note that callback thread is managed by OS, and some implementations (e.g. glibc) spawn new
+    ///thread on each expiration, hence returned id is of the thread that run the most recent callback and it
+    ///may be already finished.
+    ///Calls to `fire_sync` are not tracked.
+    pub fn callback_thread_id(&self) -> Option<libc::pthread_t> {
+        match self.context()?.thread.load(Ordering::Acquire) {
+            0 => None,
+            thread => Some(thread as libc::pthread_t),
+        }
+    }
+
+    #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+    ///Returns `timerfd` descriptor, if timer is created with `DeliveryMode::TimerFd`.
+    ///
+    ///Descriptor is non-blocking and becomes readable on expiration, at which point user should call `dispatch`.
+    ///It is owned by timer and closed on drop.
+    pub fn timer_fd(&self) -> Option<libc::c_int> {
+        let ctx = self.context()?;
+        match ctx.timerfd {
+            true => Some(ctx.handle.load(Ordering::Acquire) as libc::c_int),
+            false => None,
+        }
+    }
+
+    #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+    ///Consumes expirations of `timerfd`, invoking callback on the calling thread.
+    ///
+    ///Callback is invoked according to `CatchUp` policy (or once with exact count for `Callback::with_count`).
+    ///
+    ///Returns number of expirations, reported by OS, which is 0 if timer has not expired yet, or it is not backed by `timerfd`.
+    pub fn dispatch(&self) -> u64 {
+        let ctx = match self.context() {
+            Some(ctx) if ctx.timerfd => ctx,
+            _ => return 0,
+        };
+
+        let mut expirations = 0u64;
+        let fd = ctx.handle.load(Ordering::Acquire) as libc::c_int;
+        let size = mem::size_of::<u64>();
+        unsafe {
+            if libc::read(fd, &mut expirations as *mut u64 as *mut ffi::c_void, size) != size as isize {
+                return 0;
+            }
+            ctx.fire(ctx.running.enter(), Some(expirations));
+        }
+
+        expirations
+    }
+
+    ///Replaces timer's callback with `cb`, without re-creating OS timer and keeping its schedule.
+    ///
+    ///Callbacks in flight are finished first, after which previous callback (with its closure, if any) is freed.
+    ///Expirations, that happen meanwhile, wait for the swap and then invoke `cb`.
+    ///Concurrent calls are serialized, hence timer can be shared (e.g. via `Arc`) while its callback is replaced.
+    ///
+    ///Must not be called from within timer's own callback, as it would wait for itself forever, nor concurrently with `fire_sync`.
+    ///
+    ///Returns `false` if timer is not initialized, in which case `cb` is dropped.
+    pub fn set_callback(&self, cb: Callback) -> bool {
+        let ctx = match self.context() {
+            Some(ctx) if self.is_init() => ctx,
+            _ => return false,
+        };
+
+        let (data, mut prev) = cb.into_handler();
+        //Swap keeps out both callbacks and concurrent `set_callback`.
+        ctx.running.begin_swap();
+        unsafe {
+            ctx.swap_handler(&mut prev);
+        }
+        //Previous closure is freed with its owner.
+        let prev_data = self.data.replace(data);
+        ctx.running.end_swap();
+
+        drop(prev);
+        drop(prev_data);
+        true
+    }
+
+    ///Invokes timer's callback synchronously on the calling thread.
+    ///
+    ///Unlike scheduling with zero timeout, callback runs before this function returns.
+    ///Primarily intended as testing aid.
+    ///
+    ///Does nothing if timer is not initialized.
+    ///
+    ///# Safety
+    ///
+    ///- Normally callback is invoked on the thread, spawned by OS for the timer. Callback that relies on that
+    ///(e.g. uses thread locals or expects to never run on the thread that owns the timer) must not be invoked this way.
+    ///- If timer is scheduled, OS may invoke callback concurrently with this call. For closures it
+    ///means two mutable references to the same closure, hence user must make sure that timer is not scheduled
+    ///(or that callback is safe to run concurrently) before calling this function.
+    pub unsafe fn fire_sync(&self) {
+        if let Some(ctx) = self.context() {
+            ctx.invoke();
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let handle = self.inner.load(Ordering::Relaxed);
+        if handle != 0 {
+            if let Some(ctx) = self.context() {
+                ctx.running.close();
+                self.cancel();
+                //Callbacks in flight may re-schedule timer (see `Callback::with_timer`).
+                ctx.running.wait_idle();
+                self.cancel();
+                unsafe {
+                    ctx.delete(handle)
+                }
+            }
+        }
+
+        let ctx = self.ctx.load(Ordering::Relaxed);
+        if !ctx.is_null() {
+            unsafe {
+                //Context waits for callbacks, that may still use it and closure, on its own drop.
+                let _ = Box::from_raw(ctx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[test]
+    fn from_raw() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        //OS timer is created directly, without context, hence nothing is leaked once timer deletes it.
+        let handle = unsafe {
+            ffi::posix_timer(Clock::Monotonic.id(), ffi::timer_callback, cb as fn() as *mut ffi::c_void)
+        };
+        assert_ne!(handle, 0);
+
+        let timer = unsafe {
+            Timer::from_raw(handle)
+        };
+        assert!(timer.is_init());
+        assert_eq!(timer.as_raw(), handle);
+
+        //Expirations are delivered to the original callback.
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert_eq!(timer.fire_count(), 0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn with_count_reports_missed() {
+        use std::sync::{Arc, Mutex};
+
+        let counts = Arc::new(Mutex::new(std::vec::Vec::new()));
+        let cb = {
+            let counts = counts.clone();
+            move |count| {
+                let len = {
+                    let mut counts = counts.lock().unwrap();
+                    counts.push(count);
+                    counts.len()
+                };
+                //First run takes several intervals.
+                if len == 1 {
+                    std::thread::sleep(time::Duration::from_millis(110));
+                }
+            }
+        };
+
+        let timer = Timer::new(Callback::with_count(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(20)));
+        std::thread::sleep(time::Duration::from_millis(250));
+        timer.cancel();
+        std::thread::sleep(time::Duration::from_millis(50));
+
+        {
+            let counts = counts.lock().unwrap();
+            assert!(counts.len() >= 2, "Timer fired only {} times", counts.len());
+            assert_eq!(counts[0], 1);
+            assert!(counts[1] >= 3, "Missed expirations are not counted: {:?}", counts);
+        }
+
+        unsafe {
+            timer.fire_sync();
+        }
+        assert_eq!(counts.lock().unwrap().last(), Some(&1));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn with_name() {
+        use std::sync::Mutex;
+        static NAME: Mutex<[u8; THREAD_NAME_LEN]> = Mutex::new([0; THREAD_NAME_LEN]);
+
+        fn cb() {
+            let mut name = [0 as libc::c_char; THREAD_NAME_LEN];
+            unsafe {
+                libc::pthread_getname_np(libc::pthread_self(), name.as_mut_ptr(), name.len());
+            }
+            let mut result = NAME.lock().unwrap();
+            for (dst, src) in result.iter_mut().zip(name.iter()) {
+                *dst = *src as u8;
+            }
+        }
+
+        let timer = Timer::with_name(Callback::plain(cb), "os-timer-name-truncated").expect("To create timer");
+        //There is no callback thread until first expiration.
+        assert_eq!(timer.callback_thread_id(), None);
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+
+        let name = *NAME.lock().unwrap();
+        assert_eq!(&name[..], b"os-timer-name-t\0");
+    }
+
+    #[test]
+    fn timer_gettime_signature() {
+        //Out parameter must be mutable, as in C declaration.
+        const _: unsafe extern "C" fn(ffi::timer_t, *mut ffi::itimerspec) -> libc::c_int = ffi::timer_gettime;
+
+        fn cb() {
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_once(time::Duration::from_secs(10)));
+        let curr_value = unsafe {
+            let mut curr_value = mem::MaybeUninit::<ffi::itimerspec>::uninit();
+            assert_eq!(ffi::timer_gettime(timer.inner.load(Ordering::Relaxed), curr_value.as_mut_ptr()), 0);
+            curr_value.assume_init()
+        };
+        assert!(curr_value.it_value.tv_sec > 0);
+        assert!(curr_value.it_value.tv_sec <= 10);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn delivery_thread_callback() {
+        use std::sync::Arc;
+        use core::sync::atomic::AtomicU64;
+
+        let count = Arc::new(AtomicU64::new(0));
+        let cb = {
+            let count = count.clone();
+            move || {
+                count.fetch_add(1, Ordering::AcqRel);
+            }
+        };
+
+        let timer = Timer::with_delivery(Callback::closure(cb), DeliveryMode::ThreadCallback).expect("To create timer");
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(count.load(Ordering::Acquire), 1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+    #[test]
+    fn delivery_timerfd() {
+        use std::sync::Arc;
+        use core::sync::atomic::AtomicU64;
+
+        let count = Arc::new(AtomicU64::new(0));
+        let cb = {
+            let count = count.clone();
+            move || {
+                count.fetch_add(1, Ordering::AcqRel);
+            }
+        };
+
+        let timer = Timer::with_delivery(Callback::closure(cb), DeliveryMode::TimerFd).expect("To create timer");
+        let fd = timer.timer_fd().expect("To have timerfd");
+        //Not expired yet
+        assert_eq!(timer.dispatch(), 0);
+
+        assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(10)));
+        assert!(timer.is_scheduled());
+        //OS never invokes callback by itself.
+        std::thread::sleep(time::Duration::from_millis(50));
+        assert_eq!(count.load(Ordering::Acquire), 0);
+
+        let mut expirations = 0;
+        let mut dispatches = 0;
+        while expirations < 8 {
+            let mut pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            assert_eq!(unsafe { libc::poll(&mut pollfd, 1, 1000) }, 1);
+            let expired = timer.dispatch();
+            assert_ne!(expired, 0);
+            expirations += expired;
+            dispatches += 1;
+        }
+        //Missed expirations are merged by default policy, hence callback runs once per dispatch.
+        assert_eq!(count.load(Ordering::Acquire), dispatches);
+
+        timer.cancel();
+        assert!(!timer.is_scheduled());
+        std::thread::sleep(time::Duration::from_millis(50));
+        assert_eq!(timer.dispatch(), 0);
+    }
+
+    #[test]
+    fn cpu_time_clock() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        assert!(Timer::new_with_clock(Callback::plain(cb), Clock::ProcessCpuTime).is_some());
+
+        let timer = Timer::new_with_clock(Callback::plain(cb), Clock::ThreadCpuTime).expect("To create timer");
+        assert!(timer.schedule_once(time::Duration::from_millis(50)));
+        //Sleeping thread doesn't consume CPU time.
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 0);
+
+        let start = std::time::Instant::now();
+        while COUNT.load(Ordering::Acquire) == 0 && start.elapsed() < time::Duration::from_secs(5) {
+            core::hint::spin_loop();
+        }
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+    }
+
+    #[test]
+    fn realtime_clock() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new_with_clock(Callback::plain(cb), Clock::Realtime).expect("To create timer");
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).expect("Time after epoch");
+        let clock_now = time::Duration::from_nanos(timer.clock_now());
+        assert!(clock_now >= now);
+        assert!(clock_now - now < time::Duration::from_secs(1));
+
+        assert!(timer.schedule_once_at_raw(timer.clock_after(time::Duration::from_millis(10))));
+        assert!(timer.is_scheduled());
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert!(timer.init_with_clock(Callback::plain(cb), Clock::Realtime));
+        assert!(!timer.init_with_clock(Callback::plain(cb), Clock::Realtime));
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 2);
+    }
+
+    #[test]
+    fn clock() {
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.clock(), Clock::Monotonic);
+        assert!(timer.init_with_clock(Callback::plain(cb), Clock::Realtime));
+        assert_eq!(timer.clock(), Clock::Realtime);
+        //Failed re-initialization keeps original clock.
+        assert!(!timer.init_with_clock(Callback::plain(cb), Clock::Monotonic));
+        assert_eq!(timer.clock(), Clock::Realtime);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert_eq!(timer.clock(), Clock::Monotonic);
+
+        let timer = Timer::new_with_clock(Callback::plain(cb), Clock::Realtime).expect("To create timer");
+        assert_eq!(timer.clock(), Clock::Realtime);
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            let timer = Timer::new_with_clock(Callback::plain(cb), Clock::Boottime).expect("To create timer");
+            assert_eq!(timer.clock(), Clock::Boottime);
+        }
+
+        let timer = TimerBuilder::new().callback(Callback::plain(cb)).clock(Clock::ThreadCpuTime).build().expect("To create timer");
+        assert_eq!(timer.clock(), Clock::ThreadCpuTime);
+    }
+
+    #[test]
+    fn zero_timeout_fires() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_secs(0), time::Duration::from_secs(10)));
+        assert!(timer.is_scheduled());
+        std::thread::sleep(time::Duration::from_millis(100));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert!(timer.is_scheduled());
+
+        assert!(timer.schedule_once(time::Duration::from_secs(0)));
+        std::thread::sleep(time::Duration::from_millis(100));
+        assert_eq!(COUNT.load(Ordering::Acquire), 2);
+        assert!(!timer.is_scheduled());
+    }
+
+    #[test]
+    fn reschedule_interval() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_millis(50), time::Duration::from_millis(50)));
+        assert!(timer.reschedule_interval(time::Duration::from_millis(300), time::Duration::from_secs(0)));
+        assert!(timer.is_scheduled());
+
+        //Old schedule never fires.
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 0);
+
+        std::thread::sleep(time::Duration::from_millis(300));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert!(!timer.is_scheduled());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn overrun_count() {
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.overrun_count(), 0);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert_eq!(timer.overrun_count(), 0);
+
+        //glibc runs each expiration on its own thread, hence slow callback alone doesn't delay delivery.
+        //Interval, that is shorter than time to spawn callback thread, makes expirations pile up while signal is pending.
+        let max_overrun = alloc::sync::Arc::new(AtomicI32::new(0));
+        let timer = {
+            let max_overrun = max_overrun.clone();
+            Timer::new(Callback::with_timer(move |timer: &Timer| {
+                max_overrun.fetch_max(timer.overrun_count(), Ordering::AcqRel);
+                std::thread::sleep(time::Duration::from_micros(100));
+            })).expect("To create timer")
+        };
+        assert!(timer.schedule_interval(time::Duration::from_micros(10), time::Duration::from_micros(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        drop(timer);
+        assert!(max_overrun.load(Ordering::Acquire) > 0);
+
+        #[cfg(all(feature = "timerfd", any(target_os = "linux", target_os = "android")))]
+        {
+            let timer = Timer::with_delivery(Callback::plain(cb), DeliveryMode::TimerFd).expect("To create timer");
+            assert!(timer.schedule_interval(time::Duration::from_millis(5), time::Duration::from_millis(5)));
+            std::thread::sleep(time::Duration::from_millis(50));
+            let expired = timer.dispatch();
+            assert!(expired > 1);
+            assert_eq!(timer.overrun_count() as u64, expired - 1);
+        }
+    }
+
+    #[test]
+    fn time_remaining() {
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.time_remaining(), None);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert_eq!(timer.time_remaining(), None);
+
+        assert!(timer.schedule_once(time::Duration::from_secs(10)));
+        let remaining = timer.time_remaining().expect("To have remaining time");
+        assert!(remaining > time::Duration::from_secs(9));
+        assert!(remaining <= time::Duration::from_secs(10));
+
+        assert!(timer.schedule_once(time::Duration::from_millis(1500)));
+        let remaining = timer.time_remaining().expect("To have remaining time");
+        assert!(remaining > time::Duration::from_millis(1000));
+        assert!(remaining <= time::Duration::from_millis(1500));
+
+        timer.cancel();
+        assert_eq!(timer.time_remaining(), None);
+    }
+
+    #[test]
+    fn capabilities() {
+        const CAPABILITIES: Capabilities = Timer::capabilities();
+
+        assert!(CAPABILITIES.can_query_remaining);
+        assert!(CAPABILITIES.can_select_clock);
+        assert!(!CAPABILITIES.supports_leeway);
+        assert!(!CAPABILITIES.callbacks_serialized);
+        assert_eq!(CAPABILITIES.min_resolution, time::Duration::from_nanos(1));
+        assert_eq!(Timer::RESOLUTION, time::Duration::from_nanos(1));
+        assert_eq!(Timer::resolution(), Timer::RESOLUTION);
+        assert!(CAPABILITIES.supports_absolute);
+    }
+
+    #[test]
+    fn init_plain_fn() {
+        let mut timer = unsafe {
+            Timer::uninit()
+        };
+
+        fn cb() {
+        }
+
+        #[cfg(feature = "alloc")]
+        let closure = || {
+        };
+
+        assert!(timer.init(Callback::plain(cb)));
+        let ptr = timer.inner.load(Ordering::Relaxed);
+        assert_ne!(ptr, 0);
+        assert!(timer.data.get_mut().is_null());
+
+        #[cfg(feature = "alloc")]
+        assert!(!timer.init(Callback::closure(closure)));
+        assert_ne!(ptr, 0);
+        assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
+        assert!(timer.data.get_mut().is_null());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn init_closure() {
+        let mut timer = unsafe {
+            Timer::uninit()
+        };
+
+        fn cb() {
+        }
+
+        let closure = || {
+        };
+
+        assert!(timer.init(Callback::closure(closure)));
+        let ptr = timer.inner.load(Ordering::Relaxed);
+        assert_ne!(ptr, 0);
+        assert!(!timer.data.get_mut().is_null());
 
         assert!(!timer.init(Callback::plain(cb)));
         assert_ne!(ptr, 0);
         assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
         assert!(!timer.data.get_mut().is_null());
     }
+
+    #[test]
+    fn reschedule_fired_once() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        let handle = timer.inner.load(Ordering::Relaxed);
+
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert!(!timer.is_scheduled());
+        assert_eq!(timer.state(), TimerState::Fired);
+
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        assert!(timer.is_scheduled());
+        assert_eq!(timer.state(), TimerState::Armed);
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 2);
+        assert!(!timer.is_scheduled());
+        assert_eq!(timer.state(), TimerState::Fired);
+        assert_eq!(handle, timer.inner.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn schedule_once_replaces_interval() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_secs(1), time::Duration::from_millis(20)));
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+
+        let mut curr_value = ffi::itimerspec::zero();
+        unsafe {
+            assert_eq!(ffi::timer_gettime(timer.inner.load(Ordering::Relaxed), &mut curr_value), 0);
+        }
+        assert_eq!(curr_value.it_interval.tv_sec, 0);
+        assert_eq!(curr_value.it_interval.tv_nsec, 0);
+
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert!(!timer.is_scheduled());
+    }
+
+    #[test]
+    fn schedule_interval_returning() {
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.schedule_interval_returning(time::Duration::from_secs(1), time::Duration::from_secs(0)), None);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        let (value, interval) = timer.schedule_interval_returning(time::Duration::from_secs(10), time::Duration::from_secs(0)).expect("To schedule timer");
+        assert_eq!(value, time::Duration::from_secs(0));
+        assert_eq!(interval, time::Duration::from_secs(0));
+
+        let (value, interval) = timer.schedule_interval_returning(time::Duration::from_secs(20), time::Duration::from_secs(5)).expect("To schedule timer");
+        assert!(value > time::Duration::from_secs(9));
+        assert!(value <= time::Duration::from_secs(10));
+        assert_eq!(interval, time::Duration::from_secs(0));
+
+        let (value, interval) = timer.schedule_interval_returning(time::Duration::from_secs(1), time::Duration::from_secs(0)).expect("To schedule timer");
+        assert!(value > time::Duration::from_secs(19));
+        assert!(value <= time::Duration::from_secs(20));
+        assert_eq!(interval, time::Duration::from_secs(5));
+        timer.cancel();
+    }
+
+    #[test]
+    fn nanosecond_precision() {
+        fn cb() {
+        }
+
+        let value = time::Duration::from_nanos(999_999_999);
+        let spec = ffi::timespec(time::Duration::from_secs(1) + value);
+        assert_eq!(spec.tv_sec, 1);
+        assert_eq!(spec.tv_nsec, 999_999_999);
+        assert_eq!(ffi::duration(&spec), time::Duration::from_secs(1) + value);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_secs(10), value));
+
+        let mut curr_value = ffi::itimerspec::zero();
+        unsafe {
+            assert_eq!(ffi::timer_gettime(timer.inner.load(Ordering::Relaxed), &mut curr_value), 0);
+        }
+        assert_eq!(curr_value.it_interval.tv_sec, 0);
+        assert_eq!(curr_value.it_interval.tv_nsec, 999_999_999);
+        timer.cancel();
+    }
+
+    #[test]
+    fn try_schedule_once_error() {
+        use super::TimerError;
+
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.try_schedule_once(time::Duration::from_millis(10)), Err(TimerError::Uninitialized));
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert_eq!(timer.try_schedule_once(time::Duration::from_secs(u64::max_value())), Err(TimerError::Overflow));
+        assert_eq!(timer.state(), TimerState::Idle);
+
+        assert_eq!(timer.try_schedule_once(time::Duration::from_secs(10)), Ok(()));
+        assert_eq!(timer.state(), TimerState::Armed);
+        timer.cancel();
+    }
+
+    #[test]
+    fn new_signal() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        extern "C" fn handler(_: libc::c_int) {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        //Handler is process wide, hence use signal, that nothing else in tests relies on, and restore it afterwards.
+        let signo = libc::SIGUSR2;
+        let mut old_action: libc::sigaction = unsafe {
+            mem::zeroed()
+        };
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handler as *const () as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            assert_eq!(libc::sigaction(signo, &action, &mut old_action), 0);
+        }
+
+        let timer = Timer::new_signal(Clock::Monotonic, signo).expect("To create timer");
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        let count = COUNT.load(Ordering::Acquire);
+        let is_scheduled = timer.is_scheduled();
+        let fire_count = timer.fire_count();
+        drop(timer);
+
+        unsafe {
+            assert_eq!(libc::sigaction(signo, &old_action, ptr::null_mut()), 0);
+        }
+        assert_eq!(count, 1);
+        assert!(!is_scheduled);
+        assert_eq!(fire_count, 0);
+
+        assert!(Timer::new_signal(Clock::Monotonic, -1).is_none());
+    }
+
+    #[test]
+    fn as_raw() {
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.as_raw(), 0);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        let handle = timer.as_raw();
+        assert_ne!(handle, 0);
+        assert_eq!(handle, timer.context().expect("To have context").handle.load(Ordering::Acquire));
+
+        //Handle can be used with OS API directly.
+        assert!(timer.schedule_once(time::Duration::from_secs(10)));
+        let mut curr_value = ffi::itimerspec::zero();
+        assert_eq!(unsafe { ffi::timer_gettime(handle, &mut curr_value) }, 0);
+        assert!(!curr_value.is_zero());
+    }
+
+    #[test]
+    fn callback_thread_id() {
+        fn cb() {
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.callback_thread_id().is_none());
+
+        assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_secs(0)));
+        std::thread::sleep(time::Duration::from_millis(200));
+
+        let thread = timer.callback_thread_id().expect("To have callback thread");
+        assert_ne!(thread, 0);
+        assert_ne!(thread, unsafe { libc::pthread_self() });
+    }
 }
@@ -0,0 +1,1264 @@
+use core::{fmt, time, ptr, mem};
+use core::cmp::Reverse;
+use core::cell::{Cell, UnsafeCell};
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+use super::{Armed, BoxFnPtr, BoxedCallback, Capabilities, CatchUp, Clock, Lifecycle, Pacing, Running, RunningGuard, TimerBuilder, TimerError, TimerState};
+use super::{Deadline, Latency, Waiters};
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BinaryHeap};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Instant;
+
+//Signature of raw callback, which receives data, passed to `Callback::raw`.
+type RawCallback = unsafe fn(data: *mut c_void);
+
+unsafe fn timer_callback(data: *mut c_void) {
+    if !data.is_null() {
+        let cb: fn() -> () = mem::transmute(data);
+
+        (cb)();
+    }
+}
+
+unsafe fn timer_callback_unsafe(data: *mut c_void) {
+    if !data.is_null() {
+        let cb: unsafe fn() -> () = mem::transmute(data);
+
+        (cb)();
+    }
+}
+
+unsafe fn timer_callback_generic<T: FnMut() -> ()>(data: *mut c_void) {
+    if !data.is_null() {
+        let cb = &mut *(data as *mut T);
+
+        (cb)();
+    }
+}
+
+unsafe fn timer_callback_boxed(data: *mut c_void) {
+    if !data.is_null() {
+        super::BoxFnPtr::invoke(data as *mut ());
+    }
+}
+
+unsafe fn timer_callback_inline<T: FnMut() -> () + Copy>(data: *mut c_void) {
+    let mut cb = super::InlineClosure::<T>::unpack(data as *mut ());
+
+    (cb)();
+}
+
+//Schedule of armed timer.
+struct Slot {
+    //Distinguishes entries of the current arm from stale ones, left in queue by previous arms.
+    generation: u64,
+    //`None` if due time cannot be represented by `Instant`, in which case timer never fires.
+    due: Option<Instant>,
+    interval: time::Duration,
+}
+
+//Timers, that are armed, ordered by their due time.
+struct Queue {
+    //Stale entries of re-scheduled or cancelled timers are skipped, once they reach the top.
+    //Created on first use, as `BinaryHeap::new` cannot be used in static on MSRV.
+    heap: Option<BinaryHeap<Reverse<(Instant, u64, usize)>>>,
+    //Schedule of every armed timer, keyed by address of its context.
+    slots: BTreeMap<usize, Slot>,
+    generation: u64,
+    started: bool,
+}
+
+impl Queue {
+    #[inline(always)]
+    fn heap(&mut self) -> &mut BinaryHeap<Reverse<(Instant, u64, usize)>> {
+        self.heap.get_or_insert_with(BinaryHeap::new)
+    }
+
+    //Drops stale entries, once they outnumber live ones, as timer, that is re-scheduled before it fires, leaves one behind.
+    fn compact(&mut self) {
+        let mut heap = match self.heap.take() {
+            Some(heap) if heap.len() > 2 * self.slots.len() + 64 => heap.into_vec(),
+            heap => {
+                self.heap = heap;
+                return;
+            }
+        };
+
+        let slots = &self.slots;
+        heap.retain(|Reverse((_, generation, key))| slots.get(key).map_or(false, |slot| slot.generation == *generation));
+        self.heap = Some(heap.into());
+    }
+}
+
+//Background thread, that waits for the nearest due time and invokes callbacks of expired timers.
+struct Scheduler {
+    queue: Mutex<Queue>,
+    cond: Condvar,
+}
+
+static SCHEDULER: Scheduler = Scheduler {
+    queue: Mutex::new(Queue {
+        heap: None,
+        slots: BTreeMap::new(),
+        generation: 0,
+        started: false,
+    }),
+    cond: Condvar::new(),
+};
+
+impl Scheduler {
+    #[inline(always)]
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    //Spawns scheduler's thread, unless it is already running.
+    fn start(&'static self) -> Result<(), TimerError> {
+        let mut queue = self.lock();
+        if queue.started {
+            return Ok(());
+        }
+
+        match std::thread::Builder::new().name("os-timer".into()).spawn(move || self.run()) {
+            Ok(_) => {
+                queue.started = true;
+                Ok(())
+            },
+            Err(error) => Err(TimerError::Os(error.raw_os_error().unwrap_or(0))),
+        }
+    }
+
+    fn arm(&self, key: usize, timeout: time::Duration, interval: time::Duration) {
+        let mut queue = self.lock();
+        queue.generation += 1;
+        let generation = queue.generation;
+        let due = Instant::now().checked_add(timeout);
+        if let Some(due) = due {
+            queue.heap().push(Reverse((due, generation, key)));
+        }
+        queue.slots.insert(key, Slot {
+            generation,
+            due,
+            interval,
+        });
+        queue.compact();
+        drop(queue);
+
+        self.cond.notify_one();
+    }
+
+    //Removes schedule of timer, returning it, if timer has been armed.
+    fn disarm(&self, key: usize) -> Option<Slot> {
+        self.lock().slots.remove(&key)
+    }
+
+    //Removes schedule of timer, unless it has been re-scheduled since arm of `generation`.
+    fn disarm_generation(&self, key: usize, generation: u64) {
+        let mut queue = self.lock();
+        if queue.slots.get(&key).map_or(false, |slot| slot.generation == generation) {
+            queue.slots.remove(&key);
+        }
+    }
+
+    //Returns due time of timer, if it is armed.
+    fn due(&self, key: usize) -> Option<Option<Instant>> {
+        self.lock().slots.get(&key).map(|slot| slot.due)
+    }
+
+    fn run(&self) -> ! {
+        let mut queue = self.lock();
+        loop {
+            let (due, generation, key) = match queue.heap().peek() {
+                Some(Reverse(entry)) => *entry,
+                None => {
+                    queue = self.cond.wait(queue).unwrap_or_else(|error| error.into_inner());
+                    continue;
+                }
+            };
+            let interval = match queue.slots.get(&key) {
+                Some(slot) if slot.generation == generation => slot.interval,
+                _ => {
+                    queue.heap().pop();
+                    continue;
+                }
+            };
+
+            let now = Instant::now();
+            if due > now {
+                queue = self.cond.wait_timeout(queue, due - now).unwrap_or_else(|error| error.into_inner()).0;
+                continue;
+            }
+
+            queue.heap().pop();
+            let missed = match interval.is_zero() {
+                true => {
+                    queue.slots.remove(&key);
+                    0
+                },
+                false => {
+                    let (next, missed) = next_due(due, interval, now);
+                    if let Some(next) = next {
+                        queue.heap().push(Reverse((next, generation, key)));
+                    }
+                    if let Some(slot) = queue.slots.get_mut(&key) {
+                        slot.due = next;
+                    }
+                    missed
+                }
+            };
+
+            //Context is freed only after it is disarmed and its callbacks are done, hence entering it under lock pins it.
+            let ctx = unsafe {
+                &*(key as *const Context)
+            };
+            let running = ctx.running.enter();
+            drop(queue);
+            unsafe {
+                ctx.fire(running, generation, missed);
+            }
+            queue = self.lock();
+        }
+    }
+}
+
+//Returns next due time of periodic timer, that has been due at `due`, skipping intervals, that already passed by `now`,
+//along with number of skipped intervals.
+fn next_due(due: Instant, interval: time::Duration, now: Instant) -> (Option<Instant>, u64) {
+    let missed = now.saturating_duration_since(due).as_nanos() / interval.as_nanos();
+    let offset = interval.as_nanos().saturating_mul(missed + 1);
+    let next = match offset <= u64::max_value().into() {
+        true => due.checked_add(time::Duration::from_nanos(offset as u64)),
+        false => None,
+    };
+
+    (next, core::cmp::min(missed, u64::max_value().into()) as u64)
+}
+
+//Returns `TimerError::Overflow` if due time doesn't fit into `Instant`.
+fn check_range(timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+    match Instant::now().checked_add(timeout).and_then(|due| due.checked_add(interval)) {
+        Some(_) => Ok(()),
+        None => Err(TimerError::Overflow),
+    }
+}
+
+type CountedCallback = Box<dyn FnMut(&Timer, u64) + Send>;
+
+///Raw handle of software timer: address of its state, which identifies timer within scheduler.
+///
+///There is no OS timer behind it, hence it is only useful to tell timers apart (e.g. in logs).
+pub type RawTimer = *mut c_void;
+
+enum CallbackVariant {
+    Trivial(*mut c_void),
+    Boxed(BoxedCallback),
+    //Closure, boxed by user, which is owned by context and invoked through fat pointer.
+    Dyn(BoxedCallback),
+    Counted(CountedCallback),
+}
+
+///Timer's callback abstraction
+pub struct Callback {
+    variant: CallbackVariant,
+    ffi_cb: RawCallback,
+}
+
+impl Callback {
+    ///Creates raw callback for platform timer.
+    ///
+    ///Signature depends on platform: software timer invokes `unsafe fn(*mut c_void)` with `data`.
+    pub const unsafe fn raw(ffi_cb: unsafe fn(data: *mut c_void), data: *mut c_void) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(data),
+            ffi_cb,
+        }
+    }
+
+    ///Creates callback using plain rust function
+    pub const fn plain(cb: fn()) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(cb as _),
+            ffi_cb: timer_callback,
+        }
+    }
+
+    ///Creates callback using plain unsafe function
+    pub const fn unsafe_plain(cb: unsafe fn()) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(cb as _),
+            ffi_cb: timer_callback_unsafe,
+        }
+    }
+
+    ///Creates callback using closure, storing it on heap.
+    pub fn closure<F: 'static + FnMut() + Send>(cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Boxed(Box::new(cb)),
+            ffi_cb: timer_callback_generic::<F>,
+        }
+    }
+
+    ///Creates callback using small closure, stored in place of function's data instead of heap.
+    ///
+    ///Closure must fit into pointer (i.e. capture nothing or single reference), which is checked at compile time.
+    ///Being `Copy`, closure is copied for each run, hence changes to its captured state are not preserved between runs.
+    pub fn inline<F: 'static + FnMut() + Copy + Send>(cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(super::InlineClosure::pack(cb) as *mut c_void),
+            ffi_cb: timer_callback_inline::<F>,
+        }
+    }
+
+    #[inline(always)]
+    ///Creates callback using already boxed closure.
+    ///
+    ///Closure is stored as it is, without boxing it again.
+    pub fn boxed(cb: BoxedCallback) -> Self {
+        Self {
+            variant: CallbackVariant::Dyn(cb),
+            ffi_cb: timer_callback_boxed,
+        }
+    }
+
+    ///Creates callback using closure, that receives number of intervals elapsed since its previous run.
+    ///
+    ///Software timer counts intervals, that passed while scheduler was busy, exactly.
+    ///It is always 1 for one shot timer.
+    ///
+    ///Callback is run once per expiration, regardless of `CatchUp` policy.
+    pub fn with_elapsed<F: 'static + FnMut(u32) + Send>(mut cb: F) -> Self {
+        Self::with_count(move |count| cb(core::cmp::min(count, u32::max_value().into()) as u32))
+    }
+
+    ///Creates callback using closure, that receives number of expirations since its previous run.
+    ///
+    ///Same as `with_elapsed`, but without saturating count to `u32`.
+    pub fn with_count<F: 'static + FnMut(u64) + Send>(mut cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Counted(Box::new(move |_: &Timer, count| cb(count))),
+            ffi_cb: timer_callback,
+        }
+    }
+
+    ///Creates callback using closure, that receives reference to the timer, which invokes it.
+    ///
+    ///It allows callback to re-schedule (or cancel) its own timer, e.g. to implement backoff.
+    ///
+    ///Callback receives a view of the timer, sharing its context, rather than timer itself,
+    ///as `Timer` may be moved after creation. It is sound, because timer outlives its callbacks: on drop it
+    ///stops invoking callbacks, waits for ones in flight, and only then frees its context.
+    ///
+    ///Callback is run once per expiration, regardless of `CatchUp` policy.
+    pub fn with_timer<F: 'static + FnMut(&Timer) + Send>(mut cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Counted(Box::new(move |timer: &Timer, _| cb(timer))),
+            ffi_cb: timer_callback,
+        }
+    }
+
+    ///Creates callback using closure, that keeps periodic timer running as long as it returns `true`.
+    ///
+    ///Once closure returns `false`, timer cancels itself right after that invocation, until it is scheduled again.
+    pub fn repeating<F: 'static + FnMut() -> bool + Send>(mut cb: F) -> Self {
+        Self::with_timer(move |timer| {
+            if !cb() {
+                timer.cancel();
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Callback {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //Closure is opaque and function pointer is meaningless to user, hence only kind of callback is printed.
+        let variant = match self.variant {
+            CallbackVariant::Trivial(_) => "Trivial",
+            CallbackVariant::Boxed(_) | CallbackVariant::Dyn(_) => "Boxed",
+            CallbackVariant::Counted(_) => "Counted",
+        };
+        fmt.debug_struct("Callback").field("variant", &format_args!("{}", variant)).finish()
+    }
+}
+
+impl Callback {
+    fn into_handler(self) -> (BoxFnPtr, Handler) {
+        let mut handler = Handler {
+            ffi_cb: self.ffi_cb,
+            ffi_data: ptr::null_mut(),
+            boxed: BoxFnPtr::new(),
+            counted: None,
+        };
+        let data = match self.variant {
+            CallbackVariant::Trivial(data) => {
+                handler.ffi_data = data;
+                BoxFnPtr::new()
+            },
+            CallbackVariant::Boxed(cb) => {
+                let data = BoxFnPtr::from_box(cb);
+                handler.ffi_data = data.0 as *mut c_void;
+                data
+            },
+            //Data is pointed to `boxed`, once handler is at its place (see `Handler::bind_boxed`).
+            CallbackVariant::Dyn(cb) => {
+                handler.boxed = BoxFnPtr::from_box(cb);
+                BoxFnPtr::new()
+            },
+            CallbackVariant::Counted(cb) => {
+                super::closure_stored();
+                handler.counted = Some(cb);
+                BoxFnPtr::new()
+            },
+        };
+
+        (data, handler)
+    }
+
+    fn into_context(self) -> (BoxFnPtr, *mut Context) {
+        let (data, handler) = self.into_handler();
+
+        let ctx = Box::into_raw(Box::new(Context {
+            handler: UnsafeCell::new(handler),
+            state: Lifecycle::new(),
+            armed: Armed::new(),
+            fires: AtomicU64::new(0),
+            running: Running::new(),
+            pacing: Pacing::new(),
+            deadline: Deadline::new(),
+            latency: Latency::new(),
+            waiters: Waiters::new(),
+        }));
+        unsafe {
+            Handler::bind_boxed((*ctx).handler.get());
+        }
+        (data, ctx)
+    }
+}
+
+//Callback, that context invokes on expiration.
+struct Handler {
+    ffi_cb: RawCallback,
+    ffi_data: *mut c_void,
+    //Closure of `Callback::boxed`, which `ffi_data` points to.
+    boxed: BoxFnPtr,
+    //Callback, that receives number of expirations, used instead of `ffi_cb`.
+    counted: Option<CountedCallback>,
+}
+
+impl Handler {
+    //Points `ffi_data` to `boxed`, if callback is created by `Callback::boxed`.
+    //
+    //As handler refers to itself then, it must be called once handler is at its final place.
+    unsafe fn bind_boxed(this: *mut Self) {
+        if !(*this).boxed.is_null() {
+            (*this).ffi_data = ptr::addr_of_mut!((*this).boxed) as *mut c_void;
+        }
+    }
+}
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        if self.counted.take().is_some() {
+            super::closure_freed();
+        }
+    }
+}
+
+//Timer's state, through which scheduler invokes actual callback.
+struct Context {
+    //Callbacks access it only once they enter `running` and wait out swap, hence `set_callback` replaces it
+    //between `begin_swap` and `end_swap` of `running`, even though context is shared.
+    handler: UnsafeCell<Handler>,
+    state: Lifecycle,
+    armed: Armed,
+    //Number of expirations, that reached user's callback.
+    fires: AtomicU64,
+    running: Running,
+    pacing: Pacing,
+    deadline: Deadline,
+    latency: Latency,
+    waiters: Waiters,
+}
+
+impl Context {
+    #[inline(always)]
+    //Returns key, that identifies timer within scheduler.
+    fn key(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    //Creates view of the timer, that owns context, so that it can be passed to callback.
+    //
+    //View doesn't own closure, and it must not outlive context, hence it is never dropped.
+    unsafe fn timer_view(&self) -> mem::ManuallyDrop<Timer> {
+        mem::ManuallyDrop::new(Timer {
+            data: Cell::new(BoxFnPtr::new()),
+            ctx: AtomicPtr::new(self as *const Self as *mut Self),
+        })
+    }
+
+    //Exchanges callback with `other`.
+    //
+    //Caller must make sure that callbacks do not access it meanwhile (see `Timer::set_callback`).
+    unsafe fn swap_handler(&self, other: &mut Handler) {
+        let handler = self.handler.get();
+        ptr::swap(handler, other);
+        Handler::bind_boxed(handler);
+        Handler::bind_boxed(other);
+    }
+
+    #[inline(always)]
+    unsafe fn invoke(&self) {
+        self.invoke_count(1)
+    }
+
+    #[inline(always)]
+    unsafe fn invoke_count(&self, count: u64) {
+        let handler = &mut *self.handler.get();
+        match &mut handler.counted {
+            Some(cb) => cb(&self.timer_view(), count),
+            None => (handler.ffi_cb)(handler.ffi_data),
+        }
+    }
+
+    unsafe fn fire(&self, running: RunningGuard<'_>, generation: u64, missed: u64) {
+        if running.is_closed() {
+            return;
+        }
+        //Callback is not accessed until `set_callback` replaces it.
+        running.wait_swap();
+        self.latency.fire();
+
+        if self.deadline.is_expired() {
+            self.state.cancel();
+            self.waiters.notify();
+            SCHEDULER.disarm_generation(self.key(), generation);
+            return;
+        }
+
+        let is_periodic = self.state.is_periodic();
+        if is_periodic {
+            self.pacing.miss(core::cmp::min(missed, u32::max_value().into()) as u32);
+        }
+
+        let is_last = match self.armed.take_alarm() {
+            Some(is_last) => is_last,
+            //Alarm, that raced with the last one of `schedule_count`.
+            None => return,
+        };
+        if is_last {
+            self.state.cancel();
+            SCHEDULER.disarm_generation(self.key(), generation);
+        }
+        self.state.fire();
+        self.fires.fetch_add(1, Ordering::AcqRel);
+        self.waiters.notify();
+        if (*self.handler.get()).counted.is_some() {
+            let count = match is_periodic {
+                true => u64::from(self.pacing.take_missed()) + 1,
+                false => 1,
+            };
+            self.invoke_count(count);
+        } else {
+            let runs = match is_periodic {
+                true => self.pacing.runs(),
+                false => 1,
+            };
+            for _ in 0..runs {
+                self.invoke();
+            }
+        }
+    }
+}
+
+///Software timer, driven by background thread of the crate.
+///
+///Used on targets, that have neither POSIX timers, nor dispatch, nor Windows thread pool, or when `software` feature is enabled.
+///It is best-effort: all timers share single thread, that sleeps until the nearest due time, hence resolution is
+///as coarse as thread sleep of the OS, and long running callback delays callbacks of all other timers.
+///
+///# Drop
+///
+///Dropping timer waits for its callback, if it is already running, to finish, hence timer must not be dropped
+///from within its own callback (e.g. by taking it out of shared `Mutex<Option<Timer>>`), as it would never return.
+pub struct Timer {
+    //Owns closure, if any.
+    //Set only once by thread, that initialized timer, and otherwise accessed only on drop,
+    //hence it is safe to share timer between threads.
+    data: Cell<BoxFnPtr>,
+    ctx: AtomicPtr<Context>,
+}
+
+impl Timer {
+    #[inline]
+    ///Creates new uninitialized instance.
+    ///
+    ///In order to use it one must call `init`.
+    ///
+    ///Initialized timer cannot be reset in place, as other threads may use it concurrently, hence to re-use it
+    ///(e.g. in object pool) assign `uninit` to it: old timer is dropped, which cancels it, waits for its
+    ///callbacks in flight and frees OS timer together with callback, after which `init` succeeds again.
+    pub const unsafe fn uninit() -> Self {
+        Self {
+            data: Cell::new(BoxFnPtr::new()),
+            ctx: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    #[inline(always)]
+    fn context(&self) -> Option<&Context> {
+        unsafe {
+            self.ctx.load(Ordering::Acquire).as_ref()
+        }
+    }
+
+    #[inline(always)]
+    ///Returns raw handle of timer, which is address of its state.
+    ///
+    ///Returns null if timer is not initialized.
+    pub fn as_raw(&self) -> RawTimer {
+        self.ctx.load(Ordering::Acquire) as RawTimer
+    }
+
+    #[inline(always)]
+    ///Returns whether timer is initialized
+    pub fn is_init(&self) -> bool {
+        !self.ctx.load(Ordering::Acquire).is_null()
+    }
+
+    #[must_use]
+    ///Performs timer initialization
+    ///
+    ///`cb` is variant of callback to invoke when timer expires
+    ///
+    ///Returns whether timer has been initialized successfully or not.
+    ///
+    ///If timer is already initialized does nothing, returning false.
+    ///
+    ///Initialization is atomic: when multiple threads race to initialize the same timer, exactly one
+    ///succeeds, while the rest release callback they created, returning false.
+    pub fn init(&self, cb: impl Into<Callback>) -> bool {
+        self.try_init(cb).is_ok()
+    }
+
+    ///Performs timer initialization
+    ///
+    ///Same as `init`, but returns reason of failure:
+    ///
+    ///- `TimerError::AlreadyInitialized` if timer is already initialized, in which case it does nothing.
+    ///- `TimerError::Os` with OS error code if scheduler's thread cannot be spawned.
+    pub fn try_init(&self, cb: impl Into<Callback>) -> Result<(), TimerError> {
+        if self.is_init() {
+            return Err(TimerError::AlreadyInitialized);
+        }
+
+        SCHEDULER.start()?;
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
+
+        match self.ctx.compare_exchange(ptr::null_mut(), ctx, Ordering::SeqCst, Ordering::Acquire) {
+            Ok(_) => {
+                //safe because only the thread, that claimed context, can reach here.
+                self.data.set(data);
+                Ok(())
+            },
+            Err(_) => {
+                unsafe {
+                    let _ = Box::from_raw(ctx);
+                }
+                //Closure of the losing thread is never reachable from timer.
+                drop(data);
+                Err(TimerError::AlreadyInitialized)
+            }
+        }
+    }
+
+    #[inline(always)]
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///`cb` can be plain function or closure, as well as `Callback`.
+    ///
+    ///On failure, returns `None`
+    pub fn new(cb: impl Into<Callback>) -> Option<Self> {
+        Self::new_with_retry(cb.into(), 1)
+    }
+
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///Same as `new`, but on failure returns `TimerError::Os` with OS error code.
+    pub fn try_new(cb: impl Into<Callback>) -> Result<Self, TimerError> {
+        Self::create(cb.into(), 1)
+    }
+
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///Timer itself cannot fail to be created, but scheduler's thread is spawned by the first timer, hence it is
+    ///retried up to `attempts` times in total, pausing between attempts (starting with 1ms and doubling each time, up to 64ms).
+    ///
+    ///On failure, returns `None`
+    pub fn new_with_retry(cb: impl Into<Callback>, attempts: u32) -> Option<Self> {
+        Self::create(cb.into(), attempts).ok()
+    }
+
+    pub(super) fn from_builder(builder: TimerBuilder) -> Result<Self, TimerError> {
+        if builder.clock.map_or(false, Clock::is_cpu_time) {
+            return Err(TimerError::Unsupported);
+        }
+        Self::create(builder.callback.ok_or(TimerError::Uninitialized)?, 1)
+    }
+
+    fn create(cb: Callback, attempts: u32) -> Result<Self, TimerError> {
+        let mut error = 0;
+        let started = super::retry(attempts, || match SCHEDULER.start() {
+            Ok(()) => Ok(()),
+            Err(TimerError::Os(code)) => {
+                error = code;
+                Err(true)
+            },
+            Err(_) => Err(false),
+        });
+        if started.is_none() {
+            return Err(TimerError::Os(error));
+        }
+
+        let (data, ctx) = cb.into_context();
+        Ok(Self {
+            data: Cell::new(data),
+            ctx: AtomicPtr::new(ctx),
+        })
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be re-scheduled.
+    ///
+    ///# Note
+    ///
+    ///- Zero `interval` makes timer one shot, while zero `timeout` makes it alarm as soon as possible
+    ///- If due time doesn't fit into `Instant`, timer stays scheduled, but never fires
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        if let Some(ctx) = self.context() {
+            ctx.deadline.set(None);
+        }
+
+        self.arm(timeout, interval)
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Same as `schedule_interval`, but returns error if timer is not initialized.
+    ///Instead of never firing, returns `TimerError::Overflow` if due time doesn't fit into `Instant`.
+    pub fn try_schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+        check_range(timeout, interval)?;
+
+        self.schedule_interval(timeout, interval);
+        Ok(())
+    }
+
+    #[inline]
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Software timer doesn't coalesce, hence `leeway` is ignored, making it the same as `schedule_interval`.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_with_leeway(&self, timeout: time::Duration, interval: time::Duration, _leeway: time::Duration) -> bool {
+        self.schedule_interval(timeout, interval)
+    }
+
+    ///Schedules timer to alarm periodically with `interval` exactly `count` times, after which it cancels itself.
+    ///
+    ///First alarm happens after `interval`.
+    ///Timer is cancelled from within its own callback, right before invoking user's callback for the last time,
+    ///hence callback may re-schedule timer. Alarms are counted as by `fire_count`.
+    ///
+    ///`count` of 0 does nothing, returning `true`, while `count` of 1 is equivalent to `schedule_once(interval)`.
+    ///Subsequent `schedule_*` call removes the limit.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_count(&self, interval: time::Duration, count: u32) -> bool {
+        match count {
+            0 => true,
+            1 => self.schedule_once(interval),
+            count => {
+                if let Some(ctx) = self.context() {
+                    ctx.armed.limit(count);
+                }
+                self.schedule_interval(interval, interval)
+            }
+        }
+    }
+
+    ///Schedules timer to alarm periodically with `interval` until `deadline` is reached.
+    ///
+    ///First alarm happens after `interval`.
+    ///Once expired after `deadline`, timer cancels itself from within its own callback, without invoking user's callback.
+    ///
+    ///Subsequent `schedule_*` call removes `deadline`.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_until(&self, interval: time::Duration, deadline: Instant) -> bool {
+        if let Some(ctx) = self.context() {
+            ctx.deadline.set(Some(deadline));
+        }
+
+        self.arm(interval, interval)
+    }
+
+    ///Schedules timer to alarm once after `timeout` passes.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
+    ///
+    ///Returns error if timer is not initialized, or `TimerError::Overflow` if due time doesn't fit into `Instant`.
+    pub fn try_schedule_once(&self, timeout: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+        check_range(timeout, time::Duration::from_secs(0))?;
+
+        self.schedule_interval(timeout, time::Duration::from_secs(0));
+        Ok(())
+    }
+
+    fn arm(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return false,
+        };
+
+        ctx.pacing.reset();
+        ctx.latency.arm(timeout, interval);
+        ctx.armed.arm(interval);
+        ctx.state.arm(interval);
+        SCHEDULER.arm(ctx.key(), timeout, interval);
+        true
+    }
+
+    #[inline(always)]
+    ///Returns clock, used by timer.
+    ///
+    ///Software timer is based on `Instant`, hence it is always `Clock::Monotonic`.
+    pub fn clock(&self) -> Clock {
+        Clock::Monotonic
+    }
+
+    #[inline]
+    ///Returns current value of the timer's clock.
+    ///
+    ///Value is number of nanoseconds since unspecified `Instant`, that is the same for all timers of the process,
+    ///and can be used with `schedule_once_at_raw`.
+    pub fn clock_now(&self) -> u64 {
+        super::latency_now()
+    }
+
+    #[inline]
+    ///Returns value of the timer's clock after `delta` from now.
+    ///
+    ///See `clock_now` for details.
+    pub fn clock_after(&self, delta: time::Duration) -> u64 {
+        let delta = core::cmp::min(delta.as_nanos(), u64::max_value().into()) as u64;
+        self.clock_now().saturating_add(delta)
+    }
+
+    ///Schedules timer to alarm once, when timer's clock reaches `clock_value`.
+    ///
+    ///`clock_value` must be obtained via `clock_now` or `clock_after` of the same timer, as its meaning depends
+    ///on platform and clock used by the timer.
+    ///If `clock_value` is already in past, timer fires immediately.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_once_at_raw(&self, clock_value: u64) -> bool {
+        let timeout = time::Duration::from_nanos(clock_value.saturating_sub(self.clock_now()));
+        self.schedule_once(timeout)
+    }
+
+    #[inline]
+    ///Returns `true` if timer has been scheduled and still pending.
+    ///
+    ///One shot timer is no longer pending once it fires, while periodic one is pending until cancelled.
+    pub fn is_scheduled(&self) -> bool {
+        match self.context() {
+            Some(ctx) => SCHEDULER.due(ctx.key()).is_some(),
+            None => false,
+        }
+    }
+
+    ///Returns time remaining until next expiration of the timer.
+    ///
+    ///It is exact, as scheduler keeps due time of every timer.
+    ///Returns `None` if timer is not scheduled, one-shot timer has already expired, or due time doesn't fit into `Instant`.
+    pub fn time_remaining(&self) -> Option<time::Duration> {
+        let due = SCHEDULER.due(self.context()?.key())??;
+        Some(due.saturating_duration_since(Instant::now()))
+    }
+
+    #[inline]
+    ///Cancels ongoing timer, if it was scheduled.
+    ///
+    ///Does nothing if timer is not initialized or has never been scheduled.
+    ///Callback, that is already running, is not waited for.
+    pub fn cancel(&self) {
+        if let Some(ctx) = self.context() {
+            ctx.state.cancel();
+            ctx.waiters.notify();
+            SCHEDULER.disarm(ctx.key());
+        }
+    }
+
+    #[inline(always)]
+    ///Returns number of expirations, missed by periodic timer before its most recent run.
+    ///
+    ///Only meaningful on POSIX, while other platforms always return `0`.
+    pub fn overrun_count(&self) -> i32 {
+        0
+    }
+
+    ///Returns interval of the periodic timer, as it has been scheduled.
+    ///
+    ///Returns `None` if timer is not initialized, not scheduled or scheduled to fire once.
+    pub fn scheduled_interval(&self) -> Option<time::Duration> {
+        let ctx = self.context()?;
+        match ctx.state.is_periodic() {
+            true => Some(ctx.armed.interval()),
+            false => None,
+        }
+    }
+
+    ///Returns instant, when timer has been scheduled most recently.
+    ///
+    ///Together with `scheduled_interval` it allows to tell when periodic timer is expected to fire next.
+    ///Returns `None` if timer is not initialized or has never been scheduled.
+    pub fn last_armed_at(&self) -> Option<Instant> {
+        self.context()?.armed.at()
+    }
+
+    ///Pauses scheduled timer, so that `resume` continues its schedule later.
+    ///
+    ///Time until the next alarm is preserved exactly, while time spent paused is not accounted.
+    ///Callback, that is already running, is not waited for.
+    ///
+    ///Does nothing if timer is not initialized or not scheduled.
+    pub fn pause(&self) {
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let slot = match SCHEDULER.disarm(ctx.key()) {
+            Some(slot) => slot,
+            None => return,
+        };
+
+        let remaining = match slot.due {
+            Some(due) => due.saturating_duration_since(Instant::now()),
+            None => time::Duration::MAX,
+        };
+        ctx.armed.pause(remaining);
+        ctx.latency.clear();
+    }
+
+    ///Resumes timer, paused by `pause`, to alarm after time that has been remaining at the moment of pause.
+    ///
+    ///Periodic timer continues with the same `interval` afterwards.
+    ///
+    ///Does nothing if timer is not paused, or it has been cancelled or scheduled again since then.
+    pub fn resume(&self) {
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let remaining = match ctx.armed.take_paused() {
+            Some(remaining) => remaining,
+            None => return,
+        };
+        if ctx.state.get() != TimerState::Armed {
+            return;
+        }
+
+        let interval = ctx.armed.interval();
+        ctx.latency.arm(remaining, interval);
+        SCHEDULER.arm(ctx.key(), remaining, interval);
+    }
+
+    #[inline]
+    ///Returns whether timer's callback is currently running.
+    ///
+    ///Result is a snapshot, that may be already outdated once returned: callback may start right after
+    ///`false` is returned, or finish right after `true` is returned.
+    ///Use drop timer to actually wait for callback to finish.
+    ///
+    ///Uninitialized timer is never running.
+    pub fn is_running(&self) -> bool {
+        match self.context() {
+            Some(ctx) => ctx.running.is_busy(),
+            None => false,
+        }
+    }
+
+    ///Returns instant, when timer is expected to fire next.
+    ///
+    ///Returns `None` if timer is not scheduled (e.g. one shot timer has already fired) or paused.
+    pub fn deadline(&self) -> Option<Instant> {
+        let ctx = self.context()?;
+        match ctx.state.get() {
+            TimerState::Armed => SCHEDULER.due(ctx.key())?,
+            _ => None,
+        }
+    }
+
+    ///Returns number of times timer fired, invoking its callback.
+    ///
+    ///Expirations, skipped or merged according to `CatchUp` policy, are counted as one, while `fire_sync` is not counted.
+    pub fn fire_count(&self) -> u64 {
+        match self.context() {
+            Some(ctx) => ctx.fires.load(Ordering::Acquire),
+            None => 0,
+        }
+    }
+
+    ///Sets policy to handle intervals, missed by periodic timer.
+    ///
+    ///Takes effect starting with the next expiration.
+    pub fn set_catch_up(&self, policy: CatchUp) {
+        if let Some(ctx) = self.context() {
+            ctx.pacing.set_policy(policy);
+        }
+    }
+
+    ///Returns policy to handle intervals, missed by periodic timer.
+    pub fn catch_up(&self) -> CatchUp {
+        match self.context() {
+            Some(ctx) => ctx.pacing.policy(),
+            None => CatchUp::default(),
+        }
+    }
+
+    #[inline(always)]
+    ///Returns features, supported by timer on current platform.
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            can_query_remaining: true,
+            can_select_clock: false,
+            supports_leeway: false,
+            callbacks_serialized: true,
+            min_resolution: time::Duration::from_millis(1),
+            supports_absolute: true,
+        }
+    }
+
+    ///Blocks current thread until timer's next expiration.
+    ///
+    ///Returns `true` once timer fires, or `false` if timer is not scheduled or gets cancelled while waiting.
+    ///Must not be called from within timer's callback, as it would block scheduler.
+    pub fn wait(&self) -> bool {
+        match self.context() {
+            Some(ctx) => ctx.waiters.wait(&ctx.fires, &ctx.state),
+            None => false,
+        }
+    }
+
+    ///Returns how late the most recent expiration was, compared to its scheduled time.
+    ///
+    ///For periodic timer it is relative to the latest point of its schedule, hence it is always less than `interval`.
+    ///Returns `None` if timer has not fired yet.
+    pub fn last_latency(&self) -> Option<time::Duration> {
+        self.context()?.latency.last()
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when the most recent expiration was scheduled to happen.
+    ///
+    ///Together with `last_latency` it is tracked by the crate itself, and for periodic timer it is the latest point of its schedule.
+    ///Returns `None` if timer has not fired yet.
+    pub fn last_deadline(&self) -> Option<std::time::Instant> {
+        self.context()?.latency.due()
+    }
+
+    #[inline]
+    ///Returns timer's lifecycle state.
+    ///
+    ///Uninitialized timer is always `Idle`.
+    pub fn state(&self) -> TimerState {
+        match self.context() {
+            Some(ctx) => ctx.state.get(),
+            None => TimerState::Idle,
+        }
+    }
+
+    ///Replaces timer's callback with `cb`, without re-creating OS timer and keeping its schedule.
+    ///
+    ///Callbacks in flight are finished first, after which previous callback (with its closure, if any) is freed.
+    ///Expirations, that happen meanwhile, wait for the swap and then invoke `cb`.
+    ///Concurrent calls are serialized, hence timer can be shared (e.g. via `Arc`) while its callback is replaced.
+    ///
+    ///Must not be called from within timer's own callback, as it would wait for itself forever, nor concurrently with `fire_sync`.
+    ///
+    ///Returns `false` if timer is not initialized, in which case `cb` is dropped.
+    pub fn set_callback(&self, cb: Callback) -> bool {
+        let ctx = match self.context() {
+            Some(ctx) if self.is_init() => ctx,
+            _ => return false,
+        };
+
+        let (data, mut prev) = cb.into_handler();
+        //Swap keeps out both callbacks and concurrent `set_callback`.
+        ctx.running.begin_swap();
+        unsafe {
+            ctx.swap_handler(&mut prev);
+        }
+        //Previous closure is freed with its owner.
+        let prev_data = self.data.replace(data);
+        ctx.running.end_swap();
+
+        drop(prev);
+        drop(prev_data);
+        true
+    }
+
+    ///Invokes timer's callback synchronously on the calling thread.
+    ///
+    ///Unlike scheduling with zero timeout, callback runs before this function returns.
+    ///Primarily intended as testing aid.
+    ///
+    ///Does nothing if timer is not initialized.
+    ///
+    ///# Safety
+    ///
+    ///- Normally callback is invoked on the scheduler's thread. Callback that relies on that
+    ///(e.g. uses thread locals or expects to never run on the thread that owns the timer) must not be invoked this way.
+    ///- If timer is scheduled, scheduler may invoke callback concurrently with this call. For closures it
+    ///means two mutable references to the same closure, hence user must make sure that timer is not scheduled
+    ///(or that callback is safe to run concurrently) before calling this function.
+    pub unsafe fn fire_sync(&self) {
+        if let Some(ctx) = self.context() {
+            ctx.invoke();
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let ctx = self.ctx.load(Ordering::Relaxed);
+        if !ctx.is_null() {
+            unsafe {
+                (*ctx).running.close();
+                SCHEDULER.disarm((*ctx).key());
+                //Scheduler pins context before releasing its lock, hence once disarmed, only callbacks in flight may use it.
+                (*ctx).running.wait_idle();
+                let _ = Box::from_raw(ctx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[test]
+    fn resolution() {
+        const RESOLUTION: time::Duration = Timer::resolution();
+
+        assert_eq!(RESOLUTION, time::Duration::from_millis(1));
+        assert_eq!(Timer::RESOLUTION, RESOLUTION);
+        assert_eq!(Timer::capabilities().min_resolution, RESOLUTION);
+    }
+
+    #[test]
+    fn next_due_skips_missed() {
+        let due = Instant::now();
+        let interval = time::Duration::from_millis(10);
+
+        assert_eq!(next_due(due, interval, due), (Some(due + interval), 0));
+        assert_eq!(next_due(due, interval, due + time::Duration::from_millis(5)), (Some(due + interval), 0));
+        assert_eq!(next_due(due, interval, due + time::Duration::from_millis(25)), (Some(due + time::Duration::from_millis(30)), 2));
+        assert_eq!(next_due(due, time::Duration::MAX, due).0, None);
+    }
+
+    #[test]
+    fn queue_compacts_stale_entries() {
+        fn cb() {
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        let key = timer.context().expect("To have context").key();
+        for _ in 0..1000 {
+            assert!(timer.schedule_once(time::Duration::from_secs(60)));
+        }
+
+        {
+            let mut queue = SCHEDULER.lock();
+            //Every arm leaves stale entry behind, but they are dropped long before they add up.
+            assert!(queue.heap().len() < 1000);
+            assert_eq!(queue.slots.get(&key).map(|slot| slot.interval), Some(time::Duration::from_secs(0)));
+        }
+
+        drop(timer);
+        assert!(!SCHEDULER.lock().slots.contains_key(&key));
+    }
+
+    #[test]
+    fn exact_time_remaining() {
+        fn cb() {
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert_eq!(timer.time_remaining(), None);
+
+        assert!(timer.schedule_once(time::Duration::from_secs(10)));
+        let remaining = timer.time_remaining().expect("To have remaining time");
+        assert!(remaining <= time::Duration::from_secs(10));
+        assert!(remaining > time::Duration::from_secs(9));
+
+        timer.pause();
+        assert!(!timer.is_scheduled());
+        assert_eq!(timer.time_remaining(), None);
+        timer.resume();
+        let resumed = timer.time_remaining().expect("To have remaining time");
+        assert!(resumed <= remaining);
+        assert!(resumed > time::Duration::from_secs(9));
+
+        //Due time, that doesn't fit into `Instant`, is never reached.
+        assert!(timer.schedule_once(time::Duration::MAX));
+        assert!(timer.is_scheduled());
+        assert_eq!(timer.time_remaining(), None);
+
+        timer.cancel();
+        assert!(!timer.is_scheduled());
+    }
+
+    #[test]
+    fn callbacks_are_serialized() {
+        use core::sync::atomic::{AtomicBool, AtomicUsize};
+        use std::sync::Arc;
+
+        let active = Arc::new(AtomicBool::new(false));
+        let overlaps = Arc::new(AtomicUsize::new(0));
+        let timers = (0..4).map(|_| {
+            let active = active.clone();
+            let overlaps = overlaps.clone();
+            let timer = Timer::new(Callback::closure(move || {
+                if active.swap(true, Ordering::AcqRel) {
+                    overlaps.fetch_add(1, Ordering::AcqRel);
+                }
+                std::thread::sleep(time::Duration::from_millis(1));
+                active.store(false, Ordering::Release);
+            })).expect("To create timer");
+            assert!(timer.schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(2)));
+            timer
+        }).collect::<alloc::vec::Vec<_>>();
+
+        std::thread::sleep(time::Duration::from_millis(100));
+        drop(timers);
+        assert_eq!(overlaps.load(Ordering::Acquire), 0);
+    }
+}
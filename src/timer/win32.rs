@@ -1,7 +1,11 @@
-use core::{time, ptr, mem};
-use core::cell::Cell;
-use core::sync::atomic::{AtomicPtr, Ordering};
-use super::BoxFnPtr;
+use core::{fmt, time, ptr, mem};
+use core::cell::{Cell, UnsafeCell};
+use core::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+use super::{Armed, BoxFnPtr, Capabilities, CatchUp, Clock, Lifecycle, Pacing, Running, TimerBuilder, TimerError, TimerState};
+#[cfg(feature = "alloc")]
+use super::BoxedCallback;
+#[cfg(feature = "std")]
+use super::{Deadline, Latency, Waiters};
 
 extern crate alloc;
 use alloc::boxed::Box;
@@ -10,7 +14,7 @@ mod ffi {
     pub use core::ffi::c_void;
 
     type DWORD = u32;
-    type BOOL = i32;
+    pub type BOOL = i32;
 
     #[repr(C)]
     pub struct FileTime {
@@ -18,6 +22,49 @@ mod ffi {
         pub high_date_time: DWORD,
     }
 
+    pub const TP_CALLBACK_PRIORITY_HIGH: DWORD = 0;
+    pub const TP_CALLBACK_PRIORITY_NORMAL: DWORD = 1;
+    pub const TP_CALLBACK_PRIORITY_LOW: DWORD = 2;
+
+    //TP_CALLBACK_ENVIRON_V3, which is initialized by inline functions in Windows headers.
+    #[repr(C)]
+    pub struct CallbackEnviron {
+        pub version: DWORD,
+        pub pool: *mut c_void,
+        pub cleanup_group: *mut c_void,
+        pub cleanup_group_cancel_callback: *mut c_void,
+        pub race_dll: *mut c_void,
+        pub activation_context: *mut c_void,
+        pub finalization_callback: *mut c_void,
+        pub flags: DWORD,
+        pub callback_priority: DWORD,
+        pub size: DWORD,
+    }
+
+    impl CallbackEnviron {
+        //Equivalent of InitializeThreadpoolEnvironment followed by SetThreadpoolCallbackPriority
+        pub fn with_priority(priority: DWORD) -> Self {
+            Self {
+                version: 3,
+                pool: core::ptr::null_mut(),
+                cleanup_group: core::ptr::null_mut(),
+                cleanup_group_cancel_callback: core::ptr::null_mut(),
+                race_dll: core::ptr::null_mut(),
+                activation_context: core::ptr::null_mut(),
+                finalization_callback: core::ptr::null_mut(),
+                flags: 0,
+                callback_priority: priority,
+                size: core::mem::size_of::<Self>() as DWORD,
+            }
+        }
+
+        #[inline(always)]
+        //Equivalent of SetThreadpoolCallbackPool
+        pub fn set_pool(&mut self, pool: *mut c_void) {
+            self.pool = pool;
+        }
+    }
+
     pub type Callback = unsafe extern "system" fn(cb_inst: *mut c_void, ctx: *mut c_void, timer: *mut c_void);
 
     extern "system" {
@@ -26,6 +73,14 @@ mod ffi {
         pub fn SetThreadpoolTimerEx(timer: *mut c_void, pftDueTime: *mut FileTime, msPeriod: DWORD, msWindowLength: DWORD) -> BOOL;
         pub fn IsThreadpoolTimerSet(timer: *mut c_void) -> BOOL;
         pub fn WaitForThreadpoolTimerCallbacks(timer: *mut c_void, fCancelPendingCallbacks: BOOL);
+        pub fn GetSystemTimePreciseAsFileTime(time: *mut FileTime);
+        pub fn QueryPerformanceCounter(count: *mut i64) -> BOOL;
+        pub fn QueryPerformanceFrequency(frequency: *mut i64) -> BOOL;
+        pub fn GetLastError() -> DWORD;
+        pub fn CreateThreadpool(reserved: *mut c_void) -> *mut c_void;
+        pub fn CloseThreadpool(pool: *mut c_void);
+        pub fn SetThreadpoolThreadMaximum(pool: *mut c_void, max: DWORD);
+        pub fn SetThreadpoolThreadMinimum(pool: *mut c_void, min: DWORD) -> BOOL;
     }
 }
 
@@ -45,6 +100,7 @@ unsafe extern "system" fn timer_callback_unsafe(_: *mut ffi::c_void, data: *mut
     }
 }
 
+#[cfg(feature = "alloc")]
 unsafe extern "system" fn timer_callback_generic<T: FnMut() -> ()>(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
     if !data.is_null() {
         let cb = &mut *(data as *mut T);
@@ -53,9 +109,126 @@ unsafe extern "system" fn timer_callback_generic<T: FnMut() -> ()>(_: *mut ffi::
     }
 }
 
+#[cfg(feature = "alloc")]
+unsafe extern "system" fn timer_callback_boxed(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
+    if !data.is_null() {
+        super::BoxFnPtr::invoke(data as *mut ());
+    }
+}
+
+unsafe extern "system" fn timer_callback_inline<T: FnMut() -> () + Copy>(_: *mut ffi::c_void, data: *mut ffi::c_void, _: *mut ffi::c_void) {
+    let mut cb = super::InlineClosure::<T>::unpack(data as *mut ());
+
+    (cb)();
+}
+
+unsafe extern "system" fn timer_callback_context(cb_inst: *mut ffi::c_void, data: *mut ffi::c_void, timer: *mut ffi::c_void) {
+    if !data.is_null() {
+        let ctx = &*(data as *const Context);
+
+        ctx.fire(cb_inst, timer);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Priority of timer's callback relative to other work items of the thread pool.
+///
+///Windows only: other platforms have no per-timer callback priority (Mac's closest analogue is `QosClass`).
+pub enum Priority {
+    ///Callback is preferred over other work items.
+    High,
+    ///Default priority, used by `Timer::new`.
+    Normal,
+    ///Callback yields to other work items.
+    Low,
+}
+
+impl Priority {
+    #[inline(always)]
+    fn raw(self) -> u32 {
+        match self {
+            Priority::High => ffi::TP_CALLBACK_PRIORITY_HIGH,
+            Priority::Normal => ffi::TP_CALLBACK_PRIORITY_NORMAL,
+            Priority::Low => ffi::TP_CALLBACK_PRIORITY_LOW,
+        }
+    }
+}
+
+///Private thread pool, that runs callbacks of timers created within it.
+///
+///By default timers share process-wide thread pool, which can be starved by other work items;
+///private pool isolates timers from them and bounds number of threads, used by their callbacks.
+///
+///Pool can be dropped while its timers are alive: OS releases it once the last of them is closed.
+pub struct ThreadPool {
+    handle: *mut ffi::c_void,
+}
+
+unsafe impl Send for ThreadPool {}
+unsafe impl Sync for ThreadPool {}
+
+impl ThreadPool {
+    ///Creates new thread pool, running between `min_threads` and `max_threads` threads.
+    ///
+    ///Returns `None` if `min_threads` is greater than `max_threads`, `max_threads` is zero or OS fails to create pool.
+    pub fn new(min_threads: u32, max_threads: u32) -> Option<Self> {
+        if max_threads == 0 || min_threads > max_threads {
+            return None;
+        }
+
+        let handle = unsafe {
+            ffi::CreateThreadpool(ptr::null_mut())
+        };
+        if handle.is_null() {
+            return None;
+        }
+
+        //Pool takes ownership of handle, hence closes it on failure.
+        let pool = Self {
+            handle,
+        };
+        unsafe {
+            ffi::SetThreadpoolThreadMaximum(handle, max_threads);
+            match ffi::SetThreadpoolThreadMinimum(handle, min_threads) {
+                0 => None,
+                _ => Some(pool),
+            }
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::CloseThreadpool(self.handle);
+        }
+    }
+}
+
+impl fmt::Debug for ThreadPool {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("ThreadPool").field(&self.handle).finish()
+    }
+}
+
+#[cfg(feature = "alloc")]
+type CountedCallback = Box<dyn FnMut(&Timer, u64) + Send>;
+#[cfg(not(feature = "alloc"))]
+//Placeholder, as such callback cannot be created without heap, hence it is never set.
+type CountedCallback = fn(&Timer, u64);
+
+///Raw handle of OS timer: `PTP_TIMER`.
+pub type RawTimer = *mut ffi::c_void;
+
 enum CallbackVariant {
     Trivial(*mut ffi::c_void),
-    Boxed(Box<dyn FnMut()>),
+    #[cfg(feature = "alloc")]
+    Boxed(BoxedCallback),
+    //Closure, boxed by user, which is owned by context and invoked through fat pointer.
+    #[cfg(feature = "alloc")]
+    Dyn(BoxedCallback),
+    #[cfg(feature = "alloc")]
+    Counted(CountedCallback),
 }
 
 ///Timer's callback abstraction
@@ -68,7 +241,7 @@ impl Callback {
     ///Creates raw callback for platform timer.
     ///
     ///Signature depends on platform.
-    pub unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
+    pub const unsafe fn raw(ffi_cb: ffi::Callback, data: *mut ffi::c_void) -> Self {
         Self {
             variant: CallbackVariant::Trivial(data),
             ffi_cb,
@@ -76,7 +249,7 @@ impl Callback {
     }
 
     ///Creates callback using plain rust function
-    pub fn plain(cb: fn()) -> Self {
+    pub const fn plain(cb: fn()) -> Self {
         Self {
             variant: CallbackVariant::Trivial(cb as _),
             ffi_cb: timer_callback,
@@ -84,26 +257,442 @@ impl Callback {
     }
 
     ///Creates callback using plain unsafe function
-    pub fn unsafe_plain(cb: unsafe fn()) -> Self {
+    pub const fn unsafe_plain(cb: unsafe fn()) -> Self {
         Self {
             variant: CallbackVariant::Trivial(cb as _),
             ffi_cb: timer_callback_unsafe,
         }
     }
 
+    #[cfg(feature = "alloc")]
     ///Creates callback using closure, storing it on heap.
-    pub fn closure<F: 'static + FnMut()>(cb: F) -> Self {
+    pub fn closure<F: 'static + FnMut() + Send>(cb: F) -> Self {
         Self {
             variant: CallbackVariant::Boxed(Box::new(cb)),
             ffi_cb: timer_callback_generic::<F>,
         }
     }
+
+    ///Creates callback using small closure, stored in place of function's data instead of heap.
+    ///
+    ///Closure must fit into pointer (i.e. capture nothing or single reference), which is checked at compile time.
+    ///Being `Copy`, closure is copied for each run, hence changes to its captured state are not preserved between runs.
+    pub fn inline<F: 'static + FnMut() + Copy + Send>(cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Trivial(super::InlineClosure::pack(cb) as *mut ffi::c_void),
+            ffi_cb: timer_callback_inline::<F>,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    ///Creates callback using already boxed closure.
+    ///
+    ///Closure is stored as it is, without boxing it again.
+    pub fn boxed(cb: BoxedCallback) -> Self {
+        Self {
+            variant: CallbackVariant::Dyn(cb),
+            ffi_cb: timer_callback_boxed,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives number of intervals elapsed since its previous run.
+    ///
+    ///On Windows count is estimated from time passed since previous run, as thread pool doesn't report
+    ///missed expirations, but it is never less than number of expirations, skipped while callback was still running.
+    ///It is always 1 for one shot timer.
+    ///
+    ///Callback is run once per expiration, regardless of `CatchUp` policy.
+    pub fn with_elapsed<F: 'static + FnMut(u32) + Send>(mut cb: F) -> Self {
+        Self::with_count(move |count| cb(core::cmp::min(count, u32::max_value().into()) as u32))
+    }
+
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives number of expirations since its previous run.
+    ///
+    ///Same as `with_elapsed`, but without saturating count to `u32`.
+    pub fn with_count<F: 'static + FnMut(u64) + Send>(mut cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Counted(Box::new(move |_: &Timer, count| cb(count))),
+            ffi_cb: timer_callback,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that receives reference to the timer, which invokes it.
+    ///
+    ///It allows callback to re-schedule (or cancel) its own timer, e.g. to implement backoff.
+    ///
+    ///To cancel timer, callback must use `Timer::cancel_async`, as `Timer::cancel` waits for running
+    ///callbacks, including the one it is called from, and never returns.
+    ///
+    ///Callback receives a view of the timer, sharing its OS timer and context, rather than timer itself,
+    ///as `Timer` may be moved after creation. It is sound, because timer outlives its callbacks: on drop it
+    ///stops invoking callbacks, waits for ones in flight, and only then cancels and deletes OS timer.
+    ///
+    ///Callback is run once per expiration, regardless of `CatchUp` policy.
+    pub fn with_timer<F: 'static + FnMut(&Timer) + Send>(mut cb: F) -> Self {
+        Self {
+            variant: CallbackVariant::Counted(Box::new(move |timer: &Timer, _| cb(timer))),
+            ffi_cb: timer_callback,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    ///Creates callback using closure, that keeps periodic timer running as long as it returns `true`.
+    ///
+    ///Once closure returns `false`, timer cancels itself right after that invocation, until it is scheduled again.
+    ///
+    ///As thread pool cannot wait for callback from within itself, timer is stopped via `Timer::cancel_async`.
+    pub fn repeating<F: 'static + FnMut() -> bool + Send>(mut cb: F) -> Self {
+        Self::with_timer(move |timer| {
+            if !cb() {
+                timer.cancel_async();
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Callback {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //Closure is opaque and function pointer is meaningless to user, hence only kind of callback is printed.
+        let variant = match self.variant {
+            CallbackVariant::Trivial(_) => "Trivial",
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Boxed(_) | CallbackVariant::Dyn(_) => "Boxed",
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Counted(_) => "Counted",
+        };
+        fmt.debug_struct("Callback").field("variant", &format_args!("{}", variant)).finish()
+    }
+}
+
+impl Callback {
+    fn into_handler(self) -> (BoxFnPtr, Handler) {
+        let mut handler = Handler {
+            ffi_cb: self.ffi_cb,
+            ffi_data: ptr::null_mut(),
+            boxed: BoxFnPtr::new(),
+            counted: None,
+        };
+        let data = match self.variant {
+            CallbackVariant::Trivial(data) => {
+                handler.ffi_data = data;
+                BoxFnPtr::new()
+            },
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Boxed(cb) => {
+                let data = BoxFnPtr::from_box(cb);
+                handler.ffi_data = data.0 as *mut ffi::c_void;
+                data
+            },
+            //Data is pointed to `boxed`, once handler is at its place (see `Handler::bind_boxed`).
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Dyn(cb) => {
+                handler.boxed = BoxFnPtr::from_box(cb);
+                BoxFnPtr::new()
+            },
+            #[cfg(feature = "alloc")]
+            CallbackVariant::Counted(cb) => {
+                super::closure_stored();
+                handler.counted = Some(cb);
+                BoxFnPtr::new()
+            },
+        };
+
+        (data, handler)
+    }
+
+    fn into_context(self) -> (BoxFnPtr, *mut Context) {
+        let (data, handler) = self.into_handler();
+
+        let ctx = Box::into_raw(Box::new(Context {
+            handler: UnsafeCell::new(handler),
+            priority: None,
+            leeway: None,
+            state: Lifecycle::new(),
+            armed: Armed::new(),
+            fires: AtomicU64::new(0),
+            running: Running::new(),
+            pacing: Pacing::new(),
+            elapsed: Elapsed::new(),
+            #[cfg(feature = "std")]
+            deadline: Deadline::new(),
+            #[cfg(feature = "std")]
+            latency: Latency::new(),
+            #[cfg(feature = "std")]
+            waiters: Waiters::new(),
+        }));
+        unsafe {
+            Handler::bind_boxed((*ctx).handler.get());
+        }
+        (data, ctx)
+    }
+}
+
+//Callback, that context invokes on expiration.
+struct Handler {
+    ffi_cb: ffi::Callback,
+    ffi_data: *mut ffi::c_void,
+    //Closure of `Callback::boxed`, which `ffi_data` points to.
+    boxed: BoxFnPtr,
+    //Callback, that receives number of expirations, used instead of `ffi_cb`.
+    counted: Option<CountedCallback>,
+}
+
+impl Handler {
+    //Points `ffi_data` to `boxed`, if callback is created by `Callback::boxed`.
+    //
+    //As handler refers to itself then, it must be called once handler is at its final place.
+    unsafe fn bind_boxed(this: *mut Self) {
+        if !(*this).boxed.is_null() {
+            (*this).ffi_data = ptr::addr_of_mut!((*this).boxed) as *mut ffi::c_void;
+        }
+    }
+}
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        if self.counted.take().is_some() {
+            super::closure_freed();
+        }
+    }
+}
+
+//Timer's user data, through which OS invokes actual callback.
+struct Context {
+    //Callbacks access it only once they enter `running` and wait out swap, hence `set_callback` replaces it
+    //between `begin_swap` and `end_swap` of `running`, even though context is shared.
+    handler: UnsafeCell<Handler>,
+    //Explicitly requested callback priority, if any.
+    priority: Option<Priority>,
+    //Leeway, used when schedule doesn't specify it.
+    leeway: Option<time::Duration>,
+    state: Lifecycle,
+    armed: Armed,
+    //Number of expirations, that reached user's callback.
+    fires: AtomicU64,
+    running: Running,
+    pacing: Pacing,
+    elapsed: Elapsed,
+    #[cfg(feature = "std")]
+    deadline: Deadline,
+    #[cfg(feature = "std")]
+    latency: Latency,
+    #[cfg(feature = "std")]
+    waiters: Waiters,
+}
+
+impl Context {
+    //Creates view of the timer, that owns context, so that it can be passed to callback.
+    //
+    //View doesn't own closure, and it must not outlive context, hence it is never dropped.
+    unsafe fn timer_view(&self, timer: *mut ffi::c_void) -> mem::ManuallyDrop<Timer> {
+        mem::ManuallyDrop::new(Timer {
+            inner: AtomicPtr::new(timer),
+            data: Cell::new(BoxFnPtr::new()),
+            ctx: AtomicPtr::new(self as *const Self as *mut Self),
+        })
+    }
+
+    //Exchanges callback with `other`.
+    //
+    //Caller must make sure that callbacks do not access it meanwhile (see `Timer::set_callback`).
+    unsafe fn swap_handler(&self, other: &mut Handler) {
+        let handler = self.handler.get();
+        ptr::swap(handler, other);
+        Handler::bind_boxed(handler);
+        Handler::bind_boxed(other);
+    }
+
+    #[inline(always)]
+    unsafe fn invoke(&self, cb_inst: *mut ffi::c_void, timer: *mut ffi::c_void) {
+        self.invoke_count(cb_inst, timer, 1)
+    }
+
+    #[inline(always)]
+    unsafe fn invoke_count(&self, cb_inst: *mut ffi::c_void, timer: *mut ffi::c_void, count: u64) {
+        let handler = &mut *self.handler.get();
+        match &mut handler.counted {
+            Some(cb) => cb(&self.timer_view(timer), count),
+            None => (handler.ffi_cb)(cb_inst, handler.ffi_data, timer),
+        }
+    }
+
+    unsafe fn fire(&self, cb_inst: *mut ffi::c_void, timer: *mut ffi::c_void) {
+        let running = self.running.enter();
+        if running.is_closed() {
+            return;
+        }
+        //Callback is not accessed until `set_callback` replaces it.
+        running.wait_swap();
+        //Expiration, that was queued before `cancel_async`, is discarded.
+        //Once timer is scheduled again, state is no longer cancelled.
+        if self.state.get() == TimerState::Cancelled {
+            return;
+        }
+        #[cfg(feature = "std")]
+        self.latency.fire();
+
+        #[cfg(feature = "std")]
+        {
+            if self.deadline.is_expired() {
+                self.state.cancel();
+                self.waiters.notify();
+                //Cannot wait for callbacks from within callback itself
+                ffi::SetThreadpoolTimerEx(timer, ptr::null_mut(), 0, 0);
+                return;
+            }
+        }
+
+        let is_periodic = self.state.is_periodic();
+        //Thread pool may run multiple expirations at once, so skip it, if previous one is not done yet.
+        if is_periodic && running.is_concurrent() {
+            self.pacing.miss(1);
+            return;
+        }
+
+        let is_last = match self.armed.take_alarm() {
+            Some(is_last) => is_last,
+            //Alarm, that raced with the last one of `schedule_count`.
+            None => return,
+        };
+        if is_last {
+            self.state.cancel();
+            //Cannot wait for callbacks from within callback itself
+            ffi::SetThreadpoolTimerEx(timer, ptr::null_mut(), 0, 0);
+        }
+        self.state.fire();
+        self.fires.fetch_add(1, Ordering::AcqRel);
+        #[cfg(feature = "std")]
+        self.waiters.notify();
+        if (*self.handler.get()).counted.is_some() {
+            let count = match is_periodic {
+                true => core::cmp::max(self.elapsed.fire(), u64::from(self.pacing.take_missed()) + 1),
+                false => 1,
+            };
+            self.invoke_count(cb_inst, timer, count);
+        } else {
+            let runs = match is_periodic {
+                true => self.pacing.runs(),
+                false => 1,
+            };
+            for _ in 0..runs {
+                self.invoke(cb_inst, timer);
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn performance_counter() -> u64 {
+    let mut count = 0;
+    unsafe {
+        ffi::QueryPerformanceCounter(&mut count);
+    }
+    count as u64
+}
+
+//Estimates number of intervals, elapsed between expirations of periodic timer, as thread pool doesn't report it.
+struct Elapsed {
+    //Interval in performance counter ticks, 0 if timer is not periodic.
+    interval: AtomicU64,
+    //Performance counter at previous expiration, 0 if there was none since timer was armed.
+    last: AtomicU64,
+}
+
+impl Elapsed {
+    #[inline(always)]
+    const fn new() -> Self {
+        Self {
+            interval: AtomicU64::new(0),
+            last: AtomicU64::new(0),
+        }
+    }
+
+    fn arm(&self, interval: time::Duration) {
+        let mut frequency = 0;
+        unsafe {
+            ffi::QueryPerformanceFrequency(&mut frequency);
+        }
+        let ticks = interval.as_nanos().saturating_mul(frequency as u128) / 1_000_000_000;
+        self.interval.store(core::cmp::min(ticks, u64::max_value().into()) as u64, Ordering::Release);
+        self.last.store(0, Ordering::Release);
+    }
+
+    //Returns number of intervals since previous expiration, rounded to the nearest, but at least 1.
+    fn fire(&self) -> u64 {
+        let now = performance_counter();
+        let last = self.last.swap(now, Ordering::AcqRel);
+        let interval = self.interval.load(Ordering::Acquire);
+        if last == 0 || interval == 0 {
+            return 1;
+        }
+
+        let passed = now.saturating_sub(last);
+        core::cmp::max(passed.saturating_add(interval / 2) / interval, 1)
+    }
+}
+
+//Converts timeout into relative due time, which is negative number of 100 nanoseconds intervals.
+//
+//Timeout is clamped, as positive value would be interpreted as absolute time.
+fn relative_due_time(timeout: time::Duration) -> ffi::FileTime {
+    let ticks = core::cmp::min(timeout.as_nanos() / 100, i64::max_value() as u128) as i64;
+    let ticks = -ticks as u64;
+
+    ffi::FileTime {
+        low_date_time: ticks as u32,
+        high_date_time: (ticks >> 32) as u32,
+    }
+}
+
+//Converts interval into period of thread pool timer, which is in milliseconds.
+//
+//Sub-millisecond interval is rounded up, as zero period would make timer one-shot, while too long one is saturated.
+fn period_millis(interval: time::Duration) -> u32 {
+    match interval.as_millis() {
+        0 if !interval.is_zero() => 1,
+        millis => core::cmp::min(millis, u32::max_value().into()) as u32,
+    }
+}
+
+//Converts leeway into tolerance window of thread pool timer, which is in milliseconds.
+//
+//Too long leeway is saturated, instead of wrapping around.
+fn window_millis(leeway: time::Duration) -> u32 {
+    core::cmp::min(leeway.as_millis(), u32::max_value().into()) as u32
+}
+
+//Sets thread pool timer, accounting `window` as leeway, forwarded to OS.
+unsafe fn set_threadpool_timer(handle: *mut ffi::c_void, time: &mut ffi::FileTime, period: u32, window: u32) -> ffi::BOOL {
+    super::leeway_forwarded(time::Duration::from_millis(window.into()));
+    ffi::SetThreadpoolTimerEx(handle, time, period, window)
+}
+
+//Returns `TimerError::Overflow` if durations don't fit into thread pool timer.
+fn check_range(timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+    match timeout.as_nanos() / 100 <= i64::max_value() as u128 && interval.as_millis() <= u32::max_value().into() {
+        true => Ok(()),
+        false => Err(TimerError::Overflow),
+    }
 }
 
 ///Windows thread pool timer
+///
+///# Drop
+///
+///Dropping timer waits for its callbacks, that are already running, to finish, hence timer must not be dropped
+///from within its own callback (e.g. by taking it out of shared `Mutex<Option<Timer>>`), as it would deadlock,
+///similarly to `Timer::cancel`.
 pub struct Timer {
     inner: AtomicPtr<ffi::c_void>,
+    //Owns closure, if any.
+    //Set only once by thread, that initialized timer, and otherwise accessed only on drop,
+    //hence it is safe to share timer between threads.
     data: Cell<BoxFnPtr>,
+    ctx: AtomicPtr<Context>,
 }
 
 impl Timer {
@@ -111,10 +700,22 @@ impl Timer {
     ///Creates new uninitialized instance.
     ///
     ///In order to use it one must call `init`.
+    ///
+    ///Initialized timer cannot be reset in place, as other threads may use it concurrently, hence to re-use it
+    ///(e.g. in object pool) assign `uninit` to it: old timer is dropped, which cancels it, waits for its
+    ///callbacks in flight and frees OS timer together with callback, after which `init` succeeds again.
     pub const unsafe fn uninit() -> Self {
         Self {
             inner: AtomicPtr::new(ptr::null_mut()),
             data: Cell::new(BoxFnPtr::new()),
+            ctx: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    #[inline(always)]
+    fn context(&self) -> Option<&Context> {
+        unsafe {
+            self.ctx.load(Ordering::Acquire).as_ref()
         }
     }
 
@@ -125,6 +726,16 @@ impl Timer {
         inner
     }
 
+    #[inline(always)]
+    ///Returns raw handle of OS timer, in order to use platform API, that is not wrapped by the crate.
+    ///
+    ///Handle is owned by timer, hence caller must neither release it nor use it after timer is dropped.
+    ///
+    ///Returns null if timer is not initialized.
+    pub fn as_raw(&self) -> RawTimer {
+        self.inner.load(Ordering::Acquire)
+    }
+
     #[inline(always)]
     ///Returns whether timer is initialized
     pub fn is_init(&self) -> bool {
@@ -139,89 +750,454 @@ impl Timer {
     ///Returns whether timer has been initialized successfully or not.
     ///
     ///If timer is already initialized does nothing, returning false.
-    pub fn init(&self, cb: Callback) -> bool {
+    ///
+    ///Initialization is atomic: when multiple threads race to initialize the same timer, exactly one
+    ///succeeds, while the rest release OS timer and callback they created, returning false.
+    pub fn init(&self, cb: impl Into<Callback>) -> bool {
+        self.try_init(cb).is_ok()
+    }
+
+    ///Performs timer initialization
+    ///
+    ///Same as `init`, but returns reason of failure:
+    ///
+    ///- `TimerError::AlreadyInitialized` if timer is already initialized, in which case it does nothing.
+    ///- `TimerError::Os` with `GetLastError` if OS fails to create timer.
+    pub fn try_init(&self, cb: impl Into<Callback>) -> Result<(), TimerError> {
         if self.is_init() {
-            return false;
+            return Err(TimerError::AlreadyInitialized);
         }
 
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (BoxFnPtr(0), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (BoxFnPtr(mem::transmute(raw)), raw as *mut ffi::c_void)
-            },
-        };
+        let cb: Callback = cb.into();
+        let (data, ctx) = cb.into_context();
 
         let handle = unsafe {
-            ffi::CreateThreadpoolTimer(ffi_cb, ffi_data, ptr::null_mut())
+            ffi::CreateThreadpoolTimer(timer_callback_context, ctx as *mut ffi::c_void, ptr::null_mut())
         };
 
-        match self.inner.compare_exchange(ptr::null_mut(), handle, Ordering::SeqCst, Ordering::Acquire) {
-            Ok(_) => match handle.is_null() {
-                true => false,
-                false => {
-                    //safe because we can never reach here once `handle.is_null() != true`
-                    self.data.set(data);
-                    true
-                },
+        if handle.is_null() {
+            unsafe {
+                let error = ffi::GetLastError();
+                let _ = Box::from_raw(ctx);
+                return Err(TimerError::Os(error as i32));
+            }
+        }
+
+        //Context is claimed first, so that timer is never observed initialized without it.
+        match self.ctx.compare_exchange(ptr::null_mut(), ctx, Ordering::SeqCst, Ordering::Acquire) {
+            Ok(_) => {
+                //safe because only the thread, that claimed context, can reach here.
+                self.data.set(data);
+                self.inner.store(handle, Ordering::Release);
+                Ok(())
             },
             Err(_) => {
                 unsafe {
                     ffi::CloseThreadpoolTimer(handle);
+                    let _ = Box::from_raw(ctx);
                 }
-                false
+                //Closure of the losing thread is never reachable from timer.
+                drop(data);
+                Err(TimerError::AlreadyInitialized)
             }
         }
     }
 
+    #[inline(always)]
     ///Creates new timer, invoking provided `cb` when timer expires.
     ///
+    ///`cb` can be plain function or closure, as well as `Callback`.
+    ///
     ///On failure, returns `None`
-    pub fn new(cb: Callback) -> Option<Self> {
-        let ffi_cb = cb.ffi_cb;
-        let (data, ffi_data) = match cb.variant {
-            CallbackVariant::Trivial(data) => (BoxFnPtr(0), data),
-            CallbackVariant::Boxed(cb) => unsafe {
-                let raw = Box::into_raw(cb);
-                (BoxFnPtr(mem::transmute(raw)), raw as *mut ffi::c_void)
-            },
-        };
+    pub fn new(cb: impl Into<Callback>) -> Option<Self> {
+        Self::new_with_retry(cb.into(), 1)
+    }
 
-        let handle = unsafe {
-            ffi::CreateThreadpoolTimer(ffi_cb, ffi_data, ptr::null_mut())
-        };
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///Same as `new`, but on failure returns `TimerError::Os` with `GetLastError`.
+    pub fn try_new(cb: impl Into<Callback>) -> Result<Self, TimerError> {
+        Self::create(cb.into(), 1, None, None)
+    }
 
-        if handle.is_null() {
-            return None;
-        }
+    ///Creates new timer, invoking provided `cb` when timer expires.
+    ///
+    ///If OS fails to create timer (e.g. due to memory pressure), retries it up to `attempts` times in total,
+    ///pausing between attempts (starting with 1ms and doubling each time, up to 64ms), hence it may block briefly.
+    ///Without `std` feature there is no way to sleep, hence attempts are made back to back.
+    ///
+    ///On failure, returns `None`
+    pub fn new_with_retry(cb: impl Into<Callback>, attempts: u32) -> Option<Self> {
+        Self::create(cb.into(), attempts, None, None).ok()
+    }
 
-        Some(Self {
-            inner: AtomicPtr::new(handle),
-            data: Cell::new(data),
-        })
+    ///Creates new timer, invoking provided `cb` with specified `priority` within thread pool.
+    ///
+    ///Callback environment lives only during creation, hence timer owns no extra resources.
+    ///
+    ///On failure, returns `None`
+    pub fn new_with_priority(cb: impl Into<Callback>, priority: Priority) -> Option<Self> {
+        Self::create(cb.into(), 1, Some(priority), None).ok()
     }
 
-    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///Creates new timer, invoking provided `cb` on threads of private `pool`.
     ///
-    ///Note that if timer has been scheduled before, but hasn't expire yet, behaviour is undefined (Callback may or may not be called).
-    ///To prevent that user must `cancel` timer first.
+    ///On failure, returns `None`
+    pub fn new_in_pool(cb: impl Into<Callback>, pool: &ThreadPool) -> Option<Self> {
+        Self::create(cb.into(), 1, None, Some(pool)).ok()
+    }
+
+    ///Creates timer, that takes ownership of thread pool timer `handle`, created elsewhere.
     ///
-    ///# Note
+    ///OS keeps invoking callback, that `handle` has been created with, hence timer doesn't observe expirations:
+    ///`fire_count`, `state`, `wait` and other bookkeeping do not reflect them.
     ///
-    ///- `interval` is truncated by `u32::max_value()`
+    ///# Safety
     ///
-    ///Returns `true` if successfully set, otherwise on error returns `false`
-    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
-        let mut ticks = i64::from(timeout.subsec_nanos() / 100);
-        ticks += (timeout.as_secs() * 10_000_000) as i64;
-        let ticks = -ticks;
+    ///`handle` must be valid `PTP_TIMER`, that is not scheduled and not owned by anything else,
+    ///as timer closes it on drop.
+    pub unsafe fn from_raw(handle: RawTimer) -> Self {
+        let cb = Callback::raw(timer_callback, ptr::null_mut());
+        let (data, ctx) = cb.into_context();
 
-        let interval = interval.as_millis() as u32;
+        Self {
+            inner: AtomicPtr::new(handle),
+            data: Cell::new(data),
+            ctx: AtomicPtr::new(ctx),
+        }
+    }
 
+    pub(super) fn from_builder(builder: TimerBuilder) -> Result<Self, TimerError> {
+        if builder.clock.map_or(false, Clock::is_cpu_time) {
+            return Err(TimerError::Unsupported);
+        }
+        let timer = Self::create(builder.callback.ok_or(TimerError::Uninitialized)?, 1, None, None)?;
         unsafe {
-            let mut time: ffi::FileTime = mem::transmute(ticks);
-            ffi::SetThreadpoolTimerEx(self.get_inner(), &mut time, interval, 0);
+            //Timer is not armed yet, hence context is not shared.
+            (*timer.ctx.load(Ordering::Relaxed)).leeway = builder.leeway;
+        }
+        Ok(timer)
+    }
+
+    fn create(cb: Callback, attempts: u32, priority: Option<Priority>, pool: Option<&ThreadPool>) -> Result<Self, TimerError> {
+        let (data, ctx) = cb.into_context();
+        //Environment is only read during creation, hence it can live on stack.
+        let mut env = match (priority, pool) {
+            (None, None) => None,
+            (priority, pool) => {
+                let mut env = ffi::CallbackEnviron::with_priority(priority.unwrap_or(Priority::Normal).raw());
+                if let Some(pool) = pool {
+                    env.set_pool(pool.handle);
+                }
+                Some(env)
+            }
+        };
+        let env = match env.as_mut() {
+            Some(env) => env as *mut ffi::CallbackEnviron as *mut ffi::c_void,
+            None => ptr::null_mut(),
+        };
+        unsafe {
+            //Context is not shared with OS yet.
+            (*ctx).priority = priority;
+        }
+
+        let mut error = 0;
+        let handle = super::retry(attempts, || unsafe {
+            let handle = ffi::CreateThreadpoolTimer(timer_callback_context, ctx as *mut ffi::c_void, env);
+            match handle.is_null() {
+                true => {
+                    error = ffi::GetLastError();
+                    Err(true)
+                },
+                false => Ok(handle),
+            }
+        });
+
+        let handle = match handle {
+            Some(handle) => handle,
+            None => {
+                unsafe {
+                    let _ = Box::from_raw(ctx);
+                }
+                return Err(TimerError::Os(error as i32));
+            }
+        };
+
+        Ok(Self {
+            inner: AtomicPtr::new(handle),
+            data: Cell::new(data),
+            ctx: AtomicPtr::new(ctx),
+        })
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, behaviour is undefined (Callback may or may not be called).
+    ///To prevent that user must `cancel` timer first.
+    ///
+    ///# Note
+    ///
+    ///- Thread pool timer's period is in milliseconds, hence `interval` is truncated to milliseconds,
+    ///while non-zero `interval` below 1ms is rounded up to 1ms
+    ///- `interval` is truncated by `u32::max_value()` milliseconds
+    ///- `SetThreadpoolTimerEx` cannot fail, its result only tells whether timer was already set,
+    ///hence this returns `false` only if timer is not initialized. Use `schedule_interval_was_set` to get previous state.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        self.arm(timeout, interval)
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`,
+    ///returning whether timer had been already set, i.e. whether previous schedule got replaced.
+    ///
+    ///Thread pool timer remains set after one-shot alarm fires, until it is re-armed or cancelled.
+    ///
+    ///Returns `None` if timer is not initialized.
+    pub fn schedule_interval_was_set(&self, timeout: time::Duration, interval: time::Duration) -> Option<bool> {
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        self.set_timer(timeout, interval, self.default_leeway(timeout, interval))
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`.
+    ///
+    ///Same as `schedule_interval`, but returns error if timer is not initialized.
+    ///Instead of truncating, returns `TimerError::Overflow` if `interval` doesn't fit into `u32` milliseconds (about 49 days).
+    pub fn try_schedule_interval(&self, timeout: time::Duration, interval: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+        check_range(timeout, interval)?;
+
+        self.schedule_interval(timeout, interval);
+        Ok(())
+    }
+
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`, allowing OS
+    ///to delay each alarm by up to `leeway` in order to coalesce it with other timers.
+    ///
+    ///For periodic timer `leeway` is clamped to the `interval`, as otherwise timer would fire sporadically.
+    ///Overrides global coalescing setting.
+    ///
+    ///Window length is in milliseconds, hence `leeway` is truncated to milliseconds.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_with_leeway(&self, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        self.arm_with_leeway(timeout, interval, super::clamp_leeway(leeway, interval))
+    }
+
+    #[inline(always)]
+    ///Schedules timer to alarm periodically with `interval` with initial alarm of `timeout`, passing
+    ///`window` as `msWindowLength` to `SetThreadpoolTimerEx`.
+    ///
+    ///Same as `schedule_interval_with_leeway`, named after Windows API.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_with_window(&self, timeout: time::Duration, interval: time::Duration, window: time::Duration) -> bool {
+        self.schedule_interval_with_leeway(timeout, interval, window)
+    }
+
+    ///Schedules timer to alarm periodically with `interval` exactly `count` times, after which it cancels itself.
+    ///
+    ///First alarm happens after `interval`.
+    ///Timer is cancelled from within its own callback, right before invoking user's callback for the last time,
+    ///hence callback may re-schedule timer. Alarms are counted as by `fire_count`.
+    ///
+    ///`count` of 0 does nothing, returning `true`, while `count` of 1 is equivalent to `schedule_once(interval)`.
+    ///Subsequent `schedule_*` call removes the limit.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_count(&self, interval: time::Duration, count: u32) -> bool {
+        match count {
+            0 => true,
+            1 => self.schedule_once(interval),
+            count => {
+                if let Some(ctx) = self.context() {
+                    ctx.armed.limit(count);
+                }
+                self.schedule_interval(interval, interval)
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Schedules timer to alarm periodically with `interval` until `deadline` is reached.
+    ///
+    ///First alarm happens after `interval`.
+    ///Once expired after `deadline`, timer cancels itself from within its own callback, without invoking user's callback.
+    ///As cancellation happens from the timer's own callback, it doesn't wait for callbacks in flight (which
+    ///would dead lock).
+    ///
+    ///Subsequent `schedule_*` call removes `deadline`.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`
+    pub fn schedule_interval_until(&self, interval: time::Duration, deadline: std::time::Instant) -> bool {
+        if let Some(ctx) = self.context() {
+            ctx.deadline.set(Some(deadline));
+        }
+
+        self.arm(interval, interval)
+    }
+
+    ///Schedules timer to alarm once after `timeout` passes.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
+    ///
+    ///Returns error if timer is not initialized, or `TimerError::Overflow` if `timeout` doesn't fit into due time.
+    pub fn try_schedule_once(&self, timeout: time::Duration) -> Result<(), TimerError> {
+        if !self.is_init() {
+            return Err(TimerError::Uninitialized);
+        }
+        check_range(timeout, time::Duration::from_secs(0))?;
+
+        self.schedule_interval(timeout, time::Duration::from_secs(0));
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn arm(&self, timeout: time::Duration, interval: time::Duration) -> bool {
+        self.arm_with_leeway(timeout, interval, self.default_leeway(timeout, interval))
+    }
+
+    #[inline(always)]
+    //Returns leeway to use when user did not specify it explicitly.
+    fn default_leeway(&self, timeout: time::Duration, interval: time::Duration) -> time::Duration {
+        match self.context().and_then(|ctx| ctx.leeway) {
+            Some(leeway) => super::clamp_leeway(leeway, interval),
+            None => super::coalescing_leeway(timeout, interval),
+        }
+    }
+
+    fn arm_with_leeway(&self, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> bool {
+        self.set_timer(timeout, interval, leeway).is_some()
+    }
+
+    //Returns whether timer was already set, as reported by `SetThreadpoolTimerEx`, or `None` if timer is not initialized.
+    fn set_timer(&self, timeout: time::Duration, interval: time::Duration, leeway: time::Duration) -> Option<bool> {
+        //OS handle is null until timer is initialized.
+        if !self.is_init() {
+            return None;
+        }
+
+        let mut time = relative_due_time(timeout);
+
+        let window = window_millis(leeway);
+        let interval = period_millis(interval);
+
+        if let Some(ctx) = self.context() {
+            ctx.pacing.reset();
+            #[cfg(feature = "std")]
+            ctx.latency.arm(timeout, time::Duration::from_millis(interval.into()));
+            ctx.elapsed.arm(time::Duration::from_millis(interval.into()));
+            ctx.armed.arm(time::Duration::from_millis(interval.into()));
+            ctx.state.arm(time::Duration::from_millis(interval.into()));
+        }
+
+        let was_set = unsafe {
+            set_threadpool_timer(self.get_inner(), &mut time, interval, window)
+        };
+
+        Some(was_set != 0)
+    }
+
+    #[inline(always)]
+    ///Returns clock, used by timer.
+    ///
+    ///Win doesn't allow to select clock, and relative timeouts are not affected by changes of system time,
+    ///hence it is always `Clock::Monotonic`.
+    pub fn clock(&self) -> Clock {
+        Clock::Monotonic
+    }
+
+    ///Returns current value of the timer's clock.
+    ///
+    ///Value is `FILETIME`, i.e. number of 100 nanoseconds intervals since January 1, 1601 (UTC), and can be used with `schedule_once_at_raw`.
+    pub fn clock_now(&self) -> u64 {
+        let mut now = ffi::FileTime {
+            low_date_time: 0,
+            high_date_time: 0,
+        };
+
+        unsafe {
+            ffi::GetSystemTimePreciseAsFileTime(&mut now);
+        }
+
+        (u64::from(now.high_date_time) << 32) | u64::from(now.low_date_time)
+    }
+
+    #[inline]
+    ///Returns value of the timer's clock after `delta` from now.
+    ///
+    ///See `clock_now` for details.
+    pub fn clock_after(&self, delta: time::Duration) -> u64 {
+        let delta = core::cmp::min(delta.as_nanos() / 100, u64::max_value().into()) as u64;
+        self.clock_now().saturating_add(delta)
+    }
+
+    ///Schedules timer to alarm once, when timer's clock reaches `clock_value`.
+    ///
+    ///`clock_value` must be obtained via `clock_now` or `clock_after` of the same timer, as its meaning depends
+    ///on platform and clock used by the timer.
+    ///If `clock_value` is already in past, timer fires immediately.
+    ///
+    ///Note that if timer has been scheduled before, but hasn't expire yet, it shall be cancelled.
+    ///
+    ///Returns `true` if successfully set, otherwise on error returns `false`.
+    ///It is error if `clock_value` cannot be represented as `FILETIME` (i.e. exceeds `i64::MAX`).
+    pub fn schedule_once_at_raw(&self, clock_value: u64) -> bool {
+        if !self.is_init() {
+            return false;
+        }
+
+        //Negative value is relative due time, hence absolute one must fit into i64
+        if clock_value > i64::max_value() as u64 {
+            return false;
+        }
+
+        #[cfg(feature = "std")]
+        {
+            if let Some(ctx) = self.context() {
+                ctx.deadline.set(None);
+            }
+        }
+
+        let timeout = time::Duration::from_nanos(clock_value.saturating_sub(self.clock_now()).saturating_mul(100));
+        let window = window_millis(self.default_leeway(timeout, time::Duration::from_secs(0)));
+
+        if let Some(ctx) = self.context() {
+            #[cfg(feature = "std")]
+            ctx.latency.arm(timeout, time::Duration::from_secs(0));
+            ctx.armed.arm(time::Duration::from_secs(0));
+            ctx.state.arm(time::Duration::from_secs(0));
+        }
+
+        let mut time = ffi::FileTime {
+            low_date_time: clock_value as u32,
+            high_date_time: (clock_value >> 32) as u32,
+        };
+
+        unsafe {
+            set_threadpool_timer(self.get_inner(), &mut time, 0, window);
         }
 
         true
@@ -233,39 +1209,682 @@ impl Timer {
     ///On Win/Mac it only returns whether timer has been scheduled, as there is no way to check
     ///whether timer is ongoing
     pub fn is_scheduled(&self) -> bool {
+        if !self.is_init() {
+            return false;
+        }
+
         let handle = self.get_inner();
         unsafe {
             ffi::IsThreadpoolTimerSet(handle) != 0
         }
     }
 
+    ///Returns approximate time remaining until next expiration of the timer.
+    ///
+    ///OS provides no way to query it, hence it is computed from the time timer was scheduled and its `interval`,
+    ///without accounting for system suspend.
+    ///Returns `None` if timer is not scheduled, one-shot timer has already expired.
+    ///Without `std` feature always returns `None`.
+    pub fn time_remaining(&self) -> Option<time::Duration> {
+        let ctx = self.context()?;
+        if !self.is_scheduled() {
+            return None;
+        }
+
+        #[cfg(feature = "std")]
+        {
+            ctx.latency.remaining()
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = ctx;
+            None
+        }
+    }
+
     #[inline]
     ///Cancels ongoing timer, if it was scheduled.
+    ///
+    ///Does nothing if timer is not initialized or has never been scheduled.
+    ///Otherwise blocks until all callbacks, that are already running, are finished.
+    ///
+    ///Must not be called from within timer's own callback, as it would wait for itself forever, use `cancel_async` instead.
     pub fn cancel(&self) {
+        if !self.is_init() {
+            return;
+        }
+
+        if let Some(ctx) = self.context() {
+            if ctx.state.get() == TimerState::Idle {
+                //There is nothing to cancel or to wait for.
+                return;
+            }
+            ctx.state.cancel();
+            #[cfg(feature = "std")]
+            ctx.waiters.notify();
+        }
+
         let handle = self.get_inner();
         unsafe {
             ffi::SetThreadpoolTimerEx(handle, ptr::null_mut(), 0, 0);
             ffi::WaitForThreadpoolTimerCallbacks(handle, 1);
         }
     }
+
+    #[inline]
+    ///Cancels ongoing timer, if it was scheduled, without waiting for running callbacks.
+    ///
+    ///Stops future alarms and discards callbacks, that are queued but have not started yet.
+    ///Callback, that is already running, may still complete after this method returns, hence
+    ///use `cancel` if you need to know that callback no longer runs.
+    ///
+    ///Does nothing if timer is not initialized or has never been scheduled.
+    pub fn cancel_async(&self) {
+        if !self.is_init() {
+            return;
+        }
+
+        if let Some(ctx) = self.context() {
+            if ctx.state.get() == TimerState::Idle {
+                return;
+            }
+            ctx.state.cancel();
+            #[cfg(feature = "std")]
+            ctx.waiters.notify();
+        }
+
+        let handle = self.get_inner();
+        unsafe {
+            ffi::SetThreadpoolTimerEx(handle, ptr::null_mut(), 0, 0);
+        }
+    }
+
+    #[inline(always)]
+    ///Returns number of expirations, missed by periodic timer before its most recent run.
+    ///
+    ///Only meaningful on POSIX, while Win/Mac always return `0`.
+    pub fn overrun_count(&self) -> i32 {
+        0
+    }
+
+    ///Returns interval of the periodic timer, as it has been scheduled.
+    ///
+    ///Interval is reported as used by thread pool, i.e. rounded to milliseconds.
+    ///
+    ///Returns `None` if timer is not initialized, not scheduled or scheduled to fire once.
+    pub fn scheduled_interval(&self) -> Option<time::Duration> {
+        let ctx = self.context()?;
+        match ctx.state.is_periodic() {
+            true => Some(ctx.armed.interval()),
+            false => None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when timer has been scheduled most recently.
+    ///
+    ///Together with `scheduled_interval` it allows to tell when periodic timer is expected to fire next.
+    ///Returns `None` if timer is not initialized or has never been scheduled.
+    pub fn last_armed_at(&self) -> Option<std::time::Instant> {
+        self.context()?.armed.at()
+    }
+
+    ///Pauses scheduled timer, so that `resume` continues its schedule later.
+    ///
+    ///On Windows thread pool doesn't report time until the next alarm, hence it is approximated as in `time_remaining`,
+    ///while without `std` feature timer resumes with full `interval` (one shot timer alarms right away).
+    ///Time spent paused is not accounted. Callback, that is already running, is not waited for.
+    ///
+    ///Does nothing if timer is not initialized or not scheduled.
+    pub fn pause(&self) {
+        if !self.is_init() {
+            return;
+        }
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+
+        //Returns whether timer has been set, hence timer cannot expire between check and disarm.
+        let was_set = unsafe {
+            ffi::SetThreadpoolTimerEx(self.get_inner(), ptr::null_mut(), 0, 0)
+        };
+        if was_set == 0 {
+            return;
+        }
+
+        #[cfg(feature = "std")]
+        let remaining = ctx.latency.remaining().unwrap_or_else(|| ctx.armed.interval());
+        #[cfg(not(feature = "std"))]
+        let remaining = ctx.armed.interval();
+        ctx.armed.pause(remaining);
+        #[cfg(feature = "std")]
+        ctx.latency.clear();
+    }
+
+    ///Resumes timer, paused by `pause`, to alarm after time that has been remaining at the moment of pause.
+    ///
+    ///Periodic timer continues with the same `interval` afterwards.
+    ///
+    ///Does nothing if timer is not paused, or it has been cancelled or scheduled again since then.
+    pub fn resume(&self) {
+        if !self.is_init() {
+            return;
+        }
+        let ctx = match self.context() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let remaining = match ctx.armed.take_paused() {
+            Some(remaining) => remaining,
+            None => return,
+        };
+        if ctx.state.get() != TimerState::Armed {
+            return;
+        }
+
+        let interval = ctx.armed.interval();
+        let mut time = relative_due_time(remaining);
+        let window = window_millis(self.default_leeway(remaining, interval));
+        #[cfg(feature = "std")]
+        ctx.latency.arm(remaining, interval);
+        ctx.elapsed.arm(interval);
+        unsafe {
+            set_threadpool_timer(self.get_inner(), &mut time, period_millis(interval), window);
+        }
+    }
+
+    #[inline]
+    ///Returns whether timer's callback is currently running.
+    ///
+    ///Result is a snapshot, that may be already outdated once returned: callback may start right after
+    ///`false` is returned, or finish right after `true` is returned.
+    ///Use `cancel` or drop timer to actually wait for callback to finish.
+    ///
+    ///Uninitialized timer is never running.
+    pub fn is_running(&self) -> bool {
+        match self.context() {
+            Some(ctx) => ctx.running.is_busy(),
+            None => false,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when timer is expected to fire next.
+    ///
+    ///It is tracked by the crate itself from the time timer was scheduled and its `interval`, without querying OS,
+    ///hence it doesn't account for delays of OS or system suspend.
+    ///Returns `None` if timer is not scheduled (e.g. one shot timer has already fired) or paused.
+    pub fn deadline(&self) -> Option<std::time::Instant> {
+        let ctx = self.context()?;
+        match ctx.state.get() {
+            TimerState::Armed => ctx.latency.next(),
+            _ => None,
+        }
+    }
+
+    ///Returns number of times timer fired, invoking its callback.
+    ///
+    ///Expirations, skipped or merged according to `CatchUp` policy, are counted as one, while `fire_sync` is not counted.
+    pub fn fire_count(&self) -> u64 {
+        match self.context() {
+            Some(ctx) => ctx.fires.load(Ordering::Acquire),
+            None => 0,
+        }
+    }
+
+    ///Sets policy to handle intervals, missed by periodic timer.
+    ///
+    ///Takes effect starting with the next expiration.
+    pub fn set_catch_up(&self, policy: CatchUp) {
+        if let Some(ctx) = self.context() {
+            ctx.pacing.set_policy(policy);
+        }
+    }
+
+    ///Returns policy to handle intervals, missed by periodic timer.
+    pub fn catch_up(&self) -> CatchUp {
+        match self.context() {
+            Some(ctx) => ctx.pacing.policy(),
+            None => CatchUp::default(),
+        }
+    }
+
+    #[inline]
+    ///Returns callback priority, requested on creation via `new_with_priority`.
+    ///
+    ///Returns `None` if timer uses default priority or it is not initialized.
+    pub fn priority(&self) -> Option<Priority> {
+        self.context()?.priority
+    }
+
+    #[inline(always)]
+    ///Returns features, supported by timer on current platform.
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            can_query_remaining: false,
+            can_select_clock: false,
+            supports_leeway: true,
+            callbacks_serialized: false,
+            min_resolution: time::Duration::from_millis(1),
+            supports_absolute: true,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Blocks current thread until timer's next expiration.
+    ///
+    ///Returns `true` once timer fires, or `false` if timer is not scheduled or gets cancelled while waiting.
+    ///Must not be called from within timer's callback, as next expiration may never come.
+    pub fn wait(&self) -> bool {
+        match self.context() {
+            Some(ctx) => ctx.waiters.wait(&ctx.fires, &ctx.state),
+            None => false,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns how late the most recent expiration was, compared to its scheduled time.
+    ///
+    ///For periodic timer it is relative to the latest point of its schedule, hence it is always less than `interval`.
+    ///Returns `None` if timer has not fired yet.
+    pub fn last_latency(&self) -> Option<time::Duration> {
+        self.context()?.latency.last()
+    }
+
+    #[cfg(feature = "std")]
+    ///Returns instant, when the most recent expiration was scheduled to happen.
+    ///
+    ///Together with `last_latency` it is tracked by the crate itself, and for periodic timer it is the latest point of its schedule.
+    ///Returns `None` if timer has not fired yet.
+    pub fn last_deadline(&self) -> Option<std::time::Instant> {
+        self.context()?.latency.due()
+    }
+
+    #[inline]
+    ///Returns timer's lifecycle state.
+    ///
+    ///Uninitialized timer is always `Idle`.
+    pub fn state(&self) -> TimerState {
+        match self.context() {
+            Some(ctx) => ctx.state.get(),
+            None => TimerState::Idle,
+        }
+    }
+
+    ///Replaces timer's callback with `cb`, without re-creating OS timer and keeping its schedule.
+    ///
+    ///Callbacks in flight are finished first, after which previous callback (with its closure, if any) is freed.
+    ///Expirations, that happen meanwhile, wait for the swap and then invoke `cb`.
+    ///Concurrent calls are serialized, hence timer can be shared (e.g. via `Arc`) while its callback is replaced.
+    ///
+    ///Must not be called from within timer's own callback, as it would wait for itself forever, nor concurrently with `fire_sync`.
+    ///
+    ///Returns `false` if timer is not initialized, in which case `cb` is dropped.
+    pub fn set_callback(&self, cb: Callback) -> bool {
+        let ctx = match self.context() {
+            Some(ctx) if self.is_init() => ctx,
+            _ => return false,
+        };
+
+        let (data, mut prev) = cb.into_handler();
+        //Swap keeps out both callbacks and concurrent `set_callback`.
+        ctx.running.begin_swap();
+        unsafe {
+            ctx.swap_handler(&mut prev);
+        }
+        //Previous closure is freed with its owner.
+        let prev_data = self.data.replace(data);
+        ctx.running.end_swap();
+
+        drop(prev);
+        drop(prev_data);
+        true
+    }
+
+    ///Invokes timer's callback synchronously on the calling thread.
+    ///
+    ///Unlike scheduling with zero timeout, callback runs before this function returns.
+    ///Primarily intended as testing aid.
+    ///
+    ///Does nothing if timer is not initialized.
+    ///
+    ///# Safety
+    ///
+    ///- Normally callback is invoked on the thread pool's worker. Callback that relies on that
+    ///(e.g. uses thread locals or expects to never run on the thread that owns the timer) must not be invoked this way.
+    ///Raw callbacks receive null `PTP_CALLBACK_INSTANCE`.
+    ///- If timer is scheduled, OS may invoke callback concurrently with this call. For closures it
+    ///means two mutable references to the same closure, hence user must make sure that timer is not scheduled
+    ///(or that callback is safe to run concurrently) before calling this function.
+    pub unsafe fn fire_sync(&self) {
+        if let Some(ctx) = self.context() {
+            ctx.invoke(ptr::null_mut(), self.inner.load(Ordering::Acquire));
+        }
+    }
 }
 
 impl Drop for Timer {
     fn drop(&mut self) {
         let handle = self.inner.load(Ordering::Relaxed);
         if !handle.is_null() {
+            if let Some(ctx) = self.context() {
+                ctx.running.close();
+            }
+            self.cancel();
+            //Callbacks in flight may re-schedule timer (see `Callback::with_timer`), while `cancel` waits for them.
             self.cancel();
             unsafe {
                 ffi::CloseThreadpoolTimer(handle);
             }
         }
+
+        let ctx = self.ctx.load(Ordering::Relaxed);
+        if !ctx.is_null() {
+            unsafe {
+                //Callbacks, started before OS timer is gone, may still use context and closure.
+                (*ctx).running.wait_idle();
+                let _ = Box::from_raw(ctx);
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate std;
+
     use super::*;
 
+    #[test]
+    fn from_raw() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        //Thread pool timer is created directly, without context, hence nothing is leaked once timer closes it.
+        let handle = unsafe {
+            ffi::CreateThreadpoolTimer(timer_callback, cb as fn() as *mut ffi::c_void, ptr::null_mut())
+        };
+        assert!(!handle.is_null());
+
+        let timer = unsafe {
+            Timer::from_raw(handle)
+        };
+        assert!(timer.is_init());
+        assert_eq!(timer.as_raw(), handle);
+
+        //Expirations are delivered to the original callback.
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert_eq!(timer.fire_count(), 0);
+    }
+
+    #[test]
+    fn relative_due_time_clamp() {
+        fn ticks(time: ffi::FileTime) -> i64 {
+            ((u64::from(time.high_date_time) << 32) | u64::from(time.low_date_time)) as i64
+        }
+
+        assert_eq!(ticks(relative_due_time(time::Duration::from_secs(0))), 0);
+        assert_eq!(ticks(relative_due_time(time::Duration::from_nanos(250))), -2);
+        assert_eq!(ticks(relative_due_time(time::Duration::from_millis(1500))), -15_000_000);
+        assert_eq!(ticks(relative_due_time(time::Duration::from_secs(u64::max_value()))), -i64::max_value());
+        assert_eq!(ticks(relative_due_time(time::Duration::new(u64::max_value(), 999_999_999))), -i64::max_value());
+    }
+
+    #[test]
+    fn sub_millisecond_period() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        assert_eq!(period_millis(time::Duration::from_secs(0)), 0);
+        assert_eq!(period_millis(time::Duration::from_micros(500)), 1);
+        assert_eq!(period_millis(time::Duration::from_micros(1500)), 1);
+        assert_eq!(period_millis(time::Duration::from_secs(u64::max_value())), u32::max_value());
+
+        //500us interval must not become one-shot.
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_millis(1), time::Duration::from_micros(500)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        timer.cancel();
+        assert!(COUNT.load(Ordering::Acquire) >= 2);
+    }
+
+    #[test]
+    fn capabilities() {
+        const CAPABILITIES: Capabilities = Timer::capabilities();
+
+        assert!(!CAPABILITIES.can_query_remaining);
+        assert!(!CAPABILITIES.can_select_clock);
+        assert!(CAPABILITIES.supports_leeway);
+        assert!(!CAPABILITIES.callbacks_serialized);
+        assert_eq!(CAPABILITIES.min_resolution, time::Duration::from_millis(1));
+        assert_eq!(Timer::RESOLUTION, time::Duration::from_millis(1));
+        assert_eq!(Timer::resolution(), Timer::RESOLUTION);
+        assert!(CAPABILITIES.supports_absolute);
+    }
+
+    #[test]
+    fn priority() {
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.priority(), None);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert_eq!(timer.priority(), None);
+
+        let timer = Timer::new_with_priority(Callback::plain(cb), Priority::Low).expect("To create timer");
+        assert_eq!(timer.priority(), Some(Priority::Low));
+    }
+
+    #[test]
+    fn new_with_priority() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new_with_priority(Callback::plain(cb), Priority::High).expect("To create timer");
+        assert_eq!(timer.priority(), Some(Priority::High));
+
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+    }
+
+    #[test]
+    fn schedule_interval_was_set() {
+        fn cb() {
+        }
+
+        let timer = unsafe {
+            Timer::uninit()
+        };
+        assert_eq!(timer.schedule_interval_was_set(time::Duration::from_secs(1), time::Duration::from_secs(0)), None);
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert_eq!(timer.schedule_interval_was_set(time::Duration::from_secs(10), time::Duration::from_secs(0)), Some(false));
+        assert_eq!(timer.schedule_interval_was_set(time::Duration::from_secs(20), time::Duration::from_secs(5)), Some(true));
+        assert!(timer.schedule_interval(time::Duration::from_secs(10), time::Duration::from_secs(0)));
+
+        timer.cancel();
+        assert_eq!(timer.schedule_interval_was_set(time::Duration::from_secs(10), time::Duration::from_secs(0)), Some(false));
+        timer.cancel();
+    }
+
+    #[test]
+    fn window_millis_saturates() {
+        assert_eq!(window_millis(time::Duration::from_secs(0)), 0);
+        assert_eq!(window_millis(time::Duration::from_micros(1500)), 1);
+        assert_eq!(window_millis(time::Duration::from_millis(u32::max_value().into())), u32::max_value());
+        assert_eq!(window_millis(time::Duration::from_millis(u64::from(u32::max_value()) + 1)), u32::max_value());
+        assert_eq!(window_millis(time::Duration::from_secs(u64::max_value())), u32::max_value());
+    }
+
+    #[test]
+    fn schedule_once_at_raw_unrepresentable() {
+        fn cb() {
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(!timer.schedule_once_at_raw(u64::max_value()));
+        assert!(!timer.schedule_once_at_raw(i64::max_value() as u64 + 1));
+        assert!(!timer.is_scheduled());
+
+        assert!(timer.schedule_once_at_raw(timer.clock_after(time::Duration::from_secs(10))));
+        assert!(timer.is_scheduled());
+        timer.cancel();
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn cancel_from_own_callback() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        let cb = |timer: &Timer| {
+            if COUNT.fetch_add(1, Ordering::AcqRel) + 1 == 3 {
+                timer.cancel_async();
+            }
+        };
+
+        let timer = Timer::new(Callback::with_timer(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(20)));
+        std::thread::sleep(time::Duration::from_millis(300));
+        assert_eq!(COUNT.load(Ordering::Acquire), 3);
+        assert_eq!(timer.state(), TimerState::Cancelled);
+        assert!(!timer.is_scheduled());
+    }
+
+    #[test]
+    fn new_in_pool() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        assert!(ThreadPool::new(2, 1).is_none());
+        assert!(ThreadPool::new(0, 0).is_none());
+
+        let pool = ThreadPool::new(1, 1).expect("To create thread pool");
+        let timer = Timer::new_in_pool(Callback::plain(cb), &pool).expect("To create timer");
+        let other = Timer::new_in_pool(Callback::plain(cb), &pool).expect("To create timer");
+        //Timers keep pool alive.
+        drop(pool);
+
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        assert!(other.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 2);
+    }
+
+    #[test]
+    fn schedule_interval_with_window() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_interval_with_window(time::Duration::from_millis(10), time::Duration::from_millis(50), time::Duration::from_millis(20)));
+        std::thread::sleep(time::Duration::from_millis(300));
+        timer.cancel();
+        assert!(COUNT.load(Ordering::Acquire) >= 2);
+    }
+
+    #[test]
+    fn cancel_async() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+            std::thread::sleep(time::Duration::from_millis(200));
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        timer.cancel_async();
+        assert!(timer.schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(10)));
+        while COUNT.load(Ordering::Acquire) == 0 {
+            std::thread::yield_now();
+        }
+
+        //Doesn't wait for running callback to finish.
+        let start = std::time::Instant::now();
+        timer.cancel_async();
+        assert!(start.elapsed() < time::Duration::from_millis(100));
+        assert!(!timer.is_scheduled());
+
+        timer.cancel();
+        let fired = COUNT.load(Ordering::Acquire);
+        std::thread::sleep(time::Duration::from_millis(100));
+        assert_eq!(COUNT.load(Ordering::Acquire), fired);
+    }
+
+    #[test]
+    fn cancel_async_discards_queued() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+            std::thread::sleep(time::Duration::from_millis(200));
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(10)));
+        while COUNT.load(Ordering::Acquire) == 0 {
+            std::thread::yield_now();
+        }
+        //Let expirations queue behind running callback.
+        std::thread::sleep(time::Duration::from_millis(50));
+        let fired = COUNT.load(Ordering::Acquire);
+
+        timer.cancel_async();
+        std::thread::sleep(time::Duration::from_millis(500));
+        assert_eq!(COUNT.load(Ordering::Acquire), fired);
+        assert_eq!(timer.state(), TimerState::Cancelled);
+    }
+
+    #[test]
+    fn schedule_once_fires_once() {
+        use core::sync::atomic::AtomicU8;
+        static COUNT: AtomicU8 = AtomicU8::new(0);
+
+        fn cb() {
+            COUNT.fetch_add(1, Ordering::AcqRel);
+        }
+
+        let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+        assert_eq!(timer.state(), TimerState::Fired);
+
+        //Zero period doesn't re-arm timer.
+        std::thread::sleep(time::Duration::from_millis(200));
+        assert_eq!(COUNT.load(Ordering::Acquire), 1);
+    }
+
     #[test]
     fn init_plain_fn() {
         let mut timer = unsafe {
@@ -275,6 +1894,7 @@ mod tests {
         fn cb() {
         }
 
+        #[cfg(feature = "alloc")]
         let closure = || {
         };
 
@@ -283,12 +1903,14 @@ mod tests {
         assert!(!ptr.is_null());
         assert!(timer.data.get_mut().is_null());
 
+        #[cfg(feature = "alloc")]
         assert!(!timer.init(Callback::closure(closure)));
         assert!(!ptr.is_null());
         assert_eq!(ptr, timer.inner.load(Ordering::Relaxed));
         assert!(timer.data.get_mut().is_null());
     }
 
+    #[cfg(feature = "alloc")]
     #[test]
     fn init_closure() {
         let mut timer = unsafe {
@@ -0,0 +1,61 @@
+//! Allocations are counted by global allocator, hence this test lives in its own binary.
+#![cfg(feature = "alloc")]
+
+use os_timer::{Callback, Timer};
+
+use core::time;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+//Counts allocations of the current thread, so that tests, running concurrently, do not interfere.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+#[test]
+fn timer_boxed_callback() {
+    use os_timer::BoxedCallback;
+    use std::sync::Arc;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let cb = count.clone();
+
+    let before = ALLOCATIONS.with(Cell::get);
+    let cb: BoxedCallback = Box::new(move || {
+        cb.fetch_add(1, Ordering::AcqRel);
+    });
+    let cb = Callback::boxed(cb);
+    //Closure is not boxed again.
+    assert_eq!(ALLOCATIONS.with(Cell::get) - before, 1);
+
+    let timer = Timer::new(cb).expect("To create timer");
+    unsafe {
+        timer.fire_sync();
+        timer.fire_sync();
+    }
+    assert_eq!(count.load(Ordering::Acquire), 2);
+
+    timer.schedule_once(time::Duration::from_millis(10));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 3);
+
+    drop(timer);
+    assert_eq!(Arc::strong_count(&count), 1);
+}
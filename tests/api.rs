@@ -0,0 +1,137 @@
+//! Uses every method of `Timer` and `Callback`, that is available on all platforms.
+//!
+//! As tests are compiled for each target, it ensures that public API is the same across platforms.
+use os_timer::{Callback, Capabilities, CatchUp, Clock, QosClass, Timer, TimerBuilder, TimerError, TimerGuard, TimerState};
+
+use core::time;
+
+fn cb() {
+}
+
+unsafe fn unsafe_cb() {
+}
+
+//Trivial callbacks can be built at compile time.
+const PLAIN_CB: Callback = Callback::plain(cb);
+const UNSAFE_PLAIN_CB: Callback = Callback::unsafe_plain(unsafe_cb);
+
+#[test]
+fn common_api() {
+    let _: Callback = Callback::plain(cb);
+    let _: Callback = PLAIN_CB;
+    let _: Callback = UNSAFE_PLAIN_CB;
+    let _: Callback = Callback::unsafe_plain(unsafe_cb);
+    let _: Callback = Callback::inline(|| {});
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    let _: bool = timer.is_init();
+    let _: bool = timer.init(Callback::plain(cb));
+    let _: Result<(), TimerError> = timer.try_init(Callback::plain(cb));
+    let _: Option<Timer> = Timer::new(PLAIN_CB);
+    let _: Result<Timer, TimerError> = Timer::try_new(PLAIN_CB);
+    let _: Result<Timer, TimerError> = Timer::start_once(PLAIN_CB, time::Duration::from_secs(10));
+    let _: Result<Timer, TimerError> = Timer::start_interval(PLAIN_CB, time::Duration::from_secs(10), time::Duration::from_secs(10));
+    let _: Result<Timer, TimerError> = TimerBuilder::new().callback(PLAIN_CB).clock(Clock::Monotonic).qos(QosClass::Default).leeway(time::Duration::from_millis(1)).build();
+    let _: TimerBuilder = TimerBuilder::default();
+    let timer: Timer = Timer::new_with_retry(PLAIN_CB, 1).expect("To create timer");
+    let _: bool = timer.set_callback(Callback::plain(cb));
+
+    let _: bool = timer.schedule_once(time::Duration::from_secs(10));
+    let _: Result<(), TimerError> = timer.try_schedule_once(time::Duration::from_secs(10));
+    let _: bool = timer.schedule_interval(time::Duration::from_secs(10), time::Duration::from_secs(10));
+    let _: Result<(), TimerError> = timer.try_schedule_interval(time::Duration::from_secs(10), time::Duration::from_secs(10));
+    let _: bool = timer.schedule_interval_with_leeway(time::Duration::from_secs(10), time::Duration::from_secs(10), time::Duration::from_millis(1));
+    let _: bool = timer.schedule_once_with_leeway(time::Duration::from_secs(10), time::Duration::from_millis(1));
+    let _: bool = timer.schedule_interval_immediate(time::Duration::from_secs(10));
+    let _: bool = timer.schedule_once_ms(10_000);
+    let _: bool = timer.schedule_interval_ms(10_000, 1_000);
+    let _: bool = timer.schedule_once_us(10_000_000);
+    let _: bool = timer.schedule_interval_us(10_000_000, 1_000_000);
+    let _: bool = timer.schedule_count(time::Duration::from_secs(10), 3);
+    let _: bool = timer.reschedule_interval(time::Duration::from_secs(10), time::Duration::from_secs(10));
+    let _: bool = timer.schedule_hz(time::Duration::from_secs(10), 0.1);
+    timer.cancel();
+    let _: Result<(), TimerError> = timer.schedule_interval_strict(time::Duration::from_secs(10), time::Duration::from_secs(10));
+    let _: bool = timer.schedule().initial(time::Duration::from_secs(10)).interval(time::Duration::from_secs(10)).leeway(time::Duration::from_millis(1)).schedule();
+    let _: Clock = timer.clock();
+    let _: u64 = timer.clock_now();
+    let _: bool = timer.schedule_once_at_raw(timer.clock_after(time::Duration::from_secs(10)));
+    let _: bool = timer.is_scheduled();
+    timer.pause();
+    timer.resume();
+    timer.set_catch_up(CatchUp::ReplayAll);
+    let _: CatchUp = timer.catch_up();
+    let _: u32 = CatchUp::MAX_REPLAY;
+    let _: i32 = timer.overrun_count();
+    let _: u64 = timer.fire_count();
+    let _: bool = timer.is_running();
+    let _: Capabilities = Timer::capabilities();
+    let _: time::Duration = Timer::RESOLUTION;
+    let _: time::Duration = Timer::resolution();
+    let _: TimerState = timer.state();
+    let _: os_timer::RawTimer = timer.as_raw();
+    let _: Option<TimerGuard<'_>> = timer.arm_scoped(time::Duration::from_secs(10), time::Duration::from_secs(0));
+    let _: Option<time::Duration> = timer.time_remaining();
+    let _: Option<time::Duration> = timer.scheduled_interval();
+    #[cfg(feature = "std")]
+    let _: Option<std::time::Instant> = timer.last_armed_at();
+    #[cfg(feature = "std")]
+    let _: Option<std::time::Instant> = timer.deadline();
+    timer.cancel();
+    unsafe {
+        timer.fire_sync();
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn common_alloc_api() {
+    use os_timer::BoxedCallback;
+
+    let _: Callback = Callback::closure(|| {});
+    let _: Callback = Callback::once(|| {});
+    let boxed: BoxedCallback = Box::new(|| {});
+    let _: Callback = Callback::boxed(boxed);
+    let _: Callback = Callback::with_count(|_: u64| {});
+    let _: Callback = Callback::with_elapsed(|_: u32| {});
+    let _: Callback = Callback::with_timer(|_: &Timer| {});
+    let _: Callback = Callback::repeating(|| false);
+    let _: Callback = Callback::with_context(|_: &u32| {}, 0u32);
+    let _: Callback = Callback::arc(std::sync::Arc::new(0u32), |_: &u32| {});
+    let _: Callback = cb.into();
+    let _: Callback = (cb as fn()).into();
+    let boxed: BoxedCallback = Box::new(|| {});
+    let _: Callback = boxed.into();
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    let boxed: BoxedCallback = Box::new(|| {});
+    let _: bool = timer.init(boxed);
+    let _: Option<Timer> = Timer::new(cb);
+    let _: Result<Timer, TimerError> = Timer::try_new(cb);
+    let _: Result<Timer, TimerError> = Timer::start_once(cb, time::Duration::from_secs(10));
+    let _: Result<Timer, TimerError> = Timer::start_interval(cb, time::Duration::from_secs(10), time::Duration::from_secs(10));
+    let _: Result<Timer, TimerError> = TimerBuilder::new().callback(cb).build();
+    let _: Timer = Timer::new_with_retry(cb, 1).expect("To create timer");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn common_std_api() {
+    use std::time::Instant;
+
+    let timer = Timer::new(cb).expect("To create timer");
+    let _: bool = timer.schedule_interval_until(time::Duration::from_secs(10), Instant::now() + time::Duration::from_secs(10));
+    let _: bool = timer.schedule_at(Instant::now() + time::Duration::from_secs(10));
+    let _: bool = timer.schedule_interval_from(Instant::now() + time::Duration::from_secs(10), time::Duration::from_secs(10));
+    let _: Option<time::Duration> = timer.last_latency();
+    let _: Option<Instant> = timer.last_deadline();
+    let _: Callback = Callback::with_instant(|_: Instant, _: Option<Instant>| ());
+    timer.cancel();
+    let _: bool = timer.wait();
+
+    let _: Option<os_timer::After> = os_timer::after(time::Duration::from_secs(10), || ());
+}
@@ -0,0 +1,48 @@
+//! Coalescing switch and forwarded leeway are global, hence this test lives in its own binary.
+#![cfg(debug_assertions)]
+
+use os_timer::{Callback, Timer};
+
+use core::time;
+
+//Returns leeway, that OS is expected to receive, if platform supports it.
+fn expected(leeway: time::Duration) -> Option<time::Duration> {
+    match Timer::capabilities().supports_leeway {
+        true => Some(leeway),
+        false => None,
+    }
+}
+
+#[test]
+fn global_coalescing_forwards_leeway() {
+    fn cb() {
+    }
+
+    let second = time::Duration::from_secs(1);
+    let zero = time::Duration::from_secs(0);
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    assert!(!os_timer::global_coalescing());
+    assert!(timer.schedule_once(second));
+    assert_eq!(os_timer::last_forwarded_leeway(), expected(zero));
+    assert!(timer.schedule_interval(second, second));
+    assert_eq!(os_timer::last_forwarded_leeway(), expected(zero));
+
+    os_timer::set_global_coalescing(true);
+    assert!(os_timer::global_coalescing());
+    assert!(timer.schedule_once(second));
+    assert_eq!(os_timer::last_forwarded_leeway(), expected(time::Duration::from_millis(100)));
+    assert!(timer.schedule_interval(second, time::Duration::from_millis(500)));
+    assert_eq!(os_timer::last_forwarded_leeway(), expected(time::Duration::from_millis(50)));
+
+    //Explicit leeway takes precedence over global switch.
+    assert!(timer.schedule_interval_with_leeway(second, second, time::Duration::from_millis(20)));
+    assert_eq!(os_timer::last_forwarded_leeway(), expected(time::Duration::from_millis(20)));
+
+    os_timer::set_global_coalescing(false);
+    assert!(!os_timer::global_coalescing());
+    assert!(timer.schedule_interval(second, second));
+    assert_eq!(os_timer::last_forwarded_leeway(), expected(zero));
+
+    timer.cancel();
+}
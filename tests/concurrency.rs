@@ -0,0 +1,232 @@
+//! Timer is shared between threads by reference, so these tests hammer the same timer from multiple threads.
+//!
+//! To check for data races run them under thread sanitizer:
+//!
+//! `RUSTFLAGS="-Zsanitizer=thread" cargo +nightly test -Zbuild-std --target <target> --test concurrency`
+#![cfg(feature = "alloc")]
+
+use os_timer::{Callback, Timer};
+
+use core::time;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+
+const THREADS: usize = 4;
+const ITERATIONS: usize = 1000;
+
+#[test]
+fn concurrent_schedule_and_cancel() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let cb = {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        }
+    };
+    let timer = Arc::new(Timer::new(Callback::closure(cb)).expect("To create timer"));
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let threads = (0..THREADS).map(|idx| {
+        let timer = timer.clone();
+        let barrier = barrier.clone();
+        std::thread::spawn(move || {
+            barrier.wait();
+            for iteration in 0..ITERATIONS {
+                match (idx + iteration) % 3 {
+                    0 => {
+                        timer.schedule_interval(time::Duration::from_micros(100), time::Duration::from_micros(100));
+                    },
+                    1 => {
+                        timer.schedule_once(time::Duration::from_micros(50));
+                    },
+                    _ => timer.cancel(),
+                }
+                let _ = timer.is_scheduled();
+                let _ = timer.state();
+            }
+        })
+    }).collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().expect("Thread to finish without panic");
+    }
+
+    timer.cancel();
+    assert!(!timer.is_scheduled());
+    //Let any callback, that is already in flight, to finish.
+    std::thread::sleep(time::Duration::from_millis(50));
+
+    let fired = count.load(Ordering::Acquire);
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::Acquire), fired);
+
+    drop(timer);
+    //Closure is freed with timer
+    assert_eq!(Arc::strong_count(&count), 1);
+}
+
+#[test]
+fn concurrent_init() {
+    for _ in 0..50 {
+        let count = Arc::new(AtomicUsize::new(0));
+        let timer = Arc::new(unsafe {
+            Timer::uninit()
+        });
+        let barrier = Arc::new(Barrier::new(THREADS));
+
+        let threads = (0..THREADS).map(|_| {
+            let timer = timer.clone();
+            let barrier = barrier.clone();
+            let count = count.clone();
+            std::thread::spawn(move || {
+                let cb = move || {
+                    count.fetch_add(1, Ordering::AcqRel);
+                };
+                barrier.wait();
+                let is_init = timer.init(Callback::closure(cb));
+                if timer.is_init() {
+                    //Initialized timer is immediately usable.
+                    timer.cancel();
+                }
+                is_init
+            })
+        }).collect::<Vec<_>>();
+
+        let initialized = threads.into_iter().map(|thread| thread.join().expect("Thread to finish without panic")).filter(|is_init| *is_init).count();
+        assert_eq!(initialized, 1);
+        assert!(timer.is_init());
+
+        //Only closure of the timer remains.
+        assert_eq!(Arc::strong_count(&count), 2);
+        assert_eq!(timer.try_schedule_once(time::Duration::from_millis(1)), Ok(()));
+        std::thread::sleep(time::Duration::from_millis(50));
+        assert_eq!(count.load(Ordering::Acquire), 1);
+
+        drop(timer);
+        assert_eq!(Arc::strong_count(&count), 1);
+    }
+}
+
+#[test]
+fn concurrent_init_frees_losing_closures() {
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    let dropped = Arc::new(AtomicUsize::new(0));
+    for _ in 0..50 {
+        let timer = Arc::new(unsafe {
+            Timer::uninit()
+        });
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let before = dropped.load(Ordering::Acquire);
+
+        let threads = (0..THREADS).map(|_| {
+            let timer = timer.clone();
+            let barrier = barrier.clone();
+            let counter = DropCounter(dropped.clone());
+            std::thread::spawn(move || {
+                let cb = move || {
+                    let _ = &counter;
+                };
+                barrier.wait();
+                timer.init(Callback::closure(cb))
+            })
+        }).collect::<Vec<_>>();
+
+        let initialized = threads.into_iter().map(|thread| thread.join().expect("Thread to finish without panic")).filter(|is_init| *is_init).count();
+        assert_eq!(initialized, 1);
+        //Every closure, except the one owned by timer, is dropped once init returns.
+        assert_eq!(dropped.load(Ordering::Acquire) - before, THREADS - 1);
+
+        drop(timer);
+        assert_eq!(dropped.load(Ordering::Acquire) - before, THREADS);
+    }
+    assert_eq!(Arc::strong_count(&dropped), 1);
+}
+
+#[test]
+fn drop_while_firing() {
+    let count = Arc::new(AtomicUsize::new(0));
+
+    for idx in 0..200 {
+        let timers = (0..THREADS).map(|_| {
+            let count = count.clone();
+            let timer = Timer::new(Callback::closure(move || {
+                count.fetch_add(1, Ordering::AcqRel);
+            })).expect("To create timer");
+            assert!(timer.schedule_interval(time::Duration::from_secs(0), time::Duration::from_micros(100)));
+            timer
+        }).collect::<Vec<_>>();
+
+        std::thread::sleep(time::Duration::from_micros(100 * (idx % 10)));
+        //Once dropped, timer no longer holds its closure, even if callback was about to run.
+        drop(timers);
+        assert_eq!(Arc::strong_count(&count), 1);
+    }
+}
+
+#[test]
+fn drop_during_fast_periodic_firing() {
+    let count = Arc::new(AtomicUsize::new(0));
+
+    for idx in 0..300 {
+        let timer = {
+            let count = count.clone();
+            Timer::new(Callback::closure(move || {
+                count.fetch_add(1, Ordering::AcqRel);
+            })).expect("To create timer")
+        };
+        assert!(timer.schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(1)));
+
+        std::thread::sleep(time::Duration::from_micros(500 * (idx % 6)));
+        //Notification, that OS has already started, must not reach context, once it is freed.
+        drop(timer);
+        assert_eq!(Arc::strong_count(&count), 1);
+        //Gives such notifications time to run, while memory of freed context is reused.
+        let reuse = vec![0xa5u8; 512];
+        std::thread::sleep(time::Duration::from_micros(200));
+        drop(reuse);
+    }
+}
+
+#[test]
+fn set_callback_while_firing() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let timer = Arc::new(Timer::new(Callback::plain(|| ())).expect("To create timer"));
+    assert!(timer.schedule_interval(time::Duration::from_secs(0), time::Duration::from_micros(100)));
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let threads = (0..THREADS).map(|_| {
+        let timer = timer.clone();
+        let barrier = barrier.clone();
+        let count = count.clone();
+        std::thread::spawn(move || {
+            barrier.wait();
+            for _ in 0..ITERATIONS / 10 {
+                let count = count.clone();
+                assert!(timer.set_callback(Callback::closure(move || {
+                    count.fetch_add(1, Ordering::AcqRel);
+                })));
+            }
+        })
+    }).collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().expect("Thread to finish without panic");
+    }
+
+    //Schedule is kept across replacements.
+    assert!(timer.is_scheduled());
+    let fired = count.load(Ordering::Acquire);
+    std::thread::sleep(time::Duration::from_millis(50));
+    assert!(count.load(Ordering::Acquire) > fired);
+
+    drop(timer);
+    //Only the last closure is still held, and it is freed with timer.
+    assert_eq!(Arc::strong_count(&count), 1);
+}
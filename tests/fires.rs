@@ -0,0 +1,528 @@
+use os_timer::{Callback, Timer};
+
+use core::time;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "alloc")]
+use std::sync::Arc;
+
+#[cfg(feature = "alloc")]
+fn counting_timer() -> (Timer, Arc<AtomicUsize>) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let cb = {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        }
+    };
+
+    (Timer::new(Callback::closure(cb)).expect("To create timer"), count)
+}
+
+#[cfg(not(feature = "alloc"))]
+//Without heap closures count is captured by reference, hence it is leaked to outlive timer.
+fn counting_timer() -> (Timer, &'static AtomicUsize) {
+    let count: &'static AtomicUsize = Box::leak(Box::new(AtomicUsize::new(0)));
+    let cb = move || {
+        count.fetch_add(1, Ordering::AcqRel);
+    };
+
+    (Timer::new(Callback::inline(cb)).expect("To create timer"), count)
+}
+
+#[test]
+fn fires_once() {
+    let (timer, count) = counting_timer();
+
+    timer.schedule_once(time::Duration::from_millis(50));
+    std::thread::sleep(time::Duration::from_millis(500));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn fires_once_on_start() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let cb = {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        }
+    };
+
+    let timer = Timer::start_once(cb, time::Duration::from_millis(50)).expect("To start timer");
+    assert!(timer.is_scheduled());
+    std::thread::sleep(time::Duration::from_millis(500));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn fires_interval_on_start() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let cb = {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        }
+    };
+
+    let timer = Timer::start_interval(cb, time::Duration::from_millis(20), time::Duration::from_millis(20)).expect("To start timer");
+    std::thread::sleep(time::Duration::from_millis(300));
+    timer.cancel();
+    assert!(count.load(Ordering::Acquire) > 2);
+}
+
+#[test]
+fn fires_once_with_leeway() {
+    let (timer, count) = counting_timer();
+
+    assert!(timer.schedule_once_with_leeway(time::Duration::from_millis(50), time::Duration::from_millis(100)));
+    std::thread::sleep(time::Duration::from_millis(500));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn fires_interval() {
+    let (timer, count) = counting_timer();
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(20), time::Duration::from_millis(20)));
+    std::thread::sleep(time::Duration::from_millis(500));
+    timer.cancel();
+
+    //Expected about 25 fires, but CI machines can be very slow.
+    let fired = count.load(Ordering::Acquire);
+    assert!(fired >= 5, "Interval timer fired only {} times", fired);
+    assert!(fired <= 30, "Interval timer fired {} times", fired);
+}
+
+#[test]
+fn fires_interval_immediately() {
+    let (timer, count) = counting_timer();
+
+    let before = std::time::Instant::now();
+    assert!(timer.schedule_interval_immediate(time::Duration::from_secs(1)));
+    while count.load(Ordering::Acquire) == 0 {
+        assert!(before.elapsed() < time::Duration::from_millis(500), "Timer didn't fire immediately");
+        std::thread::sleep(time::Duration::from_millis(1));
+    }
+    //Next one is only after interval.
+    assert_eq!(count.load(Ordering::Acquire), 1);
+    assert!(timer.is_scheduled());
+    timer.cancel();
+
+    let (timer, count) = counting_timer();
+
+    assert!(timer.schedule_interval_immediate(time::Duration::from_millis(300)));
+    std::thread::sleep(time::Duration::from_millis(150));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(count.load(Ordering::Acquire), 2);
+    timer.cancel();
+}
+
+#[test]
+fn fires_interval_with_large_leeway() {
+    let (timer, count) = counting_timer();
+
+    //Leeway is clamped to interval, so timer remains periodic.
+    assert!(timer.schedule().initial(time::Duration::from_millis(20)).interval(time::Duration::from_millis(20)).leeway(time::Duration::from_secs(10)).schedule());
+    std::thread::sleep(time::Duration::from_millis(500));
+    timer.cancel();
+
+    let fired = count.load(Ordering::Acquire);
+    assert!(fired >= 5, "Interval timer fired only {} times", fired);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fires_interval_from_instant() {
+    use std::time::Instant;
+
+    let (timer, count) = counting_timer();
+
+    //First alarm in past fires as soon as possible.
+    let before = Instant::now();
+    assert!(timer.schedule_interval_from(before - time::Duration::from_millis(100), time::Duration::from_secs(1)));
+    while count.load(Ordering::Acquire) == 0 {
+        assert!(before.elapsed() < time::Duration::from_millis(500), "Timer didn't fire immediately");
+        std::thread::sleep(time::Duration::from_millis(1));
+    }
+    timer.cancel();
+
+    let (timer, count) = counting_timer();
+
+    let first = Instant::now() + time::Duration::from_millis(200);
+    assert!(timer.schedule_interval_from(first, time::Duration::from_secs(1)));
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::Acquire), 0);
+    while count.load(Ordering::Acquire) == 0 {
+        assert!(first.elapsed() < time::Duration::from_millis(500), "Timer didn't fire at first instant");
+        std::thread::sleep(time::Duration::from_millis(1));
+    }
+    assert!(Instant::now() >= first);
+    timer.cancel();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fires_at_instant() {
+    use std::time::Instant;
+
+    let (timer, count) = counting_timer();
+
+    //Deadline in past fires as soon as possible.
+    let before = Instant::now();
+    assert!(timer.schedule_at(before - time::Duration::from_millis(100)));
+    while count.load(Ordering::Acquire) == 0 {
+        assert!(before.elapsed() < time::Duration::from_millis(500), "Timer didn't fire immediately");
+        std::thread::sleep(time::Duration::from_millis(1));
+    }
+
+    let deadline = Instant::now() + time::Duration::from_millis(200);
+    assert!(timer.schedule_at(deadline));
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+    while count.load(Ordering::Acquire) == 1 {
+        assert!(deadline.elapsed() < time::Duration::from_millis(500), "Timer didn't fire at deadline");
+        std::thread::sleep(time::Duration::from_millis(1));
+    }
+    assert!(Instant::now() >= deadline);
+
+    //Fires only once.
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 2);
+}
+
+#[test]
+fn counts_fires() {
+    let (timer, count) = counting_timer();
+    assert_eq!(timer.fire_count(), 0);
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(100));
+    let fired = timer.fire_count();
+    assert!(fired > 0);
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert!(timer.fire_count() > fired);
+
+    timer.cancel();
+    //Let any callback, that is already in flight, to finish.
+    std::thread::sleep(time::Duration::from_millis(50));
+    //Callback runs once per fire by default.
+    assert_eq!(timer.fire_count(), count.load(Ordering::Acquire) as u64);
+
+    unsafe {
+        timer.fire_sync();
+    }
+    assert_eq!(timer.fire_count(), count.load(Ordering::Acquire) as u64 - 1);
+}
+
+#[test]
+fn stops_after_cancel() {
+    let (timer, count) = counting_timer();
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(20), time::Duration::from_millis(20)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    timer.cancel();
+    assert!(!timer.is_scheduled());
+
+    //Let any callback, that is already in flight, to finish.
+    std::thread::sleep(time::Duration::from_millis(50));
+    let fired = count.load(Ordering::Acquire);
+    assert!(fired > 0);
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(count.load(Ordering::Acquire), fired);
+
+    timer.schedule_once(time::Duration::from_millis(50));
+    timer.cancel();
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(count.load(Ordering::Acquire), fired);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn stops_after_deadline() {
+    let (timer, count) = counting_timer();
+
+    let deadline = std::time::Instant::now() + time::Duration::from_millis(200);
+    assert!(timer.schedule_interval_until(time::Duration::from_millis(20), deadline));
+    std::thread::sleep(time::Duration::from_millis(500));
+
+    assert!(!timer.is_scheduled());
+    assert_eq!(timer.state(), os_timer::TimerState::Cancelled);
+    let fired = count.load(Ordering::Acquire);
+    assert!(fired > 0);
+    assert!(fired <= 10, "Interval timer fired {} times", fired);
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), fired);
+}
+
+#[test]
+fn stops_after_count() {
+    let (timer, count) = counting_timer();
+
+    assert!(timer.schedule_count(time::Duration::from_millis(20), 5));
+    std::thread::sleep(time::Duration::from_millis(500));
+
+    assert!(!timer.is_scheduled());
+    assert_eq!(timer.state(), os_timer::TimerState::Cancelled);
+    assert_eq!(count.load(Ordering::Acquire), 5);
+    assert_eq!(timer.fire_count(), 5);
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 5);
+
+    //Zero count does nothing, while single alarm is just one shot timer.
+    assert!(timer.schedule_count(time::Duration::from_millis(20), 0));
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::Acquire), 5);
+
+    assert!(timer.schedule_count(time::Duration::from_millis(20), 1));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 6);
+    assert_eq!(timer.state(), os_timer::TimerState::Fired);
+
+    //Subsequent schedule removes limit.
+    assert!(timer.schedule_count(time::Duration::from_secs(10), 2));
+    assert!(timer.schedule_interval(time::Duration::from_millis(20), time::Duration::from_millis(20)));
+    std::thread::sleep(time::Duration::from_millis(300));
+    timer.cancel();
+    assert!(count.load(Ordering::Acquire) > 8);
+}
+
+#[test]
+fn pause_and_resume_interval() {
+    let (timer, count) = counting_timer();
+
+    //Pausing timer, that is not scheduled, does nothing.
+    timer.pause();
+    timer.resume();
+    assert!(!timer.is_scheduled());
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(50), time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(175));
+    timer.pause();
+    assert!(!timer.is_scheduled());
+    //Callback, that has been in flight, may still complete.
+    std::thread::sleep(time::Duration::from_millis(20));
+    let fired = count.load(Ordering::Acquire);
+    assert!(fired > 0);
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(count.load(Ordering::Acquire), fired);
+
+    timer.resume();
+    assert!(timer.is_scheduled());
+    std::thread::sleep(time::Duration::from_millis(300));
+    timer.cancel();
+    assert!(count.load(Ordering::Acquire) > fired);
+}
+
+#[test]
+fn pause_and_resume_once() {
+    let (timer, count) = counting_timer();
+
+    assert!(timer.schedule_once(time::Duration::from_millis(200)));
+    std::thread::sleep(time::Duration::from_millis(50));
+    timer.pause();
+    std::thread::sleep(time::Duration::from_millis(400));
+    assert_eq!(count.load(Ordering::Acquire), 0);
+
+    timer.resume();
+    std::thread::sleep(time::Duration::from_millis(400));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+
+    //Cancelled timer is not resumed.
+    assert!(timer.schedule_once(time::Duration::from_millis(100)));
+    timer.pause();
+    timer.cancel();
+    timer.resume();
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn fires_at_raw_clock_value() {
+    let (timer, count) = counting_timer();
+
+    let before = std::time::Instant::now();
+    let start = timer.clock_now();
+    let target = timer.clock_after(time::Duration::from_millis(100));
+    assert!(target > start);
+
+    assert!(timer.schedule_once_at_raw(target));
+    while count.load(Ordering::Acquire) == 0 {
+        assert!(before.elapsed() < time::Duration::from_secs(2), "Timer didn't fire");
+        std::thread::sleep(time::Duration::from_millis(1));
+    }
+
+    let elapsed = before.elapsed();
+    assert!(elapsed >= time::Duration::from_millis(90), "Timer fired too early: {:?}", elapsed);
+    assert!(timer.clock_now() >= target);
+
+    //Value in past fires immediately
+    assert!(timer.schedule_once_at_raw(start));
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::Acquire), 2);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn drop_waits_for_callback() {
+    use core::sync::atomic::AtomicBool;
+
+    struct Resource {
+        in_callback: Arc<AtomicBool>,
+        value: Vec<usize>,
+    }
+
+    for _ in 0..10 {
+        let in_callback = Arc::new(AtomicBool::new(false));
+        let mut resource = Resource {
+            in_callback: in_callback.clone(),
+            value: vec![0; 4],
+        };
+        let cb = move || {
+            resource.in_callback.store(true, Ordering::Release);
+            for value in resource.value.iter_mut() {
+                *value += 1;
+                std::thread::sleep(time::Duration::from_micros(500));
+            }
+            resource.in_callback.store(false, Ordering::Release);
+        };
+
+        let timer = Timer::new(Callback::closure(cb)).expect("To create timer");
+        assert!(timer.schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(5)));
+
+        while !in_callback.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+        drop(timer);
+        assert!(!in_callback.load(Ordering::Acquire));
+        //Only closure owns it, so once closure is gone, there should be only one reference
+        assert_eq!(Arc::strong_count(&in_callback), 1);
+    }
+}
+
+#[cfg(feature = "alloc")]
+//Runs periodic timer with `policy`, which callback stalls on its first run for several intervals.
+fn catch_up_calls(policy: os_timer::CatchUp) -> Vec<std::time::Instant> {
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let cb = {
+        let calls = calls.clone();
+        move || {
+            let len = {
+                let mut calls = calls.lock().unwrap();
+                calls.push(Instant::now());
+                calls.len()
+            };
+            //First run takes several intervals, which must be replayed at once by the next expiration.
+            if len == 1 {
+                std::thread::sleep(time::Duration::from_millis(300));
+            }
+        }
+    };
+
+    let timer = Timer::new(Callback::closure(cb)).expect("To create timer");
+    assert_eq!(timer.catch_up(), os_timer::CatchUp::Single);
+    timer.set_catch_up(policy);
+    assert_eq!(timer.catch_up(), policy);
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(600));
+    timer.cancel();
+    std::thread::sleep(time::Duration::from_millis(50));
+
+    let calls = calls.lock().unwrap();
+    calls.clone()
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn catch_up_replays_missed_intervals() {
+    let calls = catch_up_calls(os_timer::CatchUp::ReplayBounded(2));
+    assert!(calls.len() >= 5, "Timer fired only {} times", calls.len());
+    //Current expiration and 2 replayed ones run back to back.
+    assert!(calls[3] - calls[1] < time::Duration::from_millis(25), "Missed intervals are not replayed at once");
+    //After that timer continues at its regular schedule.
+    assert!(calls[4] - calls[3] >= time::Duration::from_millis(25), "Missed intervals are replayed more than bound");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn catch_up_single_skips_missed_intervals() {
+    let calls = catch_up_calls(os_timer::CatchUp::Single);
+    assert!(calls.len() >= 3, "Timer fired only {} times", calls.len());
+    assert!(calls[2] - calls[1] >= time::Duration::from_millis(25), "Missed intervals are replayed");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn catch_up_replays_all_missed_intervals() {
+    let calls = catch_up_calls(os_timer::CatchUp::ReplayAll);
+    //Slow run misses about 5 intervals.
+    assert!(calls.len() >= 9, "Timer fired only {} times", calls.len());
+    assert!(calls[5] - calls[1] < time::Duration::from_millis(25), "Missed intervals are not replayed at once");
+    assert!(calls[8] - calls[1] >= time::Duration::from_millis(25), "Too many intervals are replayed");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn reports_elapsed_intervals() {
+    use std::sync::Mutex;
+
+    let counts = Arc::new(Mutex::new(Vec::new()));
+    let cb = {
+        let counts = counts.clone();
+        move |count| {
+            let len = {
+                let mut counts = counts.lock().unwrap();
+                counts.push(count);
+                counts.len()
+            };
+            //First run takes several intervals.
+            if len == 1 {
+                std::thread::sleep(time::Duration::from_millis(220));
+            }
+        }
+    };
+
+    let timer = Timer::new(Callback::with_elapsed(cb)).expect("To create timer");
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(500));
+    timer.cancel();
+    std::thread::sleep(time::Duration::from_millis(50));
+
+    let counts = counts.lock().unwrap();
+    assert!(counts.len() >= 2, "Timer fired only {} times", counts.len());
+    assert_eq!(counts[0], 1);
+    //Slow run covers about 4 intervals, but it may be estimated.
+    assert!(counts[1] >= 3, "Elapsed intervals are not counted: {:?}", counts);
+    assert!(counts[1] <= 6, "Too many elapsed intervals: {:?}", counts);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reports_latency() {
+    let (timer, count) = counting_timer();
+    assert_eq!(timer.last_latency(), None);
+
+    timer.schedule_once(time::Duration::from_millis(50));
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+
+    let latency = timer.last_latency().expect("To have latency after fire");
+    //CI machines can be very slow, so only check that it is sane.
+    assert!(latency < time::Duration::from_millis(200), "Latency is too high: {:?}", latency);
+}
@@ -0,0 +1,149 @@
+#![cfg(feature = "futures")]
+
+use os_timer::{MissedTickBehavior, Timer, TimerFuture, TimerStream};
+use futures_core::Stream;
+
+use core::time;
+use core::pin::Pin;
+use core::future::Future;
+use std::sync::Arc;
+use std::task;
+
+struct ThreadWaker(std::thread::Thread);
+
+impl task::Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future + Unpin>(mut fut: F) -> F::Output {
+    let waker = task::Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut ctx = task::Context::from_waker(&waker);
+
+    loop {
+        match Pin::new(&mut fut).poll(&mut ctx) {
+            task::Poll::Ready(result) => break result,
+            task::Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+#[test]
+fn timer_future_completes() {
+    let before = std::time::Instant::now();
+    let mut fut = TimerFuture::new(time::Duration::from_millis(100));
+    assert!(!fut.is_expired());
+
+    block_on(&mut fut);
+    assert!(fut.is_expired());
+    assert!(before.elapsed() >= time::Duration::from_millis(100));
+
+    fut.reset(time::Duration::from_millis(50));
+    assert!(!fut.is_expired());
+    let before = std::time::Instant::now();
+    block_on(&mut fut);
+    assert!(before.elapsed() >= time::Duration::from_millis(50));
+}
+
+#[test]
+fn timer_future_reset_ignores_previous_arming() {
+    let waker = task::Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let mut fut = TimerFuture::new(time::Duration::from_millis(1));
+    //Reset races with expiration of previous arming at varying points.
+    for attempt in 0..50 {
+        fut.reset(time::Duration::from_millis(1));
+        let _ = Pin::new(&mut fut).poll(&mut ctx);
+        std::thread::sleep(time::Duration::from_micros(attempt * 40));
+
+        fut.reset(time::Duration::from_secs(10));
+        assert!(!fut.is_expired(), "Attempt {} completed by previous arming", attempt);
+        assert!(Pin::new(&mut fut).poll(&mut ctx).is_pending(), "Attempt {} completed by previous arming", attempt);
+        std::thread::sleep(time::Duration::from_micros(500));
+        assert!(!fut.is_expired(), "Attempt {} completed by previous arming", attempt);
+    }
+}
+
+#[test]
+fn timer_future_drop_early() {
+    let waker = task::Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let mut fut = TimerFuture::new(time::Duration::from_millis(100));
+    assert!(Pin::new(&mut fut).poll(&mut ctx).is_pending());
+    drop(fut);
+
+    std::thread::sleep(time::Duration::from_millis(300));
+}
+
+#[test]
+fn timer_sleep() {
+    let before = std::time::Instant::now();
+    futures_executor::block_on(Timer::sleep(time::Duration::from_millis(100)));
+    assert!(before.elapsed() >= time::Duration::from_millis(100));
+}
+
+#[test]
+fn timer_interval_stream() {
+    let before = std::time::Instant::now();
+    let ticks = futures_executor::block_on_stream(Timer::interval(time::Duration::from_millis(50))).take(3).count();
+    assert_eq!(ticks, 3);
+    assert!(before.elapsed() >= time::Duration::from_millis(150));
+}
+
+#[test]
+fn timer_interval_stream_zero() {
+    assert!(TimerStream::try_new(time::Duration::from_secs(0)).is_none());
+    assert!(TimerStream::try_with_behavior(time::Duration::from_secs(0), MissedTickBehavior::Burst).is_none());
+}
+
+#[test]
+#[should_panic(expected = "`interval` must be non-zero")]
+fn timer_interval_stream_zero_panics() {
+    let _ = Timer::interval(time::Duration::from_secs(0));
+}
+
+//Arms stream, stalls consumer for `stall` and returns number of ticks, yielded right after that.
+fn stalled_ticks(stream: &mut TimerStream, stall: time::Duration, ctx: &mut task::Context<'_>) -> usize {
+    assert!(Pin::new(&mut *stream).poll_next(ctx).is_pending());
+    std::thread::sleep(stall);
+
+    let mut ticks = 0;
+    while let task::Poll::Ready(Some(())) = Pin::new(&mut *stream).poll_next(ctx) {
+        ticks += 1;
+    }
+    ticks
+}
+
+#[test]
+fn timer_interval_stream_missed_ticks() {
+    let waker = task::Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut ctx = task::Context::from_waker(&waker);
+    let interval = time::Duration::from_millis(100);
+    let stall = time::Duration::from_millis(550);
+
+    let stream = Timer::interval(interval);
+    assert_eq!(stream.missed_tick_behavior(), MissedTickBehavior::Skip);
+    assert_eq!(MissedTickBehavior::default(), MissedTickBehavior::Skip);
+
+    let mut stream = Timer::interval_stream(interval, MissedTickBehavior::Burst);
+    assert_eq!(stream.missed_tick_behavior(), MissedTickBehavior::Burst);
+    let ticks = stalled_ticks(&mut stream, stall, &mut ctx);
+    assert!(ticks >= 4, "Burst yielded {} ticks", ticks);
+    assert!(ticks <= 6, "Burst yielded {} ticks", ticks);
+    drop(stream);
+
+    let mut stream = Timer::interval_stream(interval, MissedTickBehavior::Skip);
+    assert_eq!(stalled_ticks(&mut stream, stall, &mut ctx), 1);
+    drop(stream);
+
+    let mut stream = Timer::interval_stream(interval, MissedTickBehavior::Delay);
+    assert_eq!(stalled_ticks(&mut stream, stall, &mut ctx), 1);
+    //Schedule restarts from delayed tick, instead of keeping original one.
+    let restarted = std::time::Instant::now();
+    let ticks = futures_executor::block_on_stream(stream).take(1).count();
+    assert_eq!(ticks, 1);
+    assert!(restarted.elapsed() >= time::Duration::from_millis(90), "Delay ticked {:?} after stall", restarted.elapsed());
+}
@@ -0,0 +1,43 @@
+//! Closure accounting is global, hence this test lives in its own binary.
+#![cfg(all(debug_assertions, feature = "alloc"))]
+
+use os_timer::{Callback, Timer};
+
+use core::time;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn closures_are_freed_on_drop() {
+    assert_eq!(os_timer::leaked_closure_count(), 0);
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut timers = Vec::new();
+    for _ in 0..5 {
+        let count = count.clone();
+        let timer = Timer::new(Callback::closure(move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        })).expect("To create timer");
+        timers.push(timer);
+    }
+    assert_eq!(os_timer::leaked_closure_count(), 5);
+
+    //Closure of timer, that failed to initialize, is freed right away.
+    let uninit = unsafe {
+        Timer::uninit()
+    };
+    assert!(uninit.init(Callback::closure(|| {})));
+    assert!(!uninit.init(Callback::closure(|| {})));
+    assert_eq!(os_timer::leaked_closure_count(), 6);
+    drop(uninit);
+
+    for timer in timers.iter() {
+        timer.schedule_once(time::Duration::from_millis(1));
+    }
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(count.load(Ordering::Acquire), 5);
+
+    drop(timers);
+    assert_eq!(os_timer::leaked_closure_count(), 0);
+    assert_eq!(Arc::strong_count(&count), 1);
+}
@@ -1,8 +1,9 @@
-use os_timer::{Callback, Timer};
+use os_timer::{Callback, Timer, TimerState};
 
 use core::time;
-use core::sync::atomic::{AtomicU8, Ordering};
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
 
+#[cfg(feature = "alloc")]
 #[test]
 fn timer_schedule_once() {
     static COUNT: AtomicU8 = AtomicU8::new(0);
@@ -65,3 +66,1072 @@ fn timer_schedule_interval() {
     std::thread::sleep(time::Duration::from_millis(1100));
     assert_eq!(COUNT.load(Ordering::Acquire), 5);
 }
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_fire_sync() {
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    let cb = || {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+    };
+
+    let timer = Timer::new(Callback::closure(cb)).expect("To create timer");
+    unsafe {
+        timer.fire_sync();
+    }
+    assert_eq!(COUNT.load(Ordering::Acquire), 1);
+    unsafe {
+        timer.fire_sync();
+    }
+    assert_eq!(COUNT.load(Ordering::Acquire), 2);
+    assert!(!timer.is_scheduled());
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    unsafe {
+        timer.fire_sync();
+    }
+    assert_eq!(COUNT.load(Ordering::Acquire), 2);
+}
+
+#[test]
+fn timer_state_fired() {
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert_eq!(timer.state(), TimerState::Idle);
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    assert_eq!(timer.state(), TimerState::Idle);
+    timer.schedule_once(time::Duration::from_millis(50));
+    assert_eq!(timer.state(), TimerState::Armed);
+
+    std::thread::sleep(time::Duration::from_millis(500));
+    assert_eq!(timer.state(), TimerState::Fired);
+
+    timer.cancel();
+    assert_eq!(timer.state(), TimerState::Fired);
+}
+
+#[test]
+fn timer_state_cancelled() {
+    fn cb() {
+    }
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    timer.schedule_once(time::Duration::from_secs(5));
+    assert_eq!(timer.state(), TimerState::Armed);
+    timer.cancel();
+    assert_eq!(timer.state(), TimerState::Cancelled);
+
+    timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(10));
+    std::thread::sleep(time::Duration::from_millis(100));
+    assert_eq!(timer.state(), TimerState::Armed);
+    timer.cancel();
+    assert_eq!(timer.state(), TimerState::Cancelled);
+}
+
+#[test]
+fn timer_new_with_retry() {
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    fn cb() {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let timer = Timer::new_with_retry(Callback::plain(cb), 3).expect("To create timer");
+    timer.schedule_once(time::Duration::from_millis(10));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(COUNT.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn timer_schedule_hz() {
+    fn cb() {
+    }
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+
+    assert!(!timer.schedule_hz(time::Duration::from_secs(1), 0.0));
+    assert!(!timer.schedule_hz(time::Duration::from_secs(1), -1.0));
+    assert!(!timer.schedule_hz(time::Duration::from_secs(1), f64::NAN));
+    assert!(!timer.is_scheduled());
+
+    assert!(timer.schedule_hz(time::Duration::from_secs(1), 60.0));
+    assert!(timer.is_scheduled());
+    timer.cancel();
+
+    assert!(timer.schedule_hz(time::Duration::from_secs(1), 1.0));
+    assert!(timer.is_scheduled());
+    timer.cancel();
+    assert!(!timer.is_scheduled());
+}
+
+#[test]
+fn timer_cancel_never_scheduled() {
+    fn cb() {
+    }
+
+    let uninit = unsafe {
+        Timer::uninit()
+    };
+    uninit.cancel();
+    assert_eq!(uninit.state(), TimerState::Idle);
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    let before = std::time::Instant::now();
+    for _ in 0..100 {
+        timer.cancel();
+    }
+    assert!(before.elapsed() < time::Duration::from_millis(100), "Cancel of idle timer took {:?}", before.elapsed());
+    assert_eq!(timer.state(), TimerState::Idle);
+    assert!(!timer.is_scheduled());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_new_without_callback_wrapper() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    fn cb() {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let closure_timer = Timer::new(|| {
+        COUNT.fetch_add(10, Ordering::AcqRel);
+    }).expect("To create timer");
+    let fn_timer = Timer::new(cb).expect("To create timer");
+    let uninit_timer = unsafe {
+        Timer::uninit()
+    };
+    assert!(uninit_timer.init(cb));
+
+    unsafe {
+        closure_timer.fire_sync();
+        fn_timer.fire_sync();
+        uninit_timer.fire_sync();
+    }
+    assert_eq!(COUNT.load(Ordering::Acquire), 12);
+
+    fn_timer.schedule_once(time::Duration::from_millis(10));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(COUNT.load(Ordering::Acquire), 13);
+}
+
+#[test]
+fn timer_schedule_interval_strict() {
+    use os_timer::TimerError;
+
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert_eq!(timer.schedule_interval_strict(time::Duration::from_millis(10), time::Duration::from_secs(0)), Err(TimerError::Uninitialized));
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    assert_eq!(timer.schedule_interval_strict(time::Duration::from_secs(1), time::Duration::from_secs(1)), Ok(()));
+    assert_eq!(timer.schedule_interval_strict(time::Duration::from_millis(10), time::Duration::from_secs(0)), Err(TimerError::Pending));
+    //Pending schedule is kept.
+    assert!(timer.is_scheduled());
+    assert_eq!(timer.state(), TimerState::Armed);
+
+    timer.cancel();
+    assert_eq!(timer.schedule_interval_strict(time::Duration::from_millis(10), time::Duration::from_secs(0)), Ok(()));
+    std::thread::sleep(time::Duration::from_millis(200));
+    //Expired one shot timer can be scheduled again.
+    assert_eq!(timer.state(), TimerState::Fired);
+    assert_eq!(timer.schedule_interval_strict(time::Duration::from_secs(1), time::Duration::from_secs(0)), Ok(()));
+    assert_eq!(timer.schedule_interval_strict(time::Duration::from_secs(1), time::Duration::from_secs(0)), Err(TimerError::Pending));
+    timer.cancel();
+}
+
+#[test]
+fn timer_try_init() {
+    use os_timer::TimerError;
+
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert_eq!(timer.try_init(Callback::plain(cb)), Ok(()));
+    assert!(timer.is_init());
+    assert_eq!(timer.try_init(Callback::plain(cb)), Err(TimerError::AlreadyInitialized));
+
+    let timer = Timer::try_new(Callback::plain(cb)).expect("To create timer");
+    assert!(timer.is_init());
+    assert_eq!(timer.try_init(Callback::plain(cb)), Err(TimerError::AlreadyInitialized));
+}
+
+#[test]
+fn timer_builder() {
+    use os_timer::{Clock, QosClass, TimerBuilder, TimerError};
+
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    fn cb() {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+    }
+
+    assert_eq!(TimerBuilder::new().clock(Clock::Realtime).build().err(), Some(TimerError::Uninitialized));
+
+    //Options, that are not applicable to the platform, are ignored.
+    let timer = TimerBuilder::new().callback(Callback::plain(cb))
+                                   .clock(Clock::Realtime)
+                                   .qos(QosClass::Utility)
+                                   .leeway(time::Duration::from_millis(10))
+                                   .build()
+                                   .expect("To create timer");
+    assert!(timer.is_init());
+    assert!(timer.schedule_once(time::Duration::from_millis(50)));
+    std::thread::sleep(time::Duration::from_millis(500));
+    assert_eq!(COUNT.load(Ordering::Acquire), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_moved_to_thread() {
+    use std::sync::Arc;
+
+    fn assert_send_sync<T: Send + Sync>() {
+    }
+    assert_send_sync::<Timer>();
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let timer = {
+        let count = count.clone();
+        Timer::new(move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        }).expect("To create timer")
+    };
+
+    let timer = std::thread::spawn(move || {
+        assert!(timer.schedule_once(time::Duration::from_millis(10)));
+        timer
+    }).join().expect("Thread to finish without panic");
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+    assert_eq!(timer.state(), TimerState::Fired);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_once_callback() {
+    use std::sync::Arc;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let cb = {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        }
+    };
+
+    let timer = Timer::new(Callback::once(cb)).expect("To create timer");
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    timer.cancel();
+
+    assert_eq!(count.load(Ordering::Acquire), 1);
+    //Closure is consumed by its run.
+    assert_eq!(Arc::strong_count(&count), 1);
+
+    unsafe {
+        timer.fire_sync();
+    }
+    assert_eq!(count.load(Ordering::Acquire), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_context_callback() {
+    use std::sync::Arc;
+
+    fn cb(count: &Arc<AtomicUsize>) {
+        count.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let timer = Timer::new(Callback::with_context(cb, count.clone())).expect("To create timer");
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+
+    unsafe {
+        timer.fire_sync();
+    }
+    assert_eq!(count.load(Ordering::Acquire), 2);
+
+    drop(timer);
+    //Context is freed with timer
+    assert_eq!(Arc::strong_count(&count), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_drop_frees_pending_closure() {
+    use std::sync::Arc;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let timer = {
+        let count = count.clone();
+        Timer::new(Callback::closure(move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        })).expect("To create timer")
+    };
+    let counted = {
+        let count = count.clone();
+        Timer::new(Callback::with_count(move |expirations| {
+            count.fetch_add(expirations as usize, Ordering::AcqRel);
+        })).expect("To create timer")
+    };
+    assert_eq!(Arc::strong_count(&count), 3);
+
+    //Timers are dropped while still pending.
+    assert!(timer.schedule_interval(time::Duration::from_millis(5), time::Duration::from_millis(5)));
+    assert!(counted.schedule_interval(time::Duration::from_millis(5), time::Duration::from_millis(5)));
+    std::thread::sleep(time::Duration::from_millis(50));
+
+    drop(timer);
+    assert_eq!(Arc::strong_count(&count), 2);
+    drop(counted);
+    assert_eq!(Arc::strong_count(&count), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_self_rescheduling_callback() {
+    static COUNT: AtomicU8 = AtomicU8::new(0);
+
+    let cb = |timer: &Timer| {
+        //Initial alarm, followed by 3 re-schedules with growing delay.
+        let count = COUNT.fetch_add(1, Ordering::AcqRel) + 1;
+        if count < 4 {
+            assert!(timer.schedule_once(time::Duration::from_millis(10 * u64::from(count))));
+        }
+    };
+
+    let timer = Timer::new(Callback::with_timer(cb)).expect("To create timer");
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(500));
+    assert_eq!(COUNT.load(Ordering::Acquire), 4);
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(COUNT.load(Ordering::Acquire), 4);
+
+    //Timer, that keeps re-scheduling itself, can be dropped at any moment.
+    let timer = Timer::new(Callback::with_timer(|timer: &Timer| {
+        timer.schedule_once(time::Duration::from_micros(100));
+    })).expect("To create timer");
+    assert!(timer.schedule_once(time::Duration::from_micros(100)));
+    std::thread::sleep(time::Duration::from_millis(50));
+    drop(timer);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_set_callback() {
+    use os_timer::BoxedCallback;
+    use std::sync::Arc;
+
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert!(!timer.set_callback(Callback::plain(cb)));
+
+    let first = Arc::new(AtomicUsize::new(0));
+    let second = Arc::new(AtomicUsize::new(0));
+
+    let timer = {
+        let first = first.clone();
+        Timer::new(Callback::closure(move || {
+            first.fetch_add(1, Ordering::AcqRel);
+        })).expect("To create timer")
+    };
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(first.load(Ordering::Acquire), 1);
+
+    assert!(timer.schedule_once(time::Duration::from_millis(100)));
+    let callback = {
+        let second = second.clone();
+        Callback::with_count(move |count| {
+            second.fetch_add(count as usize, Ordering::AcqRel);
+        })
+    };
+    assert!(timer.set_callback(callback));
+    //Pending schedule is kept, while previous closure is freed.
+    assert!(timer.is_scheduled());
+    assert_eq!(Arc::strong_count(&first), 1);
+
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(first.load(Ordering::Acquire), 1);
+    assert_eq!(second.load(Ordering::Acquire), 1);
+
+    //Callback can be replaced through shared reference, while timer is used by other threads.
+    let timer = Arc::new(timer);
+    let third = Arc::new(AtomicUsize::new(0));
+    let replacer = {
+        let timer = timer.clone();
+        let third = third.clone();
+        std::thread::spawn(move || {
+            let cb: BoxedCallback = Box::new(move || {
+                third.fetch_add(1, Ordering::AcqRel);
+            });
+            assert!(timer.set_callback(Callback::boxed(cb)));
+        })
+    };
+    replacer.join().expect("To replace callback");
+    assert_eq!(Arc::strong_count(&second), 1);
+
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(second.load(Ordering::Acquire), 1);
+    assert_eq!(third.load(Ordering::Acquire), 1);
+
+    drop(timer);
+    assert_eq!(Arc::strong_count(&third), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_reinit_after_uninit() {
+    use std::sync::Arc;
+
+    let mut timer = unsafe {
+        Timer::uninit()
+    };
+    let first = Arc::new(AtomicUsize::new(0));
+    let second = Arc::new(AtomicUsize::new(0));
+
+    {
+        let first = first.clone();
+        assert!(timer.init(Callback::closure(move || {
+            first.fetch_add(1, Ordering::AcqRel);
+        })));
+    }
+    assert!(timer.schedule_interval(time::Duration::from_secs(1), time::Duration::from_secs(1)));
+
+    timer = unsafe {
+        Timer::uninit()
+    };
+    assert!(!timer.is_init());
+    assert!(!timer.is_scheduled());
+    assert_eq!(timer.state(), TimerState::Idle);
+    //Closure is freed together with OS timer.
+    assert_eq!(Arc::strong_count(&first), 1);
+
+    {
+        let second = second.clone();
+        assert!(timer.init(Callback::closure(move || {
+            second.fetch_add(1, Ordering::AcqRel);
+        })));
+    }
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(first.load(Ordering::Acquire), 0);
+    assert_eq!(second.load(Ordering::Acquire), 1);
+
+    drop(timer);
+    assert_eq!(Arc::strong_count(&second), 1);
+}
+
+#[test]
+fn timer_duration_overflow() {
+    use os_timer::TimerError;
+
+    fn cb() {
+    }
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    let huge = time::Duration::from_secs(u64::MAX);
+
+    assert_eq!(timer.try_schedule_once(huge), Err(TimerError::Overflow));
+    assert_eq!(timer.try_schedule_interval(huge, time::Duration::from_secs(1)), Err(TimerError::Overflow));
+    assert_eq!(timer.try_schedule_interval(time::Duration::from_secs(1), huge), Err(TimerError::Overflow));
+    assert!(!timer.is_scheduled());
+    assert_eq!(timer.state(), TimerState::Idle);
+
+    //Windows interval is `u32` milliseconds, which is about 49 days.
+    #[cfg(all(windows, not(feature = "software")))]
+    assert_eq!(timer.try_schedule_interval(time::Duration::from_secs(1), time::Duration::from_secs(50 * 24 * 60 * 60)), Err(TimerError::Overflow));
+
+    //Lossy methods truncate duration instead.
+    assert!(timer.schedule_once(huge));
+    assert!(timer.is_scheduled());
+    timer.cancel();
+    assert!(timer.schedule_interval(time::Duration::from_secs(1), huge));
+    timer.cancel();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn timer_time_remaining() {
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert_eq!(timer.time_remaining(), None);
+
+    let timer = Timer::new(cb).expect("To create timer");
+    assert_eq!(timer.time_remaining(), None);
+
+    assert!(timer.schedule_once(time::Duration::from_secs(10)));
+    let remaining = timer.time_remaining().expect("To have remaining time");
+    assert!(remaining > time::Duration::from_secs(9));
+    assert!(remaining <= time::Duration::from_secs(10));
+
+    //Periodic timer reports time until its next alarm, rather than initial timeout.
+    assert!(timer.schedule_interval(time::Duration::from_millis(1), time::Duration::from_secs(2)));
+    std::thread::sleep(time::Duration::from_millis(100));
+    let remaining = timer.time_remaining().expect("To have remaining time");
+    assert!(remaining > time::Duration::from_secs(1));
+    assert!(remaining < time::Duration::from_secs(2));
+
+    timer.cancel();
+    assert_eq!(timer.time_remaining(), None);
+}
+
+#[test]
+fn timer_scheduled_interval() {
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert_eq!(timer.scheduled_interval(), None);
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    assert_eq!(timer.scheduled_interval(), None);
+
+    assert!(timer.schedule_interval(time::Duration::from_secs(10), time::Duration::from_secs(2)));
+    assert_eq!(timer.scheduled_interval(), Some(time::Duration::from_secs(2)));
+
+    assert!(timer.schedule_interval(time::Duration::from_secs(10), time::Duration::from_millis(250)));
+    assert_eq!(timer.scheduled_interval(), Some(time::Duration::from_millis(250)));
+
+    //One shot timer has no interval.
+    assert!(timer.schedule_once(time::Duration::from_secs(10)));
+    assert_eq!(timer.scheduled_interval(), None);
+
+    assert!(timer.schedule_interval(time::Duration::from_secs(10), time::Duration::from_secs(1)));
+    timer.cancel();
+    assert_eq!(timer.scheduled_interval(), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_schedule_ms_us() {
+    use std::sync::Arc;
+
+    fn cb() {
+    }
+
+    let timer = Timer::new(cb).expect("To create timer");
+
+    assert!(timer.schedule_interval_ms(10_000, 250));
+    assert!(timer.is_scheduled());
+    assert_eq!(timer.scheduled_interval(), Some(time::Duration::from_millis(250)));
+
+    assert!(timer.schedule_interval_us(10_000_000, 2_000));
+    assert_eq!(timer.scheduled_interval(), Some(time::Duration::from_micros(2_000)));
+
+    assert!(timer.schedule_once_ms(10_000));
+    assert!(timer.is_scheduled());
+    assert_eq!(timer.scheduled_interval(), None);
+
+    assert!(timer.schedule_once_us(10_000_000));
+    assert!(timer.is_scheduled());
+    assert_eq!(timer.scheduled_interval(), None);
+    timer.cancel();
+
+    let us_count = Arc::new(AtomicUsize::new(0));
+    let duration_count = Arc::new(AtomicUsize::new(0));
+    let us_timer = {
+        let count = us_count.clone();
+        Timer::new(Callback::closure(move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        })).expect("To create timer")
+    };
+    let duration_timer = {
+        let count = duration_count.clone();
+        Timer::new(Callback::closure(move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        })).expect("To create timer")
+    };
+
+    assert!(us_timer.schedule_once_us(50_000));
+    assert!(duration_timer.schedule_once(time::Duration::from_micros(50_000)));
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(us_count.load(Ordering::Acquire), 1);
+    assert_eq!(duration_count.load(Ordering::Acquire), 1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn timer_last_armed_at() {
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert_eq!(timer.last_armed_at(), None);
+
+    let timer = Timer::new(cb).expect("To create timer");
+    assert_eq!(timer.last_armed_at(), None);
+
+    let before = std::time::Instant::now();
+    assert!(timer.schedule_interval(time::Duration::from_secs(10), time::Duration::from_secs(1)));
+    let after = std::time::Instant::now();
+    let armed_at = timer.last_armed_at().expect("To be armed");
+    assert!(armed_at >= before);
+    assert!(armed_at <= after);
+
+    //Cancel doesn't forget the most recent schedule.
+    timer.cancel();
+    assert_eq!(timer.last_armed_at(), Some(armed_at));
+
+    std::thread::sleep(time::Duration::from_millis(10));
+    assert!(timer.schedule_once(time::Duration::from_secs(10)));
+    assert!(timer.last_armed_at().expect("To be armed") > armed_at);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn timer_wait() {
+    use std::sync::Arc;
+
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert!(!timer.wait());
+
+    let timer = Arc::new(Timer::new(cb).expect("To create timer"));
+    //Not scheduled timer doesn't block.
+    assert!(!timer.wait());
+
+    let start = std::time::Instant::now();
+    assert!(timer.schedule_once(time::Duration::from_millis(50)));
+    assert!(timer.wait());
+    assert!(start.elapsed() >= time::Duration::from_millis(50));
+    assert_eq!(timer.fire_count(), 1);
+    //Expired one-shot timer is no longer scheduled.
+    assert!(!timer.wait());
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(10)));
+    assert!(timer.wait());
+    assert!(timer.wait());
+    timer.cancel();
+    assert!(!timer.wait());
+
+    //Cancellation from another thread wakes up waiter.
+    assert!(timer.schedule_once(time::Duration::from_secs(10)));
+    let canceller = {
+        let timer = timer.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(time::Duration::from_millis(50));
+            timer.cancel();
+        })
+    };
+    assert!(!timer.wait());
+    assert!(start.elapsed() < time::Duration::from_secs(10));
+    canceller.join().expect("Thread to finish without panic");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn timer_after() {
+    use std::sync::Arc;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let cb = {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        }
+    };
+    os_timer::after(time::Duration::from_millis(10), cb).expect("To arm timer").detach();
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+
+    //Dropping handle cancels timer.
+    let cb = {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::AcqRel);
+        }
+    };
+    let handle = os_timer::after(time::Duration::from_millis(50), cb).expect("To arm timer");
+    drop(handle);
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 1);
+
+    //Fired timer is released on its own.
+    assert_eq!(Arc::strong_count(&count), 1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn timer_after_from_callback() {
+    use std::sync::Arc;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    //Callback arms another timer, while its own one is still alive.
+    let cb = {
+        let count = count.clone();
+        move || {
+            count.fetch_add(1, Ordering::AcqRel);
+            os_timer::after(time::Duration::from_millis(10), move || {
+                count.fetch_add(1, Ordering::AcqRel);
+            }).expect("To arm timer").detach();
+        }
+    };
+    os_timer::after(time::Duration::from_millis(10), cb).expect("To arm timer").detach();
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(count.load(Ordering::Acquire), 2);
+    assert_eq!(Arc::strong_count(&count), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_repeating_callback() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let cb = || COUNT.fetch_add(1, Ordering::AcqRel) + 1 < 3;
+    let timer = Timer::new(Callback::repeating(cb)).expect("To create timer");
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(20)));
+    std::thread::sleep(time::Duration::from_millis(300));
+
+    assert_eq!(COUNT.load(Ordering::Acquire), 3);
+    assert_eq!(timer.state(), TimerState::Cancelled);
+    assert!(!timer.is_scheduled());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_debug() {
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    let debug = format!("{:?}", timer);
+    assert!(debug.starts_with("Timer { init: false, scheduled: false"), "{}", debug);
+
+    let timer = Timer::new(cb).expect("To create timer");
+    assert!(timer.schedule_once(time::Duration::from_secs(10)));
+    let debug = format!("{:?}", timer);
+    assert!(debug.starts_with("Timer { init: true, scheduled: true"), "{}", debug);
+    timer.cancel();
+
+    assert_eq!(format!("{:?}", Callback::plain(cb)), "Callback { variant: Trivial }");
+    assert_eq!(format!("{:?}", Callback::closure(|| ())), "Callback { variant: Boxed }");
+    assert_eq!(format!("{:?}", Callback::with_count(|_| ())), "Callback { variant: Counted }");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_reschedule_interval_from_callback() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    //Re-scheduling doesn't wait for the callback, it is called from, hence it returns on every platform.
+    let timer = Timer::new(Callback::with_timer(|timer: &Timer| {
+        if COUNT.fetch_add(1, Ordering::AcqRel) == 0 {
+            assert!(timer.reschedule_interval(time::Duration::from_millis(20), time::Duration::from_secs(0)));
+        }
+    })).expect("To create timer");
+
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_secs(10)));
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(COUNT.load(Ordering::Acquire), 2);
+    assert_eq!(timer.state(), TimerState::Fired);
+}
+
+#[test]
+fn timer_arm_scoped() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    fn cb() {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let timer = Timer::new(Callback::plain(cb)).expect("To create timer");
+    {
+        let guard = timer.arm_scoped(time::Duration::from_secs(10), time::Duration::from_secs(0)).expect("To arm timer");
+        assert!(guard.timer().is_scheduled());
+    }
+    assert!(!timer.is_scheduled());
+    assert_eq!(timer.state(), TimerState::Cancelled);
+
+    //Timer can be armed again, once guard is gone.
+    {
+        let _guard = timer.arm_scoped(time::Duration::from_millis(10), time::Duration::from_secs(0)).expect("To arm timer");
+        std::thread::sleep(time::Duration::from_millis(200));
+    }
+    assert_eq!(COUNT.load(Ordering::Acquire), 1);
+    assert!(!timer.is_scheduled());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_arm_scoped_drop_in_callback() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    //Guard doesn't wait for the callback, it is dropped in, hence it returns on every platform.
+    let timer = Timer::new(Callback::with_timer(|timer: &Timer| {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+        let guard = timer.arm_scoped(time::Duration::from_millis(10), time::Duration::from_millis(10)).expect("To arm timer");
+        drop(guard);
+    })).expect("To create timer");
+
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(300));
+    assert_eq!(COUNT.load(Ordering::Acquire), 1);
+    assert_eq!(timer.state(), TimerState::Cancelled);
+}
+
+#[cfg(any(windows, target_os = "macos", target_os = "ios"))]
+#[test]
+fn timer_builder_cpu_time_unsupported() {
+    use os_timer::{Clock, TimerBuilder, TimerError};
+
+    fn cb() {
+    }
+
+    assert_eq!(TimerBuilder::new().callback(Callback::plain(cb)).clock(Clock::ProcessCpuTime).build().err(), Some(TimerError::Unsupported));
+    assert_eq!(TimerBuilder::new().callback(Callback::plain(cb)).clock(Clock::ThreadCpuTime).build().err(), Some(TimerError::Unsupported));
+    assert!(TimerBuilder::new().callback(Callback::plain(cb)).clock(Clock::Realtime).build().is_ok());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_is_running() {
+    use core::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert!(!timer.is_running());
+
+    let entered = Arc::new(AtomicBool::new(false));
+    let cb = {
+        let entered = entered.clone();
+        move || {
+            entered.store(true, Ordering::Release);
+            std::thread::sleep(time::Duration::from_millis(300));
+        }
+    };
+    let timer = Arc::new(Timer::new(Callback::closure(cb)).expect("To create timer"));
+    assert!(!timer.is_running());
+
+    assert!(timer.schedule_once(time::Duration::from_millis(1)));
+    let start = std::time::Instant::now();
+    while !entered.load(Ordering::Acquire) {
+        assert!(start.elapsed() < time::Duration::from_secs(5), "Callback is not invoked");
+        std::thread::yield_now();
+    }
+
+    let observer = {
+        let timer = timer.clone();
+        std::thread::spawn(move || timer.is_running())
+    };
+    assert!(observer.join().expect("Thread to finish without panic"));
+
+    std::thread::sleep(time::Duration::from_millis(500));
+    assert!(!timer.is_running());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timer_arc_callback() {
+    use std::sync::Arc;
+
+    fn cb(count: &AtomicUsize) {
+        count.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let first = Timer::new(Callback::arc(count.clone(), cb)).expect("To create timer");
+    let second = Timer::new(Callback::arc(count.clone(), cb)).expect("To create timer");
+    assert_eq!(Arc::strong_count(&count), 3);
+
+    assert!(first.schedule_once(time::Duration::from_millis(10)));
+    assert!(second.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(count.load(Ordering::Acquire), 2);
+
+    //State is released together with timer.
+    drop(first);
+    drop(second);
+    assert_eq!(Arc::strong_count(&count), 1);
+}
+
+#[test]
+fn timer_uninit_is_inert() {
+    let timer = unsafe {
+        Timer::uninit()
+    };
+
+    assert!(!timer.schedule_once(time::Duration::from_millis(1)));
+    assert_eq!(timer.try_schedule_once(time::Duration::from_millis(1)), Err(os_timer::TimerError::Uninitialized));
+    assert!(!timer.schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(1)));
+    assert_eq!(timer.try_schedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(1)), Err(os_timer::TimerError::Uninitialized));
+    assert!(!timer.schedule_interval_with_leeway(time::Duration::from_millis(1), time::Duration::from_millis(1), time::Duration::from_millis(1)));
+    assert!(!timer.schedule_once_with_leeway(time::Duration::from_millis(1), time::Duration::from_millis(1)));
+    assert!(!timer.schedule_interval_immediate(time::Duration::from_millis(1)));
+    assert!(!timer.schedule_count(time::Duration::from_millis(1), 3));
+    assert!(!timer.reschedule_interval(time::Duration::from_millis(1), time::Duration::from_millis(1)));
+    assert!(!timer.schedule().initial(time::Duration::from_millis(1)).interval(time::Duration::from_millis(1)).schedule());
+    assert!(!timer.schedule_once_at_raw(timer.clock_after(time::Duration::from_millis(1))));
+    assert!(timer.arm_scoped(time::Duration::from_millis(1), time::Duration::from_secs(0)).is_none());
+    #[cfg(feature = "std")]
+    {
+        assert!(!timer.schedule_at(std::time::Instant::now()));
+        assert!(!timer.schedule_interval_until(time::Duration::from_millis(1), std::time::Instant::now() + time::Duration::from_secs(1)));
+    }
+
+    assert!(!timer.is_scheduled());
+    assert!(!timer.is_running());
+    assert_eq!(timer.time_remaining(), None);
+    assert_eq!(timer.scheduled_interval(), None);
+    assert_eq!(timer.fire_count(), 0);
+    assert_eq!(timer.state(), TimerState::Idle);
+    timer.cancel();
+
+    std::thread::sleep(time::Duration::from_millis(50));
+    assert!(!timer.is_init());
+}
+
+#[test]
+fn timer_inline_callback() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    static OTHER: AtomicUsize = AtomicUsize::new(0);
+
+    //Zero sized closure.
+    let timer = Timer::new(Callback::inline(|| {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+    })).expect("To create timer");
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+
+    //Pointer sized closure.
+    let count: &'static AtomicUsize = &OTHER;
+    let other = Timer::new(Callback::inline(move || {
+        count.fetch_add(2, Ordering::AcqRel);
+    })).expect("To create timer");
+    assert!(other.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(10)));
+
+    std::thread::sleep(time::Duration::from_millis(200));
+    other.cancel();
+    assert_eq!(COUNT.load(Ordering::Acquire), 1);
+    let fired = OTHER.load(Ordering::Acquire);
+    assert!(fired >= 4);
+    assert_eq!(fired % 2, 0);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn timer_deadline() {
+    fn cb() {
+    }
+
+    let timer = unsafe {
+        Timer::uninit()
+    };
+    assert_eq!(timer.deadline(), None);
+
+    let timer = Timer::new(cb).expect("To create timer");
+    assert_eq!(timer.deadline(), None);
+
+    let before = std::time::Instant::now();
+    assert!(timer.schedule_once(time::Duration::from_secs(10)));
+    let after = std::time::Instant::now();
+    let deadline = timer.deadline().expect("To have deadline");
+    assert!(deadline >= before + time::Duration::from_secs(10));
+    assert!(deadline <= after + time::Duration::from_secs(10));
+
+    //Periodic timer advances its deadline by interval.
+    assert!(timer.schedule_interval(time::Duration::from_millis(10), time::Duration::from_millis(100)));
+    std::thread::sleep(time::Duration::from_millis(250));
+    let now = std::time::Instant::now();
+    let deadline = timer.deadline().expect("To have deadline");
+    assert!(deadline > now);
+    assert!(deadline <= now + time::Duration::from_millis(100));
+
+    timer.cancel();
+    assert_eq!(timer.deadline(), None);
+
+    //One shot timer has no deadline once fired.
+    assert!(timer.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(timer.deadline(), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn timer_instant_callback() {
+    use std::time::Instant;
+    use std::sync::{Arc, Mutex};
+
+    let fires = Arc::new(Mutex::new(Vec::new()));
+    let cb = {
+        let fires = fires.clone();
+        move |now: Instant, deadline: Option<Instant>| fires.lock().unwrap().push((now, deadline))
+    };
+
+    let timer = Timer::new(Callback::with_instant(cb)).expect("To create timer");
+    assert_eq!(timer.last_deadline(), None);
+
+    let before = Instant::now();
+    assert!(timer.schedule_once(time::Duration::from_millis(50)));
+    let after = Instant::now();
+    std::thread::sleep(time::Duration::from_millis(300));
+
+    let fires = fires.lock().unwrap();
+    assert_eq!(fires.len(), 1);
+    let (now, deadline) = fires[0];
+    let deadline = deadline.expect("To have deadline");
+    assert!(deadline >= before + time::Duration::from_millis(50));
+    assert!(deadline <= after + time::Duration::from_millis(50));
+    assert!(now >= deadline);
+    assert!(now < before + time::Duration::from_millis(250));
+    assert_eq!(timer.last_deadline(), Some(deadline));
+}
+
+#[test]
+fn timer_const_callback() {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    fn cb() {
+        COUNT.fetch_add(1, Ordering::AcqRel);
+    }
+
+    const CB: Callback = Callback::plain(cb);
+
+    let first = Timer::new(CB).expect("To create timer");
+    let second = Timer::new(CB).expect("To create timer");
+    assert!(first.schedule_once(time::Duration::from_millis(10)));
+    assert!(second.schedule_once(time::Duration::from_millis(10)));
+    std::thread::sleep(time::Duration::from_millis(200));
+    assert_eq!(COUNT.load(Ordering::Acquire), 2);
+}